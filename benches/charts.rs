@@ -0,0 +1,124 @@
+//! The per-sample and per-frame work of a chart: `cargo bench`. Everything
+//! runs on synthetic samples, so no sensors or display are needed.
+
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use monty::bench::{self, Series};
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+/// `ms` milliseconds into the run.
+fn at(ms: i64) -> Duration {
+    Duration::from_millis(ms as u64)
+}
+
+/// A sawtooth with some noise, so the trace isn't a flat line.
+fn value(index: usize) -> i32 {
+    (index % 100) as i32 + (index * 7919 % 13) as i32
+}
+
+/// `points` samples 100 ms apart, with a window that keeps all of them.
+fn filled(points: usize) -> Series {
+    let mut series = Series::new(Duration::from_millis(100 * points as u64));
+    for index in 0..points {
+        series.push(at(100 * index as i64), value(index));
+    }
+    series
+}
+
+fn push(c: &mut Criterion) {
+    let mut group = c.benchmark_group("push");
+    for points in SIZES {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(points),
+            &points,
+            |b, &points| {
+                // Each push drops the oldest sample, as a full window does.
+                let mut series = filled(points);
+                let mut index = points;
+                b.iter(|| {
+                    series.push(at(100 * index as i64), value(index));
+                    index += 1;
+                });
+            },
+        );
+    }
+    group.finish();
+}
+
+fn retention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("retention");
+    let points = 10_000;
+
+    // Samples arriving in a burst with one timestamp, e.g. a plugin that
+    // reports late: nothing can be trimmed.
+    group.bench_function("equal timestamps", |b| {
+        b.iter_batched(
+            || Series::new(Duration::from_secs(60)),
+            |mut series| {
+                for index in 0..points {
+                    series.push(at(0), value(index));
+                }
+                series
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    // The clock jumping forward, e.g. after a suspend: the whole buffer
+    // goes at once.
+    group.bench_function("clock jump", |b| {
+        b.iter_batched(
+            || filled(points),
+            |mut series| {
+                series.push(at(3_600_000 + 100 * points as i64), 0);
+                series
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    // Timestamps going back and forth, as NTP corrections do.
+    group.bench_function("jitter", |b| {
+        b.iter_batched(
+            || Series::new(Duration::from_secs(60)),
+            |mut series| {
+                for index in 0..points {
+                    let jitter = if index % 2 == 0 { 50 } else { -50 };
+                    series.push(at(100 * index as i64 + jitter), value(index));
+                }
+                series
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+fn stats(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stats");
+    for points in SIZES {
+        let values: Vec<f64> = (0..points).map(|index| value(index).into()).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(points), &values, |b, values| {
+            b.iter(|| bench::stats(black_box(values)));
+        });
+    }
+    group.finish();
+}
+
+fn draw(c: &mut Criterion) {
+    let mut group = c.benchmark_group("draw");
+    group.sample_size(20);
+    for points in SIZES {
+        let series = filled(points);
+        for (width, height) in [(400, 300), (1280, 720), (2560, 1440)] {
+            let mut buffer = vec![0; width as usize * height as usize * 3];
+            group.bench_function(format!("{} points at {}x{}", points, width, height), |b| {
+                b.iter(|| series.draw(&mut buffer, width, height));
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, push, retention, stats, draw);
+criterion_main!(benches);
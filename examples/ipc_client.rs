@@ -0,0 +1,27 @@
+//! Prints what monty serves on `--ipc-socket`, one JSON object per line:
+//!
+//! `cargo run --example ipc_client -- /run/user/1000/monty.sock [history]`
+//!
+//! With `history`, the charts' buffers come first.
+
+use std::{
+    env,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+};
+
+fn main() -> io::Result<()> {
+    let mut args = env::args().skip(1);
+    let Some(path) = args.next() else {
+        eprintln!("usage: ipc_client SOCKET [history]");
+        std::process::exit(2);
+    };
+    let mut stream = UnixStream::connect(path)?;
+    if args.next().as_deref() == Some("history") {
+        stream.write_all(b"{\"request\":\"history\"}\n")?;
+    }
+    for line in BufReader::new(stream).lines() {
+        println!("{}", line?);
+    }
+    Ok(())
+}
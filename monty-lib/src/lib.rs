@@ -0,0 +1,200 @@
+//! The interface between monty and the shared libraries it loads with
+//! `--plugin`. A plugin is a `cdylib` that depends on this crate and
+//! exports its constructor with [`export_plugin!`]:
+//!
+//! ```ignore
+//! use monty_lib::{export_plugin, MontyPlugin};
+//!
+//! struct Fans;
+//!
+//! impl MontyPlugin for Fans {
+//!     fn name(&self) -> &str {
+//!         "fans"
+//!     }
+//!     fn unit(&self) -> &str {
+//!         "RPM"
+//!     }
+//!     fn max_value(&self) -> i32 {
+//!         5000
+//!     }
+//!     fn collect(&mut self) -> i32 {
+//!         2400
+//!     }
+//! }
+//!
+//! export_plugin!(Fans);
+//! ```
+//!
+//! Trait objects have no stable ABI, so a plugin must be built with the
+//! same compiler and the same version of this crate as monty.
+
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+};
+
+/// A custom metric, charted like the built-in ones.
+pub trait MontyPlugin: Send {
+    /// Key of the chart in the config file and history snapshots, and its
+    /// label; must not clash with another metric.
+    fn name(&self) -> &str;
+    /// Appended to readings, e.g. `%` or `RPM`; empty for none.
+    fn unit(&self) -> &str;
+    /// Top of the y-axis until a reading raises it.
+    fn max_value(&self) -> i32;
+    /// Takes a reading, once per sampling interval of the chart.
+    fn collect(&mut self) -> i32;
+    /// Why the plugin stopped working, if it did. Set by [`Guarded`], as a
+    /// panic can't unwind out of the library into monty.
+    fn failure(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Signature of the constructor every plugin exports.
+pub type CreatePlugin = fn() -> Box<dyn MontyPlugin>;
+
+/// Name of the constructor every plugin exports.
+pub const CREATE_SYMBOL: &[u8] = b"monty_plugin_create";
+
+/// Exports `monty_plugin_create`, building the plugin with `$create` and
+/// catching its panics inside the library.
+#[macro_export]
+macro_rules! export_plugin {
+    ($create:expr) => {
+        #[no_mangle]
+        pub fn monty_plugin_create() -> Box<dyn $crate::MontyPlugin> {
+            Box::new($crate::Guarded::new(|| $create))
+        }
+    };
+}
+
+/// A plugin whose panics are caught where they happen, in the plugin's own
+/// copy of the standard library. After one it is never called again.
+pub struct Guarded<P> {
+    plugin: Option<P>,
+    name: String,
+    unit: String,
+    max_value: i32,
+    failure: Option<String>,
+}
+
+impl<P: MontyPlugin> Guarded<P> {
+    pub fn new(create: impl FnOnce() -> P) -> Self {
+        let described = catch(|| {
+            let plugin = create();
+            let description = (
+                plugin.name().to_string(),
+                plugin.unit().to_string(),
+                plugin.max_value(),
+            );
+            (plugin, description)
+        });
+        match described {
+            Ok((plugin, (name, unit, max_value))) => Self {
+                plugin: Some(plugin),
+                name,
+                unit,
+                max_value,
+                failure: None,
+            },
+            Err(message) => Self {
+                plugin: None,
+                name: String::new(),
+                unit: String::new(),
+                max_value: 0,
+                failure: Some(message),
+            },
+        }
+    }
+}
+
+impl<P: MontyPlugin> MontyPlugin for Guarded<P> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn unit(&self) -> &str {
+        &self.unit
+    }
+
+    fn max_value(&self) -> i32 {
+        self.max_value
+    }
+
+    fn collect(&mut self) -> i32 {
+        let Some(plugin) = self.plugin.as_mut().filter(|_| self.failure.is_none()) else {
+            return 0;
+        };
+        catch(|| plugin.collect()).unwrap_or_else(|message| {
+            self.failure = Some(message);
+            0
+        })
+    }
+
+    fn failure(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+}
+
+/// Runs `f`, returning the message of a panic instead of unwinding.
+fn catch<T>(f: impl FnOnce() -> T) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| panic_message(&*payload))
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "unknown panic".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Counter {
+        next: i32,
+    }
+
+    impl MontyPlugin for Counter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+        fn unit(&self) -> &str {
+            "ops"
+        }
+        fn max_value(&self) -> i32 {
+            10
+        }
+        fn collect(&mut self) -> i32 {
+            self.next += 1;
+            if self.next == 3 {
+                panic!("counter overflow at {}", self.next);
+            }
+            self.next
+        }
+    }
+
+    #[test]
+    fn a_panic_disables_the_plugin() {
+        let mut plugin = Guarded::new(|| Counter { next: 0 });
+        assert_eq!((plugin.name(), plugin.unit()), ("counter", "ops"));
+        assert_eq!([plugin.collect(), plugin.collect()], [1, 2]);
+        assert_eq!(plugin.failure(), None);
+        plugin.collect();
+        assert_eq!(plugin.failure(), Some("counter overflow at 3"));
+        plugin.collect();
+        assert_eq!(plugin.plugin.as_ref().unwrap().next, 3);
+    }
+
+    #[test]
+    fn a_panicking_constructor_is_reported() {
+        let plugin = Guarded::<Counter>::new(|| panic!("no fans"));
+        assert_eq!(plugin.failure(), Some("no fans"));
+        assert_eq!(plugin.name(), "");
+    }
+}
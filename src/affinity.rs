@@ -0,0 +1,244 @@
+use std::collections::HashMap;
+
+use iced::{
+    widget::canvas::{Cache, Frame, Geometry},
+    Element, Length, Size,
+};
+use plotters::{coord::Shift, prelude::*};
+use plotters_iced::{Chart, ChartWidget, DrawingBackend, Renderer};
+
+use crate::{
+    colors::{self, ChartPalette},
+    procpower::{self, ProcessTimes},
+    Message,
+};
+
+/// How many processes get a row.
+const SHOWN_PROCESSES: usize = 10;
+
+/// A process and the CPUs it may run on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pinning {
+    pub pid: u32,
+    pub name: String,
+    /// Whether the process may run on each CPU, by index.
+    pub allowed: Vec<bool>,
+}
+
+/// The busiest processes' CPU affinity from `sched_getaffinity`: one row
+/// per process, one column per CPU, filled where the process may run. A
+/// process pinned with `taskset` or confined by a cpuset shows up as a
+/// sparse row, to hold against the per-core usage in the heat map.
+pub struct CpuAffinityView {
+    cache: Cache,
+    cpus: usize,
+    /// Start time and CPU ticks of every process at the previous update.
+    last: HashMap<u32, (u64, u64)>,
+    rows: Vec<Pinning>,
+    palette: ChartPalette,
+}
+
+impl CpuAffinityView {
+    pub fn new(cpus: usize) -> Self {
+        Self {
+            cache: Cache::new(),
+            cpus,
+            last: HashMap::new(),
+            rows: Vec::new(),
+            palette: ChartPalette::default(),
+        }
+    }
+
+    /// Takes one sample of all processes; those that used the most CPU
+    /// time since the previous one get a row. The first sample only primes
+    /// the counters.
+    pub fn sample(&mut self) {
+        let current = procpower::read_processes();
+        self.rows = pinnings(&self.last, &current, self.cpus, allowed_cpus);
+        self.last = procpower::ticks_by_pid(current);
+        self.cache.clear();
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        self.cache.clear();
+    }
+
+    pub fn view(&self, height: f32) -> Element<'_, Message> {
+        ChartWidget::new(self)
+            .width(Length::Fill)
+            .height(Length::Fixed(height))
+            .into()
+    }
+}
+
+/// The busiest processes' pinnings, skipping those whose affinity
+/// `allowed` can't read, e.g. because they exited in between.
+fn pinnings(
+    last: &HashMap<u32, (u64, u64)>,
+    current: &[ProcessTimes],
+    cpus: usize,
+    allowed: impl Fn(u32, usize) -> Option<Vec<bool>>,
+) -> Vec<Pinning> {
+    procpower::busiest(last, current)
+        .into_iter()
+        .filter_map(|(process, _)| {
+            Some(Pinning {
+                pid: process.pid,
+                name: process.name.clone(),
+                allowed: allowed(process.pid, cpus)?,
+            })
+        })
+        .take(SHOWN_PROCESSES)
+        .collect()
+}
+
+/// Which of the first `cpus` CPUs `pid` may run on, up to the
+/// `CPU_SETSIZE` (1024) a `cpu_set_t` holds.
+fn allowed_cpus(pid: u32, cpus: usize) -> Option<Vec<bool>> {
+    // SAFETY: an all-zero cpu_set_t is the empty set, and the kernel
+    // writes at most the size it is given.
+    let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    let result = unsafe {
+        libc::sched_getaffinity(
+            pid as libc::pid_t,
+            std::mem::size_of::<libc::cpu_set_t>(),
+            &mut set,
+        )
+    };
+    // SAFETY: CPU_ISSET only reads the set. It doesn't ignore CPUs past
+    // its end but indexes past its last word and panics, so those are
+    // left out.
+    let cpus = cpus.min(libc::CPU_SETSIZE as usize);
+    (result == 0).then(|| {
+        (0..cpus)
+            .map(|cpu| unsafe { libc::CPU_ISSET(cpu, &set) })
+            .collect()
+    })
+}
+
+impl Chart<Message> for CpuAffinityView {
+    type State = ();
+
+    #[inline]
+    fn draw<R: Renderer, F: Fn(&mut Frame)>(
+        &self,
+        renderer: &R,
+        bounds: Size,
+        draw_fn: F,
+    ) -> Geometry {
+        renderer.draw_cache(&self.cache, bounds, draw_fn)
+    }
+
+    fn draw_chart<DB: DrawingBackend>(&self, state: &Self::State, root: DrawingArea<DB, Shift>) {
+        self.build_chart(state, ChartBuilder::on(&root));
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut chart: ChartBuilder<DB>) {
+        let cpus = self.cpus as i32;
+        let rows = self.rows.len().max(1) as i32;
+
+        let mut chart = chart
+            .x_label_area_size(30)
+            .y_label_area_size(160)
+            .margin(20)
+            .build_cartesian_2d((0..cpus).into_segmented(), (0..rows).into_segmented())
+            .expect("failed to build chart");
+
+        let label_style = ("sans-serif", 13).into_font().color(&self.palette.label());
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .axis_style(ShapeStyle::from(self.palette.axis()).stroke_width(1))
+            .x_labels(self.cpus)
+            .x_label_style(label_style.clone())
+            .x_label_formatter(&|cpu| match cpu {
+                SegmentValue::CenterOf(cpu) => cpu.to_string(),
+                _ => String::new(),
+            })
+            .y_labels(self.rows.len())
+            .y_label_style(label_style)
+            .y_label_formatter(&|row| match row {
+                SegmentValue::CenterOf(row) => usize::try_from(*row)
+                    .ok()
+                    .and_then(|row| self.rows.get(row))
+                    .map(|process| format!("{} ({})", process.name, process.pid))
+                    .unwrap_or_default(),
+                _ => String::new(),
+            })
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        let filled = colors::rgb(self.palette.primary).filled();
+        let empty = ShapeStyle::from(self.palette.grid()).stroke_width(1);
+        chart
+            .draw_series(self.rows.iter().enumerate().flat_map(|(row, process)| {
+                process
+                    .allowed
+                    .iter()
+                    .enumerate()
+                    .map(move |(cpu, &allowed)| {
+                        let (row, cpu) = (row as i32, cpu as i32);
+                        let mut cell = Rectangle::new(
+                            [
+                                (SegmentValue::Exact(cpu), SegmentValue::Exact(row)),
+                                (SegmentValue::Exact(cpu + 1), SegmentValue::Exact(row + 1)),
+                            ],
+                            if allowed { filled } else { empty },
+                        );
+                        cell.set_margin(2, 2, 2, 2);
+                        cell
+                    })
+            }))
+            .expect("failed to draw chart data");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, ticks: u64) -> ProcessTimes {
+        ProcessTimes {
+            pid,
+            name: format!("p{}", pid),
+            start: 0,
+            ticks,
+        }
+    }
+
+    #[test]
+    fn busiest_processes_get_rows_with_their_masks() {
+        let last = HashMap::from([(1, (0, 10)), (2, (0, 10)), (3, (0, 10))]);
+        // 3 is busiest but exits before its mask is read; 1 is pinned.
+        let current = [process(1, 20), process(2, 15), process(3, 90)];
+        let rows = pinnings(&last, &current, 4, |pid, cpus| match pid {
+            1 => Some(vec![true, false, false, false]),
+            2 => Some(vec![true; cpus]),
+            _ => None,
+        });
+        assert_eq!(
+            rows,
+            [
+                Pinning {
+                    pid: 1,
+                    name: "p1".to_string(),
+                    allowed: vec![true, false, false, false],
+                },
+                Pinning {
+                    pid: 2,
+                    name: "p2".to_string(),
+                    allowed: vec![true; 4],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn this_process_may_run_somewhere() {
+        let cpus = libc::CPU_SETSIZE as usize;
+        let allowed = allowed_cpus(std::process::id(), cpus).unwrap();
+        assert_eq!(allowed.len(), cpus);
+        assert!(allowed.contains(&true));
+    }
+}
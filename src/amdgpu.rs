@@ -0,0 +1,137 @@
+//! AMD GPU temperatures straight from the driver. amdgpu's hwmon
+//! `temp1_input` is simple to read but can lag behind the sensor; the
+//! `AMDGPU_INFO_SENSOR` query of the DRM info ioctl reads it when asked.
+//! Where the ioctl fails, e.g. without access to the card's device node,
+//! the hwmon file is read instead.
+
+use std::{
+    fs::{self, File},
+    io,
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+};
+
+/// `DRM_IOCTL_AMDGPU_INFO`: `_IOW('d', DRM_COMMAND_BASE + DRM_AMDGPU_INFO,
+/// struct drm_amdgpu_info)`.
+const DRM_IOCTL_AMDGPU_INFO: libc::c_ulong = (1 << 30)
+    | ((std::mem::size_of::<DrmAmdgpuInfo>() as libc::c_ulong) << 16)
+    | ((b'd' as libc::c_ulong) << 8)
+    | (0x40 + 0x05);
+const AMDGPU_INFO_SENSOR: u32 = 0x1d;
+/// Edge temperature in millidegrees Celsius.
+const AMDGPU_INFO_SENSOR_GPU_TEMP: u32 = 0x3;
+
+/// `struct drm_amdgpu_info`, with its union as the largest member's size;
+/// a sensor query only sets the first word.
+#[repr(C)]
+struct DrmAmdgpuInfo {
+    return_pointer: u64,
+    return_size: u32,
+    query: u32,
+    sensor_type: u32,
+    _union: [u32; 3],
+}
+
+/// One amdgpu card.
+pub struct DrmAmdGpuReader {
+    name: String,
+    /// The card's device node, if it could be opened.
+    card: io::Result<File>,
+    /// The hwmon directory of the card, for the fallback.
+    hwmon: Option<PathBuf>,
+}
+
+impl DrmAmdGpuReader {
+    /// Every amdgpu card under `/sys/class/drm`, in the order of their
+    /// names.
+    pub fn probe() -> Vec<Self> {
+        let mut cards: Vec<_> = fs::read_dir("/sys/class/drm")
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                // Not the `card0-DP-1` connectors.
+                let index = name.strip_prefix("card")?;
+                index.bytes().all(|b| b.is_ascii_digit()).then_some(())?;
+                let device = entry.path().join("device");
+                let driver = fs::read_link(device.join("driver")).ok()?;
+                (driver.file_name()? == "amdgpu").then_some((name, device))
+            })
+            .collect();
+        cards.sort();
+        cards
+            .into_iter()
+            .map(|(name, device)| Self {
+                card: File::open(Path::new("/dev/dri").join(&name)),
+                hwmon: hwmon_dir(&device),
+                name,
+            })
+            .collect()
+    }
+
+    /// In °C, from the driver, else from hwmon.
+    pub fn temperature(&self) -> Option<f64> {
+        self.read_sensor()
+            .ok()
+            .or_else(|| self.read_hwmon())
+            .map(|millidegrees| millidegrees as f64 / 1000.0)
+    }
+
+    fn read_sensor(&self) -> io::Result<u32> {
+        let card = self
+            .card
+            .as_ref()
+            .map_err(|e| io::Error::new(e.kind(), e.to_string()))?;
+        let mut temperature = 0u32;
+        let mut request = DrmAmdgpuInfo {
+            return_pointer: &mut temperature as *mut u32 as u64,
+            return_size: std::mem::size_of::<u32>() as u32,
+            query: AMDGPU_INFO_SENSOR,
+            sensor_type: AMDGPU_INFO_SENSOR_GPU_TEMP,
+            _union: [0; 3],
+        };
+        // SAFETY: the request is laid out like the kernel's struct, and it
+        // writes at most `return_size` bytes to `temperature`.
+        let result = unsafe { libc::ioctl(card.as_raw_fd(), DRM_IOCTL_AMDGPU_INFO, &mut request) };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(temperature)
+    }
+
+    fn read_hwmon(&self) -> Option<u32> {
+        let input = fs::read_to_string(self.hwmon.as_ref()?.join("temp1_input")).ok()?;
+        input.trim().parse().ok()
+    }
+
+    /// What the temperature is read through, for `--check`.
+    pub fn describe(&self) -> String {
+        match (self.read_sensor(), self.read_hwmon()) {
+            (Ok(_), _) => format!("{} through the amdgpu ioctl", self.name),
+            (Err(e), Some(_)) => format!("{} through hwmon (ioctl: {})", self.name, e),
+            (Err(e), None) => format!("{} unreadable (ioctl: {}; no hwmon sensor)", self.name, e),
+        }
+    }
+}
+
+fn hwmon_dir(device: &Path) -> Option<PathBuf> {
+    let mut dirs: Vec<_> = fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort();
+    dirs.into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_request_matches_the_kernel_uapi() {
+        assert_eq!(std::mem::size_of::<DrmAmdgpuInfo>(), 32);
+        assert_eq!(DRM_IOCTL_AMDGPU_INFO, 0x4020_6445);
+    }
+}
@@ -0,0 +1,191 @@
+//! Effective frequency from the APERF and MPERF MSRs. Both count only
+//! while a CPU runs, MPERF at the base clock and APERF at the actual one,
+//! so base × ΔAPERF / ΔMPERF is the clock the scheduled work really got,
+//! where cpufreq reports a requested or cached value. The counters are
+//! read by the power thread, summed per package, and taken by the charts
+//! at their own rate. Only built with the `aperf` feature.
+
+use std::{
+    fs::{self, File},
+    io,
+    os::unix::fs::FileExt,
+    sync::{Arc, Mutex},
+};
+
+const IA32_MPERF: u64 = 0xe7;
+const IA32_APERF: u64 = 0xe8;
+/// Bits 15:8 are the base ratio, in 100 MHz.
+const MSR_PLATFORM_INFO: u64 = 0xce;
+
+/// Counter increments summed over a package's CPUs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Cycles {
+    pub aperf: u64,
+    pub mperf: u64,
+}
+
+impl Cycles {
+    /// `None` if no CPU of the package ran since the last take.
+    fn mhz(self, base_mhz: f64) -> Option<f64> {
+        (self.mperf > 0).then(|| base_mhz * self.aperf as f64 / self.mperf as f64)
+    }
+}
+
+struct CpuCounters {
+    msr: File,
+    /// Index of the CPU's package in the totals.
+    package: usize,
+    last: Option<Cycles>,
+}
+
+/// Every CPU's counters, read by the power thread.
+pub struct AperfReader {
+    cpus: Vec<CpuCounters>,
+    totals: Arc<Mutex<Vec<Cycles>>>,
+}
+
+/// The charts' side: per package, the cycles since the last take.
+pub struct EffectiveFrequency {
+    totals: Arc<Mutex<Vec<Cycles>>>,
+    base_mhz: f64,
+}
+
+/// Opens the MSRs of `cpus` logical CPUs. A CPU whose MSRs can't be
+/// opened, e.g. one that is offline, is left out of its package's total;
+/// fails only if none can be opened.
+pub fn open(cpus: usize) -> io::Result<(AperfReader, EffectiveFrequency)> {
+    let mut error = None;
+    let msrs: Vec<(usize, File)> = (0..cpus)
+        .filter_map(|cpu| match File::open(format!("/dev/cpu/{}/msr", cpu)) {
+            Ok(msr) => Some((cpu, msr)),
+            Err(e) => {
+                error.get_or_insert(e);
+                None
+            }
+        })
+        .collect();
+    let Some((_, first)) = msrs.first() else {
+        return Err(error.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no CPUs")));
+    };
+    let base_mhz = base_mhz(first)?;
+    let package_ids: Vec<u32> = msrs.iter().map(|&(cpu, _)| package_id(cpu)).collect();
+    let packages = package_indices(&package_ids);
+    let totals = Arc::new(Mutex::new(vec![
+        Cycles::default();
+        packages
+            .iter()
+            .max()
+            .map_or(0, |last| last + 1)
+    ]));
+    let reader = AperfReader {
+        cpus: msrs
+            .into_iter()
+            .zip(packages)
+            .map(|((_, msr), package)| CpuCounters {
+                msr,
+                package,
+                last: None,
+            })
+            .collect(),
+        totals: totals.clone(),
+    };
+    Ok((reader, EffectiveFrequency { totals, base_mhz }))
+}
+
+impl AperfReader {
+    /// Adds what every CPU's counters advanced since the last read to its
+    /// package. A CPU that can't be read, e.g. one taken offline, is
+    /// skipped.
+    pub fn read(&mut self) {
+        let mut totals = self.totals.lock().unwrap();
+        for cpu in &mut self.cpus {
+            let (Ok(aperf), Ok(mperf)) = (
+                read_msr(&cpu.msr, IA32_APERF),
+                read_msr(&cpu.msr, IA32_MPERF),
+            ) else {
+                continue;
+            };
+            let now = Cycles { aperf, mperf };
+            if let Some(last) = cpu.last.replace(now) {
+                let total = &mut totals[cpu.package];
+                total.aperf += now.aperf.wrapping_sub(last.aperf);
+                total.mperf += now.mperf.wrapping_sub(last.mperf);
+            }
+        }
+    }
+}
+
+impl EffectiveFrequency {
+    pub fn packages(&self) -> usize {
+        self.totals.lock().unwrap().len()
+    }
+
+    /// Effective frequency of `package` since the last take, in MHz.
+    pub fn take(&self, package: usize) -> Option<f64> {
+        let mut totals = self.totals.lock().unwrap();
+        std::mem::take(totals.get_mut(package)?).mhz(self.base_mhz)
+    }
+}
+
+fn read_msr(msr: &File, register: u64) -> io::Result<u64> {
+    let mut value = [0; 8];
+    msr.read_exact_at(&mut value, register)?;
+    Ok(u64::from_le_bytes(value))
+}
+
+/// Intel's base clock from `MSR_PLATFORM_INFO`, else cpufreq's, which
+/// intel_pstate and amd-pstate report.
+fn base_mhz(msr: &File) -> io::Result<f64> {
+    let ratio = read_msr(msr, MSR_PLATFORM_INFO).map_or(0, |info| (info >> 8) & 0xff);
+    if ratio > 0 {
+        return Ok(ratio as f64 * 100.0);
+    }
+    let khz: f64 = fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/base_frequency")?
+        .trim()
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(khz / 1000.0)
+}
+
+fn package_id(cpu: usize) -> u32 {
+    fs::read_to_string(format!(
+        "/sys/devices/system/cpu/cpu{}/topology/physical_package_id",
+        cpu
+    ))
+    .ok()
+    .and_then(|id| id.trim().parse().ok())
+    .unwrap_or(0)
+}
+
+/// Each CPU's package numbered from 0 in the order of the ids, which
+/// needn't be contiguous.
+fn package_indices(ids: &[u32]) -> Vec<usize> {
+    let mut distinct = ids.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    ids.iter()
+        .map(|id| distinct.binary_search(id).unwrap_or_default())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequency_is_base_scaled_by_the_counter_ratio() {
+        let cycles = Cycles {
+            aperf: 4_500,
+            mperf: 3_000,
+        };
+        assert_eq!(cycles.mhz(3000.0), Some(4500.0));
+        assert_eq!(Cycles::default().mhz(3000.0), None);
+    }
+
+    #[test]
+    fn packages_are_numbered_densely() {
+        assert_eq!(package_indices(&[0, 0, 1, 1]), [0, 0, 1, 1]);
+        assert_eq!(package_indices(&[3, 0, 3, 0]), [1, 0, 1, 0]);
+        assert_eq!(package_indices(&[]), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,50 @@
+//! Entry points for the benchmarks in `benches/`, which only see public
+//! items. They work on samples handed in by the caller, so a benchmark
+//! needs neither sensors nor a display.
+
+use std::{hint::black_box, time::Duration};
+
+use plotters::prelude::*;
+use plotters_iced::Chart;
+
+use crate::{clock::Time, stats::StatReport, SimpleChart};
+
+/// A chart fed by hand instead of by a source.
+pub struct Series(SimpleChart);
+
+impl Series {
+    /// An empty chart keeping `limit` of history, like a metric's tile.
+    pub fn new(limit: Duration) -> Self {
+        let mut chart = SimpleChart::new(std::iter::empty(), "%".to_string(), 100);
+        chart.limit = limit;
+        Self(chart)
+    }
+
+    /// Adds a sample `time` into the run and drops those that fell out
+    /// of the window.
+    pub fn push(&mut self, time: Duration, value: i32) {
+        let time = chrono::Duration::from_std(time).unwrap_or_default();
+        self.0.push_data(Time::default() + time, value);
+    }
+
+    /// Samples currently kept.
+    pub fn len(&self) -> usize {
+        self.0.raw_data().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.raw_data().is_empty()
+    }
+
+    /// Draws the chart as its tile would into `buffer`, RGB and `width` by
+    /// `height` pixels.
+    pub fn draw(&self, buffer: &mut [u8], width: u32, height: u32) {
+        let root = BitMapBackend::with_buffer(buffer, (width, height)).into_drawing_area();
+        self.0.draw_chart(&(), root);
+    }
+}
+
+/// Computes the statistics panel's report of `values`.
+pub fn stats(values: &[f64]) {
+    black_box(StatReport::new(values.iter().copied()));
+}
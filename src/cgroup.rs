@@ -0,0 +1,205 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use iced::{
+    widget::{Row, Text},
+    Alignment, Element, Length,
+};
+
+use crate::{clock, colors::ChartPalette, config::ByteUnits, Message, SimpleChart};
+
+/// CPU and memory use of one cgroup v2, e.g. a container or systemd slice.
+pub struct CgroupMonitor {
+    pub path: PathBuf,
+    cpu: SimpleChart,
+    memory: SimpleChart,
+    /// `usage_usec` and `throttled_usec` at the previous sample.
+    last: Option<(Instant, u64, u64)>,
+    throttled_ms: u64,
+    memory_max: Option<u64>,
+}
+
+impl CgroupMonitor {
+    pub fn new(path: PathBuf, cpu_count: usize, byte_units: ByteUnits) -> Self {
+        let mut memory = SimpleChart::new(std::iter::empty(), String::new(), 1);
+        memory.byte_units = Some(byte_units);
+        Self {
+            path,
+            cpu: SimpleChart::new(std::iter::empty(), "%".into(), 100 * cpu_count as i32),
+            memory,
+            last: None,
+            throttled_ms: 0,
+            memory_max: None,
+        }
+    }
+
+    pub fn set_byte_units(&mut self, byte_units: ByteUnits) {
+        self.memory.byte_units = Some(byte_units);
+        self.memory.cache.clear();
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.cpu.set_palette(palette);
+        self.memory.set_palette(palette);
+    }
+
+    /// Takes one sample. Fails if the cgroup is gone or isn't a v2 cgroup.
+    pub fn update(&mut self) -> io::Result<()> {
+        let now = Instant::now();
+        let time = clock::now();
+
+        let stat = fs::read_to_string(self.path.join("cpu.stat"))?;
+        let usage = stat_field(&stat, "usage_usec").unwrap_or(0);
+        let throttled = stat_field(&stat, "throttled_usec").unwrap_or(0);
+        if let Some((last_time, last_usage, last_throttled)) = self.last {
+            let elapsed = now.duration_since(last_time).as_micros().max(1) as f64;
+            let percent = usage.saturating_sub(last_usage) as f64 / elapsed * 100.0;
+            self.cpu.push_data(time, percent.round() as i32);
+            self.throttled_ms = throttled.saturating_sub(last_throttled) / 1000;
+        }
+        self.last = Some((now, usage, throttled));
+
+        let current = read_u64(&self.path.join("memory.current"))?;
+        // `memory.max` is the literal "max" when unlimited.
+        self.memory_max = read_u64(&self.path.join("memory.max")).ok();
+        let current_kib = kib(current);
+        self.memory.max_value = match self.memory_max {
+            Some(max) => kib(max),
+            None => self.memory.max_value.max(current_kib),
+        };
+        self.memory.push_data(time, current_kib);
+        self.cpu.redraw_changed();
+        self.memory.redraw_changed();
+
+        Ok(())
+    }
+
+    pub fn view(&self, chart_height: f32) -> Element<'_, Message> {
+        let limit = match self.memory_max {
+            Some(max) => self.memory.display(kib(max).into()),
+            None => "no limit".to_string(),
+        };
+
+        Row::new()
+            .spacing(15)
+            .padding(20)
+            .width(Length::Fill)
+            .align_items(Alignment::Center)
+            .push(self.cpu.titled(
+                Text::new(format!(
+                    "{}: CPU {}% (throttled {} ms)",
+                    self.path.display(),
+                    self.cpu.latest().unwrap_or_default(),
+                    self.throttled_ms
+                )),
+                chart_height,
+            ))
+            .push(self.memory.titled(
+                Text::new(format!(
+                    "{}: Memory {} of {}",
+                    self.path.display(),
+                    self.memory
+                        .display(self.memory.latest().unwrap_or_default().into()),
+                    limit
+                )),
+                chart_height,
+            ))
+            .into()
+    }
+}
+
+/// Value of a `key value` line in a flat-keyed file like `cpu.stat`.
+fn stat_field(stat: &str, key: &str) -> Option<u64> {
+    stat.lines()
+        .filter_map(|line| line.split_once(' '))
+        .find(|(name, _)| *name == key)
+        .and_then(|(_, value)| value.trim().parse().ok())
+}
+
+/// Byte charts sample in KiB, which fits 2 TiB into an `i32`.
+fn kib(bytes: u64) -> i32 {
+    i32::try_from(bytes / 1024).unwrap_or(i32::MAX)
+}
+
+fn read_u64(path: &Path) -> io::Result<u64> {
+    fs::read_to_string(path)?
+        .trim()
+        .parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "not a number"))
+}
+
+/// CPU use of monty's own cgroup as a share of its `cpu.max` quota, for
+/// containers where the host-wide usage sysinfo reports is meaningless.
+pub struct CpuQuota {
+    /// The quota in CPUs, e.g. 2 for `200000 100000`.
+    pub cpus: f64,
+    last: Option<(Instant, u64)>,
+}
+
+impl CpuQuota {
+    /// Inside a cgroup namespace our own cgroup is the mount root. `None`
+    /// if there is no quota.
+    pub fn detect() -> Option<Self> {
+        let cpu_max = fs::read_to_string("/sys/fs/cgroup/cpu.max").ok()?;
+        Some(Self {
+            cpus: parse_cpu_max(&cpu_max)?,
+            last: None,
+        })
+    }
+
+    /// Usage since the previous call in percent of the quota; `None` on
+    /// the first call or if the cgroup can't be read.
+    pub fn usage_percent(&mut self) -> Option<f32> {
+        let now = Instant::now();
+        let stat = fs::read_to_string("/sys/fs/cgroup/cpu.stat").ok()?;
+        let usage = stat_field(&stat, "usage_usec")?;
+        let (last_time, last_usage) = self.last.replace((now, usage))?;
+
+        let elapsed = now.duration_since(last_time).as_micros() as u64;
+        Some(quota_percent(usage.saturating_sub(last_usage), elapsed, self.cpus) as f32)
+    }
+}
+
+/// `used_usec` of CPU time over `elapsed_usec` as a share of a quota of
+/// `cpus`. Capped at 100, as the quota is enforced over a whole period and
+/// a sample can straddle two.
+fn quota_percent(used_usec: u64, elapsed_usec: u64, cpus: f64) -> f64 {
+    let allowed = elapsed_usec.max(1) as f64 * cpus;
+    (used_usec as f64 / allowed * 100.0).min(100.0)
+}
+
+/// The quota in CPUs from `cpu.max` (`$MAX $PERIOD`, `max` if unlimited).
+fn parse_cpu_max(cpu_max: &str) -> Option<f64> {
+    let (max, period) = cpu_max.trim().split_once(' ')?;
+    let max: f64 = max.parse().ok()?;
+    let period: f64 = period.parse().ok()?;
+    (max > 0.0 && period > 0.0).then(|| max / period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_quota_in_cpus() {
+        assert_eq!(parse_cpu_max("200000 100000\n"), Some(2.0));
+        assert_eq!(parse_cpu_max("50000 100000"), Some(0.5));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+        assert_eq!(parse_cpu_max("0 100000"), None);
+        assert_eq!(parse_cpu_max("200000"), None);
+    }
+
+    #[test]
+    fn usage_is_scaled_to_the_quota() {
+        // Two busy CPUs in a 2-CPU container are all it may use, while on
+        // a 16-core host they are 12.5%.
+        assert_eq!(quota_percent(2_000_000, 1_000_000, 2.0), 100.0);
+        assert_eq!(quota_percent(500_000, 1_000_000, 2.0), 25.0);
+        assert_eq!(quota_percent(250_000, 1_000_000, 0.5), 50.0);
+        assert_eq!(quota_percent(2_100_000, 1_000_000, 2.0), 100.0);
+        assert_eq!(quota_percent(0, 0, 2.0), 0.0);
+    }
+}
@@ -0,0 +1,402 @@
+//! `monty doctor` and `--check`: probes every data source the way the GUI
+//! does at startup and prints which ones work, why not, and how to fix
+//! them, so a missing chart can be diagnosed without reading the code. The
+//! GUI shows the same report as its data sources panel.
+
+use std::{
+    env, fs, io,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+};
+
+use sysinfo::{CpuRefreshKind, RefreshKind, System};
+
+#[cfg(feature = "aperf")]
+use crate::aperf;
+use crate::{
+    amdgpu::DrmAmdGpuReader,
+    cgroup::CpuQuota,
+    perf::PerfEventReader,
+    sources::{self, GpuSource, IntelPstate, LMSensors, PowerSource, TempSource},
+    virt,
+};
+
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pub source: &'static str,
+    /// What was found, or why the source is unavailable.
+    pub result: Result<String, String>,
+    /// How to make an unavailable source work, e.g. `run: sudo modprobe msr`.
+    pub suggestion: Option<String>,
+    /// monty can't run without it; `monty doctor` fails if it's missing.
+    pub required: bool,
+}
+
+impl Probe {
+    pub fn new(source: &'static str, result: Result<String, String>) -> Self {
+        Self {
+            source,
+            result,
+            suggestion: None,
+            required: false,
+        }
+    }
+
+    /// Offers `suggestion` if the source turned out unavailable.
+    fn suggest(mut self, suggestion: impl Into<String>) -> Self {
+        if self.result.is_err() {
+            self.suggestion = Some(suggestion.into());
+        }
+        self
+    }
+
+    fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// A required source that is unavailable.
+    pub fn failed(&self) -> bool {
+        self.required && self.result.is_err()
+    }
+}
+
+/// Probes every source in the order the GUI does. libsensors can only be
+/// initialized once per process, so `sensors` is the caller's handle, or
+/// why it has none.
+pub fn probes(sensors: Result<&LMSensors, &str>) -> Vec<Probe> {
+    probes_with(sensor_probes(sensors))
+}
+
+/// The probes that need libsensors. Its handle can't leave the thread
+/// that owns it, so the GUI runs these there and the rest in the
+/// background.
+pub fn sensor_probes(sensors: Result<&LMSensors, &str>) -> Vec<Probe> {
+    let mut probes = vec![
+        Probe::new("lm-sensors", sensors.map(chips).map_err(str::to_string))
+            .suggest("install lm-sensors and run: sudo sensors-detect"),
+    ];
+    let sensors = sensors.ok();
+
+    probes.push(
+        Probe::new("temperature", temperature(sensors))
+            .suggest("run: sudo sensors-detect, and load the modules it names"),
+    );
+    probes.push(Probe::new(
+        "hwmon power",
+        match sensors.and_then(PowerSource::probe_hwmon) {
+            Some(PowerSource::Hwmon { chip, feature }) => Ok(format!("{} {}", chip, feature)),
+            _ if sensors.is_none() => Err("needs lm-sensors".to_string()),
+            _ => Err("no chip exposes a power input".to_string()),
+        },
+    ));
+    probes
+}
+
+/// Every probe, with the result of [`sensor_probes`] in its place. Reads
+/// sysfs and opens the display socket and perf counters, so the GUI calls
+/// it off the UI thread.
+pub fn probes_with(sensor_probes: Vec<Probe>) -> Vec<Probe> {
+    let environment = virt::detect();
+    let mut probes = vec![
+        display(),
+        Probe::new(
+            "cpu usage",
+            fs::read_to_string("/proc/stat")
+                .map(|_| "/proc/stat readable".to_string())
+                .map_err(|e| format!("/proc/stat: {}", e)),
+        )
+        .suggest("mount procfs on /proc")
+        .required(),
+        Probe::new(
+            "environment",
+            if environment.is_virtualized() {
+                Err(format!(
+                    "{:?}; sensors and power belong to the host and are skipped",
+                    environment
+                ))
+            } else {
+                Ok("bare metal".to_string())
+            },
+        ),
+    ];
+
+    let msr = if cfg!(target_arch = "x86_64") {
+        match sources::open_msr() {
+            Ok(_) => Probe::new("msr", Ok(format!("{} readable", sources::MSR_PATH))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                Probe::new("msr", Err(format!("{} missing", sources::MSR_PATH)))
+                    .suggest("run: sudo modprobe msr")
+            }
+            Err(e) => io_probe("msr", sources::MSR_PATH, &e),
+        }
+    } else {
+        Probe::new("msr", Err("not an x86_64 CPU".to_string()))
+    };
+    probes.push(msr);
+
+    let powercap = sources::constraint_path(0, "power_limit_uw");
+    probes.push(match fs::read_to_string(&powercap) {
+        Ok(_) => Probe::new("powercap", Ok(format!("{} readable", powercap.display()))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            Probe::new("powercap", Err(format!("{} missing", powercap.display())))
+                .suggest("run: sudo modprobe intel_rapl_msr")
+        }
+        Err(e) => io_probe("powercap", &powercap, &e),
+    });
+
+    probes.extend(sensor_probes);
+
+    let sys = System::new_with_specifics(RefreshKind::new().with_cpu(CpuRefreshKind::new()));
+    probes.push(
+        Probe::new(
+            "cpufreq",
+            match sources::average_frequency(&sys) {
+                0 => Err("no frequency from sysinfo or /sys/devices/system/cpu".to_string()),
+                mhz => Ok(format!("{} CPUs, {} MHz", sys.cpus().len(), mhz)),
+            },
+        )
+        .suggest("load a cpufreq driver, e.g. run: sudo modprobe acpi-cpufreq"),
+    );
+    #[cfg(feature = "aperf")]
+    probes.push(
+        Probe::new(
+            "aperf/mperf",
+            aperf::open(sys.cpus().len())
+                .map(|(_, frequency)| format!("{} packages", frequency.packages()))
+                .map_err(|e| format!("{}; frequencies come from cpufreq", e)),
+        )
+        .suggest("run: sudo modprobe msr, and run monty as root"),
+    );
+    probes.push(Probe::new(
+        "intel_pstate",
+        match IntelPstate::read() {
+            Some(pstate) => Ok(format!(
+                "{:?} mode{}",
+                pstate.mode,
+                if pstate.no_turbo { ", turbo off" } else { "" }
+            )),
+            None => Err(format!(
+                "{} missing; not an Intel CPU, or another cpufreq driver is in use",
+                sources::INTEL_PSTATE_PATH
+            )),
+        },
+    ));
+    probes.push(Probe::new(
+        "cpu quota",
+        match CpuQuota::detect() {
+            Some(quota) => Ok(format!("{:.1} CPUs from cpu.max", quota.cpus)),
+            None => Err("no cgroup cpu.max limit; usage is relative to the host".to_string()),
+        },
+    ));
+    let perf = PerfEventReader::open(sys.cpus().len());
+    let denied = perf
+        .as_ref()
+        .is_err_and(|e| e.kind() == io::ErrorKind::PermissionDenied);
+    let perf = Probe::new(
+        "perf events",
+        perf.map(|_| "cycle and instruction counters".to_string())
+            .map_err(|e| e.to_string()),
+    );
+    probes.push(if denied {
+        perf.suggest("run: sudo sysctl kernel.perf_event_paranoid=1, or run monty as root")
+    } else {
+        perf
+    });
+
+    probes.push(Probe::new(
+        "battery",
+        Err("not charted by monty".to_string()),
+    ));
+    probes.push(Probe::new(
+        "gpu memory",
+        GpuSource::probe()
+            .map(|gpu| gpu.describe())
+            .ok_or_else(|| "no NVIDIA device with libnvidia-ml, and no amdgpu card".to_string()),
+    ));
+    let amd_gpus = DrmAmdGpuReader::probe();
+    probes.push(Probe::new(
+        "gpu temperature",
+        if amd_gpus.is_empty() {
+            Err("no amdgpu card".to_string())
+        } else {
+            Ok(amd_gpus
+                .iter()
+                .map(DrmAmdGpuReader::describe)
+                .collect::<Vec<_>>()
+                .join(", "))
+        },
+    ));
+    probes
+}
+
+/// Which temperature source the GUI would pick.
+fn temperature(sensors: Option<&LMSensors>) -> Result<String, String> {
+    match TempSource::probe(sensors) {
+        Some(TempSource::Coretemp) => Ok("coretemp package sensor".to_string()),
+        Some(TempSource::Component(_)) => {
+            Ok("hwmon package sensor through sysinfo (no libsensors)".to_string())
+        }
+        Some(TempSource::ThermalZone(zone)) => Ok(format!("{} (no coretemp)", zone.display())),
+        None if sensors.is_none() => {
+            Err("no CPU thermal zone, and coretemp needs lm-sensors".to_string())
+        }
+        None => Err("no coretemp chip and no CPU thermal zone".to_string()),
+    }
+}
+
+/// The chips libsensors found, by name.
+#[cfg(feature = "sensors")]
+fn chips(sensors: &LMSensors) -> String {
+    let names: Vec<_> = sensors
+        .chip_iter(None)
+        .filter_map(|chip| chip.name().ok())
+        .collect();
+    match names.as_slice() {
+        [] => "no chips".to_string(),
+        names => format!("{} chips: {}", names.len(), names.join(", ")),
+    }
+}
+
+#[cfg(not(feature = "sensors"))]
+fn chips(sensors: &LMSensors) -> String {
+    match *sensors {}
+}
+
+/// Whether winit will find a display server: Wayland first, then X11.
+fn display() -> Probe {
+    let wayland = env::var_os("WAYLAND_DISPLAY").map(|name| {
+        let socket = PathBuf::from(name);
+        match env::var_os("XDG_RUNTIME_DIR") {
+            Some(dir) if socket.is_relative() => Path::new(&dir).join(socket),
+            _ => socket,
+        }
+    });
+    let x11 = env::var("DISPLAY").ok();
+    let result = match (wayland, x11) {
+        (Some(socket), _) if UnixStream::connect(&socket).is_ok() => {
+            Ok(format!("Wayland at {}", socket.display()))
+        }
+        (wayland, Some(display)) => match x11_socket(&display) {
+            Some(socket) => match UnixStream::connect(&socket) {
+                Ok(_) => Ok(format!("X11 at {}", display)),
+                Err(e) => Err(format!("X11 display {}: {}", display, e)),
+            },
+            // Forwarded over TCP, e.g. by `ssh -X`; not worth a probe.
+            None => Ok(format!("X11 at {}", display)),
+        }
+        .map_err(|e| match wayland {
+            Some(socket) => format!("no Wayland at {}, and {}", socket.display(), e),
+            None => e,
+        }),
+        (Some(socket), None) => Err(format!(
+            "no Wayland compositor at {}, and DISPLAY is unset",
+            socket.display()
+        )),
+        (None, None) => Err("neither WAYLAND_DISPLAY nor DISPLAY is set".to_string()),
+    };
+    Probe::new("display", result)
+        .suggest("start monty from a desktop session")
+        .required()
+}
+
+/// The local socket of an X11 display like `:0` or `:1.0`; `None` for
+/// one on another host.
+fn x11_socket(display: &str) -> Option<PathBuf> {
+    let number = display.strip_prefix(':')?;
+    let number = number.split('.').next()?;
+    number
+        .parse::<u32>()
+        .ok()
+        .map(|number| PathBuf::from(format!("/tmp/.X11-unix/X{}", number)))
+}
+
+fn io_probe(source: &'static str, path: impl AsRef<Path>, error: &io::Error) -> Probe {
+    let path = path.as_ref().display();
+    match error.kind() {
+        io::ErrorKind::PermissionDenied => {
+            Probe::new(source, Err(format!("no permission to read {}", path)))
+                .suggest("run monty as root")
+        }
+        _ => Probe::new(source, Err(format!("{}: {}", path, error))),
+    }
+}
+
+/// One row per probe, columns aligned, with the suggestion under it.
+pub fn report(probes: &[Probe]) -> String {
+    let width = probes.iter().map(|p| p.source.len()).max().unwrap_or(0);
+    probes
+        .iter()
+        .map(|probe| {
+            let (status, detail) = match &probe.result {
+                Ok(detail) => ("available", detail),
+                Err(reason) if probe.required => ("FAILED", reason),
+                Err(reason) => ("unavailable", reason),
+            };
+            let mut row = format!(
+                "{:width$}  {:11}  {}\n",
+                probe.source,
+                status,
+                detail,
+                width = width
+            );
+            if let Some(suggestion) = &probe.suggestion {
+                row += &format!("{:width$}  → {}\n", "", suggestion, width = width + 13);
+            }
+            row
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_aligns_columns() {
+        let probes = [
+            Probe::new("msr", Ok("readable".to_string())),
+            Probe::new("powercap", Err("missing".to_string())),
+        ];
+        assert_eq!(
+            report(&probes),
+            "msr       available    readable\npowercap  unavailable  missing\n"
+        );
+    }
+
+    #[test]
+    fn suggestions_go_under_unavailable_sources() {
+        let probes = [
+            Probe::new("msr", Err("missing".to_string())).suggest("run: sudo modprobe msr"),
+            Probe::new("cpufreq", Ok("4 CPUs".to_string())).suggest("load a driver"),
+            Probe::new("display", Err("unset".to_string())).required(),
+        ];
+        assert_eq!(
+            report(&probes),
+            "msr      unavailable  missing\n\
+             \x20                     → run: sudo modprobe msr\n\
+             cpufreq  available    4 CPUs\n\
+             display  FAILED       unset\n"
+        );
+        assert!(!probes[0].failed());
+        assert!(probes[2].failed());
+    }
+
+    #[test]
+    fn sensor_probes_report_why_there_is_no_handle() {
+        let probes = sensor_probes(Err("initialization failed: no config"));
+        assert_eq!(probes[0].source, "lm-sensors");
+        assert_eq!(
+            probes[0].result,
+            Err("initialization failed: no config".to_string())
+        );
+        assert_eq!(probes[2].result, Err("needs lm-sensors".to_string()));
+    }
+
+    #[test]
+    fn local_x11_displays_have_a_socket() {
+        assert_eq!(x11_socket(":0"), Some(PathBuf::from("/tmp/.X11-unix/X0")));
+        assert_eq!(x11_socket(":1.0"), Some(PathBuf::from("/tmp/.X11-unix/X1")));
+        assert_eq!(x11_socket("localhost:10.0"), None);
+    }
+}
@@ -0,0 +1,246 @@
+//! Sample times. Samples are keyed by monotonic time since startup, so an
+//! NTP step can't reorder or squeeze them and a suspend doesn't leave a
+//! hole the size of the night in every chart. That time is a [`Time`]:
+//! the wall clock at startup plus the monotonic time since, in a type of
+//! its own so it can't be mixed up with a wall time. Whenever the wall
+//! clock jumps relative to it, the new offset is noted, so any sample time
+//! can be mapped back to the wall clock for labels and exports, and wall
+//! times from files back onto the axis.
+
+use std::{
+    ops::{Add, Range, Sub},
+    sync::{Mutex, OnceLock},
+    time::{self, Instant},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use plotters::coord::{
+    ranged1d::{DefaultFormatting, KeyPointHint, Ranged},
+    types::RangedDateTime,
+};
+
+/// A point on the charts' time axis. Only [`wall`] and [`from_wall`] turn
+/// it into a wall time and back. The default is the axis' origin, which
+/// sessions compared side by side are shifted to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Time(DateTime<Utc>);
+
+impl Add<Duration> for Time {
+    type Output = Time;
+
+    fn add(self, duration: Duration) -> Time {
+        Time(self.0 + duration)
+    }
+}
+
+impl Sub<Duration> for Time {
+    type Output = Time;
+
+    fn sub(self, duration: Duration) -> Time {
+        Time(self.0 - duration)
+    }
+}
+
+impl Sub for Time {
+    type Output = Duration;
+
+    fn sub(self, other: Time) -> Duration {
+        self.0 - other.0
+    }
+}
+
+/// The x-axis of a chart over [`Time`], for plotters'
+/// `build_cartesian_2d`.
+pub struct TimeAxis(RangedDateTime<DateTime<Utc>>);
+
+impl From<Range<Time>> for TimeAxis {
+    fn from(range: Range<Time>) -> Self {
+        TimeAxis((range.start.0..range.end.0).into())
+    }
+}
+
+impl Ranged for TimeAxis {
+    type FormatOption = DefaultFormatting;
+    type ValueType = Time;
+
+    fn map(&self, value: &Time, limit: (i32, i32)) -> i32 {
+        self.0.map(&value.0, limit)
+    }
+
+    fn key_points<Hint: KeyPointHint>(&self, hint: Hint) -> Vec<Time> {
+        self.0.key_points(hint).into_iter().map(Time).collect()
+    }
+
+    fn range(&self) -> Range<Time> {
+        let range = self.0.range();
+        Time(range.start)..Time(range.end)
+    }
+}
+
+/// Smallest jump of the wall clock that is noted; below it, labels are
+/// simply that far off.
+const STEP: Duration = Duration::milliseconds(100);
+
+struct Timeline {
+    start: Instant,
+    start_wall: DateTime<Utc>,
+    /// `CLOCK_MONOTONIC` at `start`, which is what `Instant` reads.
+    start_monotonic: time::Duration,
+    /// From which sample time on the wall clock was how far ahead of the
+    /// sample times, oldest first. Before the first, it wasn't.
+    offsets: Vec<(Time, Duration)>,
+    /// The newest sample time handed out.
+    last: Time,
+}
+
+impl Timeline {
+    fn new(start: Instant, start_wall: DateTime<Utc>, start_monotonic: time::Duration) -> Self {
+        Self {
+            start,
+            start_wall,
+            start_monotonic,
+            offsets: Vec::new(),
+            last: Time(start_wall),
+        }
+    }
+
+    /// The sample time of `instant`, noting where `wall` has moved from
+    /// the last known offset.
+    fn observe(&mut self, instant: Instant, wall: DateTime<Utc>) -> Time {
+        let elapsed =
+            Duration::from_std(instant.saturating_duration_since(self.start)).unwrap_or_default();
+        let time = Time(self.start_wall + elapsed);
+        let offset = wall - time.0;
+        if (offset - self.offset_at(time)).abs() >= STEP {
+            self.offsets.push((time, offset));
+        }
+        self.last = self.last.max(time);
+        time
+    }
+
+    fn offset_at(&self, time: Time) -> Duration {
+        self.offsets
+            .iter()
+            .rev()
+            .find(|(since, _)| *since <= time)
+            .map_or(Duration::zero(), |(_, offset)| *offset)
+    }
+
+    fn at_monotonic(&self, monotonic: time::Duration) -> Time {
+        let since_start = Duration::from_std(monotonic).unwrap_or_default()
+            - Duration::from_std(self.start_monotonic).unwrap_or_default();
+        Time(self.start_wall + since_start)
+    }
+
+    fn wall(&self, time: Time) -> DateTime<Utc> {
+        time.0 + self.offset_at(time)
+    }
+
+    /// Where the wall clock read `wall`; the latest such time if it was
+    /// set back and read `wall` twice. A wall time the clock skipped, in a
+    /// suspend or a step forward, is put at the end of the stretch before
+    /// the jump, and one that hasn't come yet at the newest sample time,
+    /// so nothing read back lands right of "now".
+    fn time(&self, wall: DateTime<Utc>) -> Time {
+        let mut end = self.last;
+        let mut skipped = None;
+        // Each stretch runs from its offset's start to the next one's.
+        let stretches = self.offsets.iter().rev().copied();
+        for (since, offset) in stretches.chain([(Time(DateTime::<Utc>::MIN_UTC), Duration::zero())])
+        {
+            let time = Time(wall - offset);
+            if since <= time && time <= end {
+                return time;
+            }
+            if since <= time {
+                skipped.get_or_insert(end);
+            }
+            end = since;
+        }
+        skipped.unwrap_or(self.last)
+    }
+}
+
+fn timeline() -> &'static Mutex<Timeline> {
+    static TIMELINE: OnceLock<Mutex<Timeline>> = OnceLock::new();
+    TIMELINE.get_or_init(|| Mutex::new(Timeline::new(Instant::now(), Utc::now(), monotonic_now())))
+}
+
+fn monotonic_now() -> time::Duration {
+    // SAFETY: clock_gettime only writes the timespec it is given.
+    let mut now: libc::timespec = unsafe { std::mem::zeroed() };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) };
+    time::Duration::new(now.tv_sec as u64, now.tv_nsec as u32)
+}
+
+/// The time to key a new sample by.
+pub fn now() -> Time {
+    timeline()
+        .lock()
+        .unwrap()
+        .observe(Instant::now(), Utc::now())
+}
+
+/// The wall clock at sample time `time`, to show or export.
+pub fn wall(time: Time) -> DateTime<Utc> {
+    timeline().lock().unwrap().wall(time)
+}
+
+/// The sample time at a reading of `CLOCK_MONOTONIC`, like the
+/// timestamps of `perf record -k CLOCK_MONOTONIC`.
+pub fn from_monotonic(monotonic: time::Duration) -> Time {
+    timeline().lock().unwrap().at_monotonic(monotonic)
+}
+
+/// The sample time the wall clock read `wall` at, for samples read back
+/// from a file.
+pub fn from_wall(wall: DateTime<Utc>) -> Time {
+    timeline().lock().unwrap().time(wall)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    /// The sample time the wall clock read `at(secs)` at, had it never
+    /// jumped.
+    fn axis(secs: i64) -> Time {
+        Time(at(secs))
+    }
+
+    #[test]
+    fn jumps_of_the_wall_clock_stay_off_the_axis() {
+        let start = Instant::now();
+        let after = |secs| start + time::Duration::from_secs(secs);
+        let mut timeline = Timeline::new(start, at(0), time::Duration::from_secs(500));
+
+        assert_eq!(timeline.observe(after(10), at(10)), axis(10));
+        // Suspended for an hour: the wall clock moved on, the axis didn't.
+        assert_eq!(timeline.observe(after(11), at(3611)), axis(11));
+        // NTP sets the clock back a minute.
+        assert_eq!(timeline.observe(after(20), at(3560)), axis(20));
+        assert_eq!(timeline.observe(after(21), at(3561)), axis(21));
+
+        assert_eq!(timeline.wall(axis(10)), at(10));
+        assert_eq!(timeline.wall(axis(11)), at(3611));
+        assert_eq!(timeline.wall(axis(19)), at(3619));
+        assert_eq!(timeline.wall(axis(21)), at(3561));
+
+        assert_eq!(timeline.time(at(5)), axis(5));
+        assert_eq!(timeline.time(at(3561)), axis(21));
+        // Read at 15, before the clock was set back past it.
+        assert_eq!(timeline.time(at(3615)), axis(15));
+        // Skipped while suspended, and not read yet.
+        assert_eq!(timeline.time(at(1000)), axis(11));
+        assert_eq!(timeline.time(at(9000)), axis(21));
+
+        assert_eq!(
+            timeline.at_monotonic(time::Duration::from_secs(511)),
+            axis(11)
+        );
+    }
+}
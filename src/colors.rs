@@ -0,0 +1,178 @@
+use iced::{theme, Color, Theme};
+use plotters::style::{Color as _, RGBAColor, RGBColor};
+use serde::{Deserialize, Serialize};
+
+/// Every color monty draws with. The iced theme is generated from it, so
+/// widgets, chart lines (picked from the theme's palette, see
+/// `MetricDescriptor::default_color`) and the charts' grids and labels
+/// always match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ChartPalette {
+    pub background: [u8; 3],
+    /// Text; grids, axes and annotations are drawn in faded shades of it.
+    pub text: [u8; 3],
+    pub primary: [u8; 3],
+    pub success: [u8; 3],
+    pub warning: [u8; 3],
+    /// Alerts, throttling and anything else over a threshold.
+    pub danger: [u8; 3],
+}
+
+/// Palettes that are always available, by the name the config uses.
+pub const PRESETS: &[(&str, ChartPalette)] = &[
+    (
+        "dark",
+        ChartPalette {
+            background: [0x20, 0x22, 0x25],
+            text: [0xe6, 0xe6, 0xe6],
+            primary: [0x5e, 0x7c, 0xe2],
+            success: [0x12, 0x66, 0x4f],
+            warning: [0xf0, 0xb4, 0x00],
+            danger: [0xc3, 0x42, 0x3f],
+        },
+    ),
+    (
+        "light",
+        ChartPalette {
+            background: [0xff, 0xff, 0xff],
+            text: [0x00, 0x00, 0x00],
+            primary: [0x5e, 0x7c, 0xe2],
+            success: [0x12, 0x66, 0x4f],
+            warning: [0xd0, 0x90, 0x00],
+            danger: [0xc3, 0x42, 0x3f],
+        },
+    ),
+    (
+        "nord",
+        ChartPalette {
+            background: [0x2e, 0x34, 0x40],
+            text: [0xec, 0xef, 0xf4],
+            primary: [0x88, 0xc0, 0xd0],
+            success: [0xa3, 0xbe, 0x8c],
+            warning: [0xeb, 0xcb, 0x8b],
+            danger: [0xbf, 0x61, 0x6a],
+        },
+    ),
+    (
+        "gruvbox",
+        ChartPalette {
+            background: [0x28, 0x28, 0x28],
+            text: [0xfb, 0xf1, 0xc7],
+            primary: [0x45, 0x85, 0x88],
+            success: [0x98, 0x97, 0x1a],
+            warning: [0xd7, 0x99, 0x21],
+            danger: [0xcc, 0x24, 0x1d],
+        },
+    ),
+    (
+        "solarized",
+        ChartPalette {
+            background: [0x00, 0x2b, 0x36],
+            text: [0x93, 0xa1, 0xa1],
+            primary: [0x26, 0x8b, 0xd2],
+            success: [0x85, 0x99, 0x00],
+            warning: [0xb5, 0x89, 0x00],
+            danger: [0xdc, 0x32, 0x2f],
+        },
+    ),
+];
+
+impl Default for ChartPalette {
+    fn default() -> Self {
+        PRESETS[0].1
+    }
+}
+
+impl ChartPalette {
+    /// The preset called `name`, if there is one.
+    pub fn preset(name: &str) -> Option<ChartPalette> {
+        PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .map(|&(_, palette)| palette)
+    }
+
+    /// An iced theme with the same colors, for the widgets around the
+    /// charts.
+    pub fn theme(&self, name: &str) -> Theme {
+        Theme::custom(
+            name.to_string(),
+            theme::Palette {
+                background: color(self.background),
+                text: color(self.text),
+                primary: color(self.primary),
+                success: color(self.success),
+                danger: color(self.danger),
+            },
+        )
+    }
+
+    pub fn grid(&self) -> RGBAColor {
+        self.faded(0.1)
+    }
+
+    pub fn fine_grid(&self) -> RGBAColor {
+        self.faded(0.02)
+    }
+
+    pub fn axis(&self) -> RGBAColor {
+        self.faded(0.45)
+    }
+
+    pub fn label(&self) -> RGBAColor {
+        self.faded(0.65)
+    }
+
+    /// Lines drawn over the data, like markers and limits.
+    pub fn annotation(&self) -> RGBAColor {
+        self.faded(0.6)
+    }
+
+    /// Text of annotations and legends.
+    pub fn annotation_text(&self) -> RGBAColor {
+        self.faded(0.8)
+    }
+
+    /// Text written across a whole chart, like the stale watermark.
+    pub fn watermark(&self) -> RGBAColor {
+        self.faded(0.35)
+    }
+
+    pub fn legend_background(&self) -> RGBAColor {
+        rgb(self.background).mix(0.6)
+    }
+
+    pub fn legend_border(&self) -> RGBAColor {
+        self.faded(0.3)
+    }
+
+    /// Text in shades of `text`, e.g. `0.1` for a faint grid.
+    pub fn faded(&self, alpha: f64) -> RGBAColor {
+        rgb(self.text).mix(alpha)
+    }
+}
+
+/// A palette color for plotters.
+pub fn rgb([r, g, b]: [u8; 3]) -> RGBColor {
+    RGBColor(r, g, b)
+}
+
+/// A palette color for iced widgets.
+pub fn color([r, g, b]: [u8; 3]) -> Color {
+    Color::from_rgb8(r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_round_trip_through_the_config() {
+        for &(name, palette) in PRESETS {
+            assert_eq!(ChartPalette::preset(name), Some(palette));
+            let toml = toml::to_string(&palette).unwrap();
+            assert_eq!(toml::from_str::<ChartPalette>(&toml).unwrap(), palette);
+        }
+        assert_eq!(ChartPalette::preset("dracula"), None);
+    }
+}
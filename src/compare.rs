@@ -0,0 +1,162 @@
+use std::{path::PathBuf, time::Duration};
+
+use iced::{
+    widget::{Column, Row, Scrollable, Text},
+    Alignment, Element, Length,
+};
+
+use crate::{
+    clock::Time,
+    colors::{self, ChartPalette},
+    config::TraceStyle,
+    history::{Series, Snapshot},
+    Message, SimpleChart,
+};
+
+/// Two saved sessions side by side, session A on the left and B on the
+/// right, each chart also tracing the other session for reference. Both
+/// are aligned on time since their own start rather than wall-clock time.
+#[derive(Default)]
+pub struct CompareMode {
+    sessions: Vec<(PathBuf, Snapshot)>,
+    /// Per series name: the chart of session A and that of session B.
+    charts: Vec<(String, SimpleChart, SimpleChart)>,
+    palette: ChartPalette,
+}
+
+impl CompareMode {
+    /// Adds a session; a third one replaces the older two.
+    pub fn load(&mut self, path: PathBuf, snapshot: Snapshot) {
+        if self.sessions.len() == 2 {
+            self.sessions.clear();
+        }
+        self.sessions.push((path, snapshot));
+        self.rebuild();
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        self.charts.clear();
+        let [(_, a), (_, b)] = &self.sessions[..] else {
+            return;
+        };
+
+        let (a, b) = (aligned(a), aligned(b));
+        let span = a
+            .iter()
+            .chain(&b)
+            .filter_map(|(_, points)| points.first())
+            .map(|(time, _)| *time)
+            .max()
+            .unwrap_or_default();
+
+        let mut names: Vec<&String> = a.iter().chain(&b).map(|(name, _)| name).collect();
+        names.sort();
+        names.dedup();
+
+        let series = |session: &[(String, Series)], name: &str| {
+            session
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, points)| points.clone())
+                .unwrap_or_default()
+        };
+        for name in names {
+            let (points_a, points_b) = (series(&a, name), series(&b, name));
+            let max_value = points_a
+                .iter()
+                .chain(&points_b)
+                .map(|(_, value)| *value)
+                .max()
+                .unwrap_or_default()
+                .max(1);
+            let chart = |own: &[(Time, i32)], other: &[(Time, i32)]| {
+                let mut chart = SimpleChart::new(own.iter().copied(), String::new(), max_value);
+                chart.limit = (span - Time::default()).to_std().unwrap_or(Duration::ZERO);
+                chart.anchor = Some(span);
+                chart.set_palette(self.palette);
+                // The other session is traced in the warning color, and
+                // two fills would hide each other.
+                chart.style = TraceStyle::LineOnly;
+                chart.overlay = Some((other.to_vec(), colors::rgb(self.palette.warning)));
+                chart
+            };
+            self.charts.push((
+                name.clone(),
+                chart(&points_a, &points_b),
+                chart(&points_b, &points_a),
+            ));
+        }
+    }
+
+    pub fn view(&self, chart_height: f32) -> Element<'_, Message> {
+        if self.charts.is_empty() {
+            return Text::new(format!(
+                "{} of 2 sessions loaded; start monty with --compare-sessions <A> <B>",
+                self.sessions.len()
+            ))
+            .into();
+        }
+
+        let name = |i: usize| {
+            self.sessions[i]
+                .0
+                .file_name()
+                .map_or_else(String::new, |n| n.to_string_lossy().into_owned())
+        };
+        let mut col = Column::new()
+            .width(Length::Fill)
+            .align_items(Alignment::Center)
+            .push(Text::new(format!(
+                "A: {} ({} min)  |  B: {} ({} min), other session in orange",
+                name(0),
+                self.sessions[0].1.minutes(),
+                name(1),
+                self.sessions[1].1.minutes()
+            )));
+
+        for (series, a, b) in &self.charts {
+            col = col.push(
+                Row::new()
+                    .spacing(15)
+                    .padding(20)
+                    .width(Length::Fill)
+                    .align_items(Alignment::Center)
+                    .push(a.titled(Text::new(format!("{} (A)", series)), chart_height))
+                    .push(b.titled(Text::new(format!("{} (B)", series)), chart_height)),
+            );
+        }
+
+        Scrollable::new(col).height(Length::Shrink).into()
+    }
+}
+
+/// Shifts every series so the session starts at the Unix epoch, keeping
+/// the newest-first order.
+fn aligned(snapshot: &Snapshot) -> Vec<(String, Series)> {
+    let Some(start) = snapshot
+        .series
+        .iter()
+        .filter_map(|(_, points)| points.last())
+        .map(|(time, _)| *time)
+        .min()
+    else {
+        return Vec::new();
+    };
+
+    snapshot
+        .series
+        .iter()
+        .map(|(name, points)| {
+            let points = points
+                .iter()
+                .map(|(time, value)| (Time::default() + (*time - start), *value))
+                .collect();
+            (name.clone(), points)
+        })
+        .collect()
+}
@@ -0,0 +1,487 @@
+use std::{collections::HashMap, env, fs, io, path::PathBuf};
+
+use color_eyre::eyre::{Result as EyreResult, WrapErr};
+use iced::Theme;
+use plotters::style::RGBColor;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    colors::{ChartPalette, PRESETS},
+    metrics,
+    sources::SampleSource,
+    units::Unit,
+    ChartId,
+};
+
+/// User configuration, read from `$XDG_CONFIG_HOME/monty/config.toml`.
+/// Every field has a default so a partial (or missing) file is fine.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Name of the color palette: a preset (`dark`, `light`, `nord`,
+    /// `gruvbox`, `solarized`) or one of `palettes`.
+    pub theme: String,
+    /// User-defined palettes by name; a name shared with a preset replaces it.
+    pub palettes: HashMap<String, ChartPalette>,
+    /// How often the charts take a sample, unless the chart sets its own.
+    pub sample_interval_ms: u32,
+    /// Never sample on a timer, only when asked to with "Sample now" or
+    /// `r`; for spot checks on battery.
+    pub manual_sampling: bool,
+    /// Show CPU usage as a share of the cgroup's `cpu.max` quota, so 100%
+    /// means everything the container may use rather than the whole host;
+    /// unset means only inside a container.
+    pub scale_usage_to_quota: Option<bool>,
+    /// Tick interval while the window is unfocused or minimized; unset
+    /// means tick once per sample so collection carries on unchanged.
+    pub unfocused_interval_ms: Option<u32>,
+    /// `[width, height]` the window opens at. The size it was last closed
+    /// at is saved here.
+    pub window_size: Option<[u32; 2]>,
+    /// `[width, height]` the window can't be shrunk below.
+    pub min_window_size: Option<[u32; 2]>,
+    /// Open as a borderless, translucent window above the others; see
+    /// `--overlay`.
+    pub overlay: bool,
+    /// Keep the window above the others.
+    pub always_on_top: bool,
+    /// How much history every chart keeps, unless the chart sets its own.
+    pub window_secs: u32,
+    pub chart_height: f32,
+    /// Chart tiles per row, 1 to 4.
+    pub columns: u32,
+    /// Slide the x-axis continuously with the clock instead of jumping on
+    /// every sample. Redraws every frame, so it costs noticeably more CPU.
+    pub smooth_scroll: bool,
+    /// Redraw every chart on each sample, so charts sampled at different
+    /// rates scroll in step. Otherwise only charts with new samples are
+    /// redrawn, and a slower chart's axis lags until its next sample.
+    pub redraw_all_charts: bool,
+    /// Cheaper chart rendering for slow machines: fewer gridlines, no area
+    /// fill and at most `LOW_QUALITY_POINTS` points per trace.
+    pub low_quality: bool,
+    /// Color stops used by charts with `heat_gradient` enabled.
+    pub gradient: Gradient,
+    /// Per-chart settings, keyed by metric id (`usage`, `freq`, `temp`, `watts`,
+    /// `headroom`).
+    pub charts: HashMap<String, ChartConfig>,
+    /// Order of the chart tiles by metric key, left to right and top to
+    /// bottom.
+    pub layout: Option<Vec<String>>,
+    /// Charts computed from other metrics, as `[[derived]]` tables.
+    pub derived: Vec<DerivedConfig>,
+    /// Disk fill percentage from which its capacity bar turns red.
+    pub disk_warning_percent: f32,
+    /// Weight of the newest reading in the average smoothing the RAPL
+    /// wattage, 0.01 to 1 (no smoothing).
+    pub power_smoothing_alpha: f32,
+    /// Round-trip time in ms above which the `--ping` latency chart turns
+    /// red, unless the chart sets a `threshold` of its own.
+    pub ping_threshold_ms: u32,
+    /// Lower edges in MHz of the bins of the frequency residency view.
+    pub frequency_bins: Vec<u64>,
+    /// Length of the stress ramp, from one busy thread up to one per core.
+    pub stress_ramp_secs: u32,
+    /// Names of the processes whose start and exit are logged as events,
+    /// as `/proc/<pid>/stat` has them (at most 15 characters), e.g.
+    /// `["make", "steam"]`. None by default, which keeps the log for
+    /// events that matter.
+    pub watch_processes: Vec<String>,
+    /// Prefixes of memory and disk sizes: `binary` (GiB, 2^30) or `si`
+    /// (GB, 10^9).
+    pub byte_units: ByteUnits,
+    /// Language of the UI, e.g. `de`; unset means the one of `LANG`.
+    /// Takes effect on the next start.
+    pub language: Option<String>,
+    /// Set for a config loaded from a state dump, which must never be
+    /// written over the user's own.
+    #[serde(skip)]
+    pub offline: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: "dark".to_string(),
+            palettes: HashMap::new(),
+            sample_interval_ms: 500,
+            manual_sampling: false,
+            scale_usage_to_quota: None,
+            unfocused_interval_ms: None,
+            window_size: None,
+            min_window_size: None,
+            overlay: false,
+            always_on_top: false,
+            window_secs: 60,
+            chart_height: 300.0,
+            columns: 2,
+            smooth_scroll: false,
+            redraw_all_charts: false,
+            low_quality: false,
+            gradient: Gradient::default(),
+            charts: HashMap::new(),
+            layout: None,
+            derived: Vec::new(),
+            disk_warning_percent: 90.0,
+            power_smoothing_alpha: 0.3,
+            ping_threshold_ms: 100,
+            frequency_bins: vec![400, 800, 1600, 2400, 3200, 4000, 5000],
+            stress_ramp_secs: 30,
+            watch_processes: Vec::new(),
+            byte_units: ByteUnits::Binary,
+            language: None,
+            offline: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ByteUnits {
+    Binary,
+    Si,
+}
+
+impl ByteUnits {
+    /// `bytes` in the largest unit that keeps it at 1 or more, e.g.
+    /// `1.5 GiB` or `1.6 GB`.
+    pub fn format(self, bytes: f64) -> String {
+        let (base, units) = match self {
+            ByteUnits::Binary => (1024.0, ["B", "KiB", "MiB", "GiB", "TiB", "PiB"]),
+            ByteUnits::Si => (1000.0, ["B", "kB", "MB", "GB", "TB", "PB"]),
+        };
+        let mut value = bytes;
+        let mut unit = 0;
+        while value.abs() >= base && unit < units.len() - 1 {
+            value /= base;
+            unit += 1;
+        }
+        if unit == 0 {
+            format!("{:.0} B", value)
+        } else {
+            format!("{:.1} {}", value, units[unit])
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TraceStyle {
+    #[default]
+    Area,
+    LineOnly,
+}
+
+/// A chart computed from other metrics, e.g.
+/// `name = "efficiency"`, `expr = "freq * usage / 100 / watts"`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DerivedConfig {
+    /// Title of the chart and its key in `charts` and `layout`.
+    pub name: String,
+    /// Arithmetic over built-in metric keys (`usage`, `freq`, `temp`,
+    /// `watts`, `headroom`, `dtemp`, `ipc`) and numbers.
+    pub expr: String,
+    pub unit: String,
+    /// Range of the y-axis until something raises it.
+    pub max: i32,
+    pub min: i32,
+}
+
+impl Default for DerivedConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            expr: String::new(),
+            unit: String::new(),
+            max: 100,
+            min: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChartConfig {
+    /// Color the trace by value instead of a flat fill.
+    pub heat_gradient: bool,
+    /// `area` fills under the trace, `line-only` draws just the line,
+    /// which reads better for jumpy metrics like the frequency.
+    pub style: TraceStyle,
+    /// Line color override; by default it comes from the theme's palette.
+    pub color: Option<[u8; 3]>,
+    /// Value at which the title turns red and an event is logged, in the
+    /// metric's unit, e.g. ms for the latency.
+    pub threshold: Option<f64>,
+    /// How far below `threshold` the value must drop to count as back
+    /// under it, so a value hovering at the limit doesn't flicker.
+    pub hysteresis: f64,
+    /// How long the value must stay over (or back under) the threshold
+    /// before the chart's state changes.
+    pub sustain_ms: u32,
+    /// Weight of the newest sample in an exponentially weighted moving
+    /// average; unset means no smoothing.
+    pub ewma_alpha: Option<f32>,
+    /// Logarithmic y-axis, for metrics spanning several orders of magnitude.
+    pub log_scale: bool,
+    /// Show a gauge of the current value instead of the history chart.
+    pub gauge: bool,
+    /// Decimals shown in the title and axis labels.
+    pub precision: usize,
+    /// History kept and shown by this chart; defaults to `window_secs`.
+    pub window_secs: Option<u32>,
+    /// Sample rate of this chart; defaults to `sample_interval_ms`.
+    pub sample_interval_ms: Option<u32>,
+    /// Leave the chart out of every view.
+    pub hidden: bool,
+    /// Fit the y-axis to the data in the window instead of the metric's
+    /// fixed range. Defaults to whether the metric has one, which the
+    /// kernel's rates don't.
+    pub auto_scale: Option<bool>,
+    /// Keep samples below zero instead of flooring them at 0, which hides
+    /// counter timing glitches. Defaults to whether the metric can go
+    /// negative, like the temperature rate.
+    pub allow_negative: Option<bool>,
+    /// Shade the background green, yellow and red, split at these two
+    /// values in the metric's unit, e.g. `bands = [50, 80]` on the usage
+    /// chart.
+    pub bands: Option<[f64; 2]>,
+    /// Unit values are shown in, e.g. `GHz` for the frequency chart. The
+    /// samples themselves stay in the metric's unit.
+    pub unit: Option<Unit>,
+    /// Tile title, with `{label}`, `{value}`, `{unit}` and the package's
+    /// `{tdp}` in W filled in; defaults to `{label}: {value}{unit}`, and
+    /// to `{label}: {value}{unit} of {tdp} W` for the TDP headroom.
+    pub title: Option<String>,
+    /// Sample this instead of the metric's own source, e.g.
+    /// `source = { core_freq = 0 }` to chart the first core's frequency
+    /// rather than the mean, `{ package_freq = 0 }` for the first
+    /// package's effective frequency (with the `aperf` feature), or
+    /// `"gpu_temp"` on the temperature chart for the hottest AMD GPU.
+    /// Built-in charts only.
+    pub source: Option<SampleSource>,
+    /// `[width, height]` of the window of its own the chart was popped out
+    /// into, which opens again on the next start; the main window leaves
+    /// the chart out meanwhile.
+    pub popped_out: Option<[u32; 2]>,
+}
+
+/// Color stops over the fraction of a chart's range, e.g.
+/// `stops = [[0.0, [0, 200, 0]], [1.0, [240, 0, 0]]]`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Gradient {
+    pub stops: Vec<(f32, [u8; 3])>,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self {
+            stops: vec![(0.0, [0, 200, 0]), (0.6, [240, 200, 0]), (1.0, [240, 0, 0])],
+        }
+    }
+}
+
+impl Gradient {
+    /// Interpolates the color at `fraction` (0.0..=1.0) of the range.
+    pub fn color_at(&self, fraction: f32) -> RGBColor {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let Some(first) = self.stops.first() else {
+            return RGBColor(0, 175, 255);
+        };
+
+        let mut lower = first;
+        for upper in &self.stops {
+            if fraction <= upper.0 {
+                let span = upper.0 - lower.0;
+                let t = if span > 0.0 {
+                    (fraction - lower.0) / span
+                } else {
+                    1.0
+                };
+                let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+                return RGBColor(
+                    mix(lower.1[0], upper.1[0]),
+                    mix(lower.1[1], upper.1[1]),
+                    mix(lower.1[2], upper.1[2]),
+                );
+            }
+            lower = upper;
+        }
+
+        RGBColor(lower.1[0], lower.1[1], lower.1[2])
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .filter(|p| p.is_absolute())
+            .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+        Some(config_home.join("monty").join("config.toml"))
+    }
+
+    /// Loads the config file, treating a missing file as all defaults.
+    pub fn load() -> EyreResult<Self> {
+        let Some(path) = Config::path() else {
+            return Ok(Config::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text)
+                .wrap_err_with(|| format!("invalid config file {}", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e).wrap_err_with(|| format!("failed to read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self) -> EyreResult<()> {
+        let Some(path) = Config::path().filter(|_| !self.offline) else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, toml::to_string_pretty(self)?)
+            .wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+
+    /// The configured palette, falling back to the default for an unknown
+    /// name.
+    pub fn palette(&self) -> ChartPalette {
+        self.palettes
+            .get(&self.theme)
+            .copied()
+            .or_else(|| ChartPalette::preset(&self.theme))
+            .unwrap_or_default()
+    }
+
+    pub fn theme(&self) -> Theme {
+        self.palette().theme(&self.theme)
+    }
+
+    /// Every palette the config can name, presets first.
+    pub fn theme_names(&self) -> Vec<String> {
+        let mut custom: Vec<_> = self
+            .palettes
+            .keys()
+            .filter(|name| ChartPalette::preset(name).is_none())
+            .cloned()
+            .collect();
+        custom.sort();
+        PRESETS
+            .iter()
+            .map(|(name, _)| name.to_string())
+            .chain(custom)
+            .collect()
+    }
+
+    /// The configured tile order, without hidden charts. Duplicates and
+    /// unknown keys are dropped and charts the config doesn't mention (e.g.
+    /// ones added since it was saved) are appended in registry order.
+    pub fn layout(&self) -> Vec<ChartId> {
+        let mut layout = Vec::new();
+        let configured = self
+            .layout
+            .iter()
+            .flatten()
+            .filter_map(|key| metrics::id(key));
+        for id in configured.chain(metrics::ids()) {
+            if !layout.contains(&id) && !self.chart(id).hidden {
+                layout.push(id);
+            }
+        }
+        layout
+    }
+
+    pub fn chart(&self, id: ChartId) -> ChartConfig {
+        self.charts.get(id.key()).cloned().unwrap_or_default()
+    }
+
+    pub fn chart_mut(&mut self, id: ChartId) -> &mut ChartConfig {
+        self.charts.entry(id.key().to_string()).or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_bytes_in_either_unit_system() {
+        assert_eq!(ByteUnits::Binary.format(512.0), "512 B");
+        assert_eq!(ByteUnits::Binary.format(1536.0), "1.5 KiB");
+        assert_eq!(ByteUnits::Binary.format(1e9), "953.7 MiB");
+        assert_eq!(
+            ByteUnits::Binary.format(1.5 * (1u64 << 30) as f64),
+            "1.5 GiB"
+        );
+        assert_eq!(ByteUnits::Si.format(1e9), "1.0 GB");
+        assert_eq!(ByteUnits::Si.format(999.0), "999 B");
+        assert_eq!(ByteUnits::Si.format(2.5e15), "2.5 PB");
+    }
+
+    #[test]
+    fn themes_name_presets_or_user_palettes() {
+        let config: Config = toml::from_str(
+            r#"
+            theme = "mine"
+            [palettes.mine]
+            background = [0, 0, 0]
+            text = [200, 200, 200]
+            primary = [1, 2, 3]
+            success = [0, 255, 0]
+            warning = [255, 255, 0]
+            danger = [255, 0, 0]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.palette().primary, [1, 2, 3]);
+        assert_eq!(
+            config.theme_names().last().map(String::as_str),
+            Some("mine")
+        );
+
+        let light: Config = toml::from_str(r#"theme = "light""#).unwrap();
+        assert_eq!(light.palette(), ChartPalette::preset("light").unwrap());
+        let unknown: Config = toml::from_str(r#"theme = "nope""#).unwrap();
+        assert_eq!(unknown.palette(), ChartPalette::default());
+    }
+
+    #[test]
+    fn charts_are_filled_unless_set_to_line_only() {
+        let config: Config = toml::from_str(
+            r#"
+            [charts.freq]
+            style = "line-only"
+            [charts.temp]
+            threshold = 90
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.chart(ChartId::Freq).style, TraceStyle::LineOnly);
+        assert_eq!(config.chart(ChartId::Temp).style, TraceStyle::Area);
+        assert_eq!(config.chart(ChartId::Usage).style, TraceStyle::Area);
+    }
+
+    #[test]
+    fn charts_can_be_bound_to_another_source() {
+        let config: Config = toml::from_str(
+            r#"
+            [charts.freq]
+            source = { core_freq = 0 }
+            [charts.temp]
+            source = "package_temp"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.chart(ChartId::Freq).source,
+            Some(SampleSource::CoreFreq(0))
+        );
+        assert_eq!(
+            config.chart(ChartId::Temp).source,
+            Some(SampleSource::PackageTemp)
+        );
+        assert_eq!(config.chart(ChartId::Usage).source, None);
+    }
+}
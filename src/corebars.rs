@@ -0,0 +1,107 @@
+use iced::{
+    widget::canvas::{Cache, Frame, Geometry},
+    Element, Length, Size,
+};
+use plotters::{coord::Shift, prelude::*};
+use plotters_iced::{Chart, ChartWidget, DrawingBackend, Renderer};
+
+use crate::{colors::ChartPalette, config::Gradient, Message};
+
+/// The classic CPU meter: one bar per logical CPU at its current usage.
+pub struct CoreBars {
+    cache: Cache,
+    usages: Vec<f32>,
+    gradient: Gradient,
+    palette: ChartPalette,
+}
+
+impl CoreBars {
+    pub fn new(gradient: Gradient) -> Self {
+        Self {
+            cache: Cache::new(),
+            usages: Vec::new(),
+            gradient,
+            palette: ChartPalette::default(),
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        self.cache.clear();
+    }
+
+    pub fn push_data(&mut self, usages: impl Iterator<Item = f32>) {
+        self.usages = usages.collect();
+        self.cache.clear();
+    }
+
+    pub fn view(&self, height: f32) -> Element<'_, Message> {
+        ChartWidget::new(self)
+            .width(Length::Fill)
+            .height(Length::Fixed(height))
+            .into()
+    }
+}
+
+impl Chart<Message> for CoreBars {
+    type State = ();
+
+    #[inline]
+    fn draw<R: Renderer, F: Fn(&mut Frame)>(
+        &self,
+        renderer: &R,
+        bounds: Size,
+        draw_fn: F,
+    ) -> Geometry {
+        renderer.draw_cache(&self.cache, bounds, draw_fn)
+    }
+
+    fn draw_chart<DB: DrawingBackend>(&self, state: &Self::State, root: DrawingArea<DB, Shift>) {
+        self.build_chart(state, ChartBuilder::on(&root));
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut chart: ChartBuilder<DB>) {
+        let core_count = self.usages.len() as i32;
+
+        let mut chart = chart
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .margin(20)
+            .build_cartesian_2d((0..core_count).into_segmented(), 0.0..100.0)
+            .expect("failed to build chart");
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .bold_line_style(self.palette.grid())
+            .light_line_style(self.palette.fine_grid())
+            .axis_style(ShapeStyle::from(self.palette.axis()).stroke_width(1))
+            .x_labels(self.usages.len().min(32))
+            .x_label_style(("sans-serif", 13).into_font().color(&self.palette.label()))
+            .x_label_formatter(&|core| match core {
+                SegmentValue::CenterOf(core) => core.to_string(),
+                _ => String::new(),
+            })
+            .y_labels(5)
+            .y_label_style(("sans-serif", 13).into_font().color(&self.palette.label()))
+            .y_label_formatter(&|usage| format!("{:.0}%", usage))
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        chart
+            .draw_series(self.usages.iter().enumerate().map(|(core, usage)| {
+                let core = core as i32;
+                let color = self.gradient.color_at(usage / 100.0);
+                let mut bar = Rectangle::new(
+                    [
+                        (SegmentValue::Exact(core), 0.0),
+                        (SegmentValue::Exact(core + 1), *usage as f64),
+                    ],
+                    color.filled(),
+                );
+                bar.set_margin(0, 0, 2, 2);
+                bar
+            }))
+            .expect("failed to draw chart data");
+    }
+}
@@ -0,0 +1,247 @@
+//! The `org.monty.Monitor` service on the session bus, for KDE plasmoids,
+//! GNOME extensions and the like: the headline readings as properties at
+//! `/org/monty/Monitor`, announced with `PropertiesChanged` as they change,
+//! and a `ThresholdExceeded(metric, threshold, peak, seconds)` signal for
+//! every alert the alerts panel logs. Built with the `dbus` feature.
+
+use std::future::Future;
+
+use tokio::sync::mpsc;
+use zbus::{connection, interface, SignalContext};
+
+pub const NAME: &str = "org.monty.Monitor";
+pub const PATH: &str = "/org/monty/Monitor";
+
+/// One sample's readings, in the metrics' own units; unset for a chart
+/// without a sample, which keeps its property's last value.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Readings {
+    pub cpu_usage: Option<f64>,
+    pub frequency_mhz: Option<f64>,
+    pub temperature_c: Option<f64>,
+    pub power_w: Option<f64>,
+}
+
+/// A threshold excursion that ended, as logged in the alerts panel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    /// The chart's config key, e.g. `temp`.
+    pub metric: String,
+    pub threshold: f64,
+    pub peak: f64,
+    pub seconds: i64,
+}
+
+enum Update {
+    Readings(Readings),
+    Alert(Alert),
+}
+
+pub struct DbusService {
+    updates: mpsc::UnboundedSender<Update>,
+}
+
+impl DbusService {
+    /// Connects to the session bus and claims `org.monty.Monitor`. The
+    /// service is run by the returned future, which must run on the tokio
+    /// runtime and only returns on an error or once this is dropped.
+    pub fn session() -> zbus::Result<(Self, impl Future<Output = zbus::Result<()>>)> {
+        Ok(Self::serve(connection::Builder::session()?.name(NAME)?))
+    }
+
+    fn serve(
+        builder: connection::Builder<'static>,
+    ) -> (Self, impl Future<Output = zbus::Result<()>>) {
+        let (updates, mut received) = mpsc::unbounded_channel();
+        let serve = async move {
+            let connection = builder.serve_at(PATH, Monitor::default())?.build().await?;
+            let monitor = connection
+                .object_server()
+                .interface::<_, Monitor>(PATH)
+                .await?;
+            while let Some(update) = received.recv().await {
+                let context = monitor.signal_context();
+                match update {
+                    Update::Readings(readings) => {
+                        monitor.get_mut().await.apply(readings, context).await?;
+                    }
+                    Update::Alert(alert) => {
+                        Monitor::threshold_exceeded(
+                            context,
+                            &alert.metric,
+                            alert.threshold,
+                            alert.peak,
+                            alert.seconds,
+                        )
+                        .await?;
+                    }
+                }
+            }
+            Ok(())
+        };
+        (Self { updates }, serve)
+    }
+
+    pub fn publish(&self, readings: Readings) {
+        // Once the service stopped, monty has been told why.
+        let _ = self.updates.send(Update::Readings(readings));
+    }
+
+    pub fn alert(&self, alert: Alert) {
+        let _ = self.updates.send(Update::Alert(alert));
+    }
+}
+
+#[derive(Default)]
+struct Monitor {
+    cpu_usage: f64,
+    frequency_mhz: f64,
+    temperature_c: f64,
+    power_w: f64,
+}
+
+impl Monitor {
+    /// Takes the new readings, announcing the properties that changed.
+    async fn apply(&mut self, readings: Readings, context: &SignalContext<'_>) -> zbus::Result<()> {
+        if let Some(value) = readings.cpu_usage.filter(|value| *value != self.cpu_usage) {
+            self.cpu_usage = value;
+            self.cpu_usage_changed(context).await?;
+        }
+        if let Some(value) = readings
+            .frequency_mhz
+            .filter(|value| *value != self.frequency_mhz)
+        {
+            self.frequency_mhz = value;
+            self.frequency_mhz_changed(context).await?;
+        }
+        if let Some(value) = readings
+            .temperature_c
+            .filter(|value| *value != self.temperature_c)
+        {
+            self.temperature_c = value;
+            self.temperature_c_changed(context).await?;
+        }
+        if let Some(value) = readings.power_w.filter(|value| *value != self.power_w) {
+            self.power_w = value;
+            self.power_w_changed(context).await?;
+        }
+        Ok(())
+    }
+}
+
+#[interface(name = "org.monty.Monitor")]
+impl Monitor {
+    /// Usage of all CPUs in %.
+    #[zbus(property)]
+    fn cpu_usage(&self) -> f64 {
+        self.cpu_usage
+    }
+
+    /// Mean CPU frequency in MHz.
+    #[zbus(property)]
+    fn frequency_mhz(&self) -> f64 {
+        self.frequency_mhz
+    }
+
+    /// Package temperature in °C.
+    #[zbus(property)]
+    fn temperature_c(&self) -> f64 {
+        self.temperature_c
+    }
+
+    /// Package power in W.
+    #[zbus(property)]
+    fn power_w(&self) -> f64 {
+        self.power_w
+    }
+
+    /// A chart was over its threshold for `seconds`, peaking at `peak`.
+    #[zbus(signal)]
+    async fn threshold_exceeded(
+        context: &SignalContext<'_>,
+        metric: &str,
+        threshold: f64,
+        peak: f64,
+        seconds: i64,
+    ) -> zbus::Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::futures::StreamExt;
+    use tokio::net::UnixStream;
+    use zbus::Guid;
+
+    use super::*;
+
+    #[zbus::proxy(
+        interface = "org.monty.Monitor",
+        default_service = "org.monty.Monitor",
+        default_path = "/org/monty/Monitor"
+    )]
+    trait Client {
+        #[zbus(property)]
+        fn cpu_usage(&self) -> zbus::Result<f64>;
+
+        #[zbus(signal)]
+        fn threshold_exceeded(
+            &self,
+            metric: &str,
+            threshold: f64,
+            peak: f64,
+            seconds: i64,
+        ) -> zbus::Result<()>;
+    }
+
+    /// Serves the interface over a private peer-to-peer connection, as on
+    /// a bus of its own.
+    #[test]
+    fn clients_see_property_changes_and_alerts() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (server, client) = UnixStream::pair().unwrap();
+            let (service, serve) = DbusService::serve(
+                connection::Builder::unix_stream(server)
+                    .server(Guid::generate())
+                    .unwrap()
+                    .p2p(),
+            );
+            let serve = tokio::spawn(serve);
+            let client = connection::Builder::unix_stream(client)
+                .p2p()
+                .build()
+                .await
+                .unwrap();
+            let proxy = ClientProxy::new(&client).await.unwrap();
+            let mut usage = proxy.receive_cpu_usage_changed().await;
+            let mut alerts = proxy.receive_threshold_exceeded().await.unwrap();
+
+            service.publish(Readings {
+                cpu_usage: Some(42.5),
+                ..Readings::default()
+            });
+            while let Some(change) = usage.next().await {
+                if change.get().await.unwrap() == 42.5 {
+                    break;
+                }
+            }
+            assert_eq!(proxy.cpu_usage().await.unwrap(), 42.5);
+
+            service.alert(Alert {
+                metric: "temp".to_string(),
+                threshold: 95.0,
+                peak: 99.0,
+                seconds: 4,
+            });
+            let alert = alerts.next().await.unwrap();
+            let args = alert.args().unwrap();
+            assert_eq!(
+                (args.metric, args.threshold, args.peak, args.seconds),
+                ("temp", 95.0, 99.0, 4)
+            );
+
+            drop(service);
+            serve.await.unwrap().unwrap();
+        });
+    }
+}
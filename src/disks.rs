@@ -0,0 +1,78 @@
+use std::time::{Duration, Instant};
+
+use iced::{
+    theme,
+    widget::{Column, ProgressBar, Text},
+    Element, Length,
+};
+use sysinfo::Disks;
+
+use crate::{config::ByteUnits, Message};
+
+/// Capacity barely moves, so there's no point listing mounts every sample.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Used space of every mounted filesystem, as one bar each.
+pub struct DiskPanel {
+    disks: Disks,
+    last_refresh: Instant,
+    /// Fill percentage from which a bar turns red.
+    pub warning_percent: f32,
+    pub byte_units: ByteUnits,
+}
+
+impl DiskPanel {
+    pub fn new() -> Self {
+        Self {
+            disks: Disks::new_with_refreshed_list(),
+            last_refresh: Instant::now(),
+            warning_percent: 90.0,
+            byte_units: ByteUnits::Binary,
+        }
+    }
+
+    /// Re-reads the mount list and usage if they're due.
+    pub fn update(&mut self) {
+        if self.last_refresh.elapsed() < REFRESH_INTERVAL {
+            return;
+        }
+        self.last_refresh = Instant::now();
+        self.disks.refresh_list();
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let col = Column::new()
+            .spacing(10)
+            .width(Length::Fill)
+            .push(Text::new("Disks").size(24));
+
+        self.disks
+            .iter()
+            .filter(|disk| disk.total_space() > 0)
+            .fold(col, |col, disk| {
+                let total = disk.total_space();
+                let used = total.saturating_sub(disk.available_space());
+                let percent = used as f32 / total as f32 * 100.0;
+                let style = if percent >= self.warning_percent {
+                    theme::ProgressBar::Danger
+                } else {
+                    theme::ProgressBar::Primary
+                };
+
+                col.push(Text::new(format!(
+                    "{} ({}): {} / {} ({:.0}%)",
+                    disk.mount_point().display(),
+                    disk.file_system().to_string_lossy(),
+                    self.byte_units.format(used as f64),
+                    self.byte_units.format(total as f64),
+                    percent
+                )))
+                .push(
+                    ProgressBar::new(0.0..=100.0, percent)
+                        .height(Length::Fixed(20.0))
+                        .style(style),
+                )
+            })
+            .into()
+    }
+}
@@ -0,0 +1,368 @@
+//! `--dump-state` and `--load-state`: everything needed to see what a bug
+//! reporter saw, as one JSON file. The structs here mirror the app's own
+//! rather than serializing them directly, so the format only changes on
+//! purpose, along with `SCHEMA_VERSION`.
+
+use std::{collections::VecDeque, fs, path::Path};
+
+use chrono::{DateTime, Local, Utc};
+use color_eyre::eyre::{bail, Result as EyreResult, WrapErr};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    check::Probe,
+    clock::{self, Time},
+    config::Config,
+    events::{EventLog, MontyEvent, Severity},
+    history, metrics,
+};
+
+/// Bumped on every change a dump from an older monty can't be read with.
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct StateDump {
+    pub schema_version: u32,
+    pub build: BuildInfo,
+    /// When the dump was taken, in milliseconds since the epoch.
+    pub dumped_at: i64,
+    /// Every data source as `--check` reports it.
+    pub sources: Vec<SourceDump>,
+    /// The config in effect, as the TOML of the config file.
+    pub config: String,
+    pub series: Vec<SeriesDump>,
+    /// The event log, newest first.
+    pub events: Vec<EventDump>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BuildInfo {
+    pub version: String,
+    /// Architecture and OS, e.g. `x86_64-linux`.
+    pub target: String,
+    pub debug: bool,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            target: format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS),
+            debug: cfg!(debug_assertions),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SourceDump {
+    pub source: String,
+    pub available: bool,
+    /// What was found, or why the source is unavailable.
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SeriesDump {
+    /// Metric key, as in the config's `charts` and `layout`.
+    pub key: String,
+    pub unit: String,
+    /// Values are the readings times this.
+    pub scale: i32,
+    /// `[milliseconds since the epoch, value]`, newest first.
+    pub points: Vec<(i64, i32)>,
+    /// `[milliseconds since the epoch, label]` of the markers in the
+    /// chart's window, oldest first.
+    #[serde(default)]
+    pub markers: Vec<(i64, String)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EventDump {
+    /// Milliseconds since the epoch.
+    pub time: i64,
+    pub severity: Severity,
+    pub text: String,
+}
+
+/// Just enough of a dump to refuse one in a format we don't know.
+#[derive(Deserialize)]
+struct Versioned {
+    schema_version: u32,
+}
+
+impl StateDump {
+    pub fn new<'a>(
+        config: &Config,
+        sources: &[Probe],
+        series: impl Iterator<Item = (&'a str, &'a VecDeque<(Time, i32)>, &'a [(Time, String)])>,
+        events: impl Iterator<Item = &'a MontyEvent>,
+    ) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            build: BuildInfo::current(),
+            dumped_at: Utc::now().timestamp_millis(),
+            sources: sources
+                .iter()
+                .map(|probe| {
+                    let (available, detail) = match &probe.result {
+                        Ok(detail) => (true, detail),
+                        Err(reason) => (false, reason),
+                    };
+                    SourceDump {
+                        source: probe.source.to_string(),
+                        available,
+                        detail: detail.clone(),
+                    }
+                })
+                .collect(),
+            config: toml::to_string_pretty(config).unwrap_or_else(|e| format!("# {}", e)),
+            series: series
+                .map(|(key, points, markers)| {
+                    let descriptor = metrics::id(key).map(metrics::descriptor);
+                    SeriesDump {
+                        key: key.to_string(),
+                        unit: descriptor.map_or("", |d| d.unit).trim().to_string(),
+                        scale: descriptor.map_or(1, |d| d.scale),
+                        points: points
+                            .iter()
+                            .map(|(time, value)| (clock::wall(*time).timestamp_millis(), *value))
+                            .collect(),
+                        markers: markers
+                            .iter()
+                            .map(|(time, label)| {
+                                (clock::wall(*time).timestamp_millis(), label.clone())
+                            })
+                            .collect(),
+                    }
+                })
+                .collect(),
+            events: events
+                .map(|event| EventDump {
+                    time: event.time.timestamp_millis(),
+                    severity: event.severity,
+                    text: event.text.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> EyreResult<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .wrap_err_with(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> EyreResult<Self> {
+        let text = fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?;
+        Self::parse(&text).wrap_err_with(|| format!("invalid state dump {}", path.display()))
+    }
+
+    fn parse(text: &str) -> EyreResult<Self> {
+        let Versioned { schema_version } = serde_json::from_str(text)?;
+        if schema_version != SCHEMA_VERSION {
+            bail!(
+                "schema version {} is not supported, only {}",
+                schema_version,
+                SCHEMA_VERSION
+            );
+        }
+        Ok(serde_json::from_str(text)?)
+    }
+
+    /// The config in effect when the dump was taken.
+    pub fn config(&self) -> EyreResult<Config> {
+        toml::from_str(&self.config).wrap_err("invalid config in state dump")
+    }
+
+    /// The sample buffers by metric key, like a history snapshot.
+    pub fn buffers(&self) -> Vec<(String, history::Series)> {
+        self.series
+            .iter()
+            .map(|series| {
+                let points = series
+                    .points
+                    .iter()
+                    .filter_map(|&(ms, value)| {
+                        Some((
+                            clock::from_wall(DateTime::from_timestamp_millis(ms)?),
+                            value,
+                        ))
+                    })
+                    .collect();
+                (series.key.clone(), points)
+            })
+            .collect()
+    }
+
+    /// Logs the dumped events, and the sources as the reporter's
+    /// `--check` saw them.
+    pub fn replay_events(&self, log: &mut EventLog) {
+        let time = |ms| DateTime::from_timestamp_millis(ms).unwrap_or_default();
+        for event in self.events.iter().rev() {
+            log.push_at(time(event.time), event.severity, event.text.clone());
+        }
+        for source in &self.sources {
+            log.push_at(
+                time(self.dumped_at),
+                if source.available {
+                    Severity::Info
+                } else {
+                    Severity::Warning
+                },
+                format!("Source {}: {}", source.source, source.detail),
+            );
+        }
+    }
+
+    /// One line on where the dump came from, for the offline banner.
+    pub fn origin(&self) -> String {
+        let when = DateTime::from_timestamp_millis(self.dumped_at)
+            .map(|time| {
+                time.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        format!(
+            "state dumped by monty {} ({}{}) at {}",
+            self.build.version,
+            self.build.target,
+            if self.build.debug { ", debug" } else { "" },
+            when
+        )
+    }
+}
+
+/// A dump without a running GUI, for `--dump-state`: the buffers are those
+/// of the history snapshot the last session left.
+pub fn from_last_session(sources: &[Probe]) -> StateDump {
+    let mut events = Vec::new();
+    let mut event = |severity, text: String| {
+        events.push(MontyEvent {
+            time: Utc::now(),
+            severity,
+            text,
+        })
+    };
+
+    let config = Config::load().unwrap_or_else(|e| {
+        event(Severity::Warning, format!("{:#}", e));
+        Config::default()
+    });
+    for error in metrics::register_derived(&config.derived) {
+        event(Severity::Warning, error);
+    }
+    let buffers: Vec<_> = match history::snapshot_path().map(|path| history::load(&path)) {
+        Some(Ok(snapshot)) => snapshot
+            .series
+            .into_iter()
+            .map(|(key, points)| (key, VecDeque::from(points)))
+            .collect(),
+        Some(Err(e)) => {
+            event(Severity::Info, format!("No saved history: {}", e));
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+
+    StateDump::new(
+        &config,
+        sources,
+        buffers
+            .iter()
+            .map(|(key, points)| (key.as_str(), points, &[][..])),
+        events.iter().rev(),
+    )
+}
+
+/// A fresh `state-<timestamp>.json` next to the history snapshot.
+pub fn default_path() -> Option<std::path::PathBuf> {
+    let name = format!("state-{}.json", Local::now().format("%Y%m%d-%H%M%S"));
+    Some(history::snapshot_path()?.with_file_name(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    /// The sample time of wall time `at(secs)`, the clock never having
+    /// jumped in the test.
+    fn axis(secs: i64) -> Time {
+        Time::default() + chrono::Duration::seconds(1_700_000_000 + secs)
+    }
+
+    fn dump() -> StateDump {
+        let mut config = Config {
+            window_secs: 120,
+            ..Config::default()
+        };
+        config.chart_mut(crate::ChartId::Temp).threshold = Some(90.0);
+        let usage = VecDeque::from([(axis(1), 40), (axis(0), 35)]);
+        let temp = VecDeque::from([(axis(1), 72)]);
+        let markers = [(axis(0), "build".to_string())];
+        let events = [MontyEvent {
+            time: at(1),
+            severity: Severity::Critical,
+            text: "CPU throttled to minimum frequency".to_string(),
+        }];
+        StateDump::new(
+            &config,
+            &[
+                Probe::new("msr", Ok("readable".to_string())),
+                Probe::new("powercap", Err("missing".to_string())),
+            ],
+            [("usage", &usage, &markers[..]), ("temp", &temp, &[][..])].into_iter(),
+            events.iter(),
+        )
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let dump = dump();
+        let json = serde_json::to_string_pretty(&dump).unwrap();
+        assert_eq!(StateDump::parse(&json).unwrap(), dump);
+
+        assert_eq!(dump.sources[1].detail, "missing");
+        assert!(!dump.sources[1].available);
+        assert_eq!(dump.series[0].unit, "%");
+        assert_eq!(
+            dump.series[0].markers,
+            [(at(0).timestamp_millis(), "build".to_string())]
+        );
+        assert_eq!(dump.events[0].severity, Severity::Critical);
+        assert!(json.contains(r#""severity": "critical""#));
+    }
+
+    #[test]
+    fn restores_the_config_and_buffers() {
+        let dump = dump();
+        let config = dump.config().unwrap();
+        assert_eq!(config.window_secs, 120);
+        assert_eq!(config.chart(crate::ChartId::Temp).threshold, Some(90.0));
+        assert_eq!(
+            dump.buffers(),
+            [
+                ("usage".to_string(), vec![(axis(1), 40), (axis(0), 35)]),
+                ("temp".to_string(), vec![(axis(1), 72)])
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_other_schema_versions() {
+        let mut dump = dump();
+        dump.schema_version = SCHEMA_VERSION + 1;
+        let json = serde_json::to_string(&dump).unwrap();
+        let error = StateDump::parse(&json).unwrap_err().to_string();
+        assert!(
+            error.contains("schema version 2 is not supported"),
+            "{}",
+            error
+        );
+        assert!(StateDump::parse(r#"{"schema_version": 1}"#).is_err());
+    }
+}
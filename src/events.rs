@@ -0,0 +1,475 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Duration, Local, Utc};
+use iced::{
+    widget::{Column, Scrollable, Text},
+    Color, Element, Length,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    clock::Time,
+    colors::{self, ChartPalette},
+    Message,
+};
+
+/// How many events the log keeps before dropping the oldest.
+const EVENT_LOG_CAPACITY: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    fn color(self, palette: &ChartPalette) -> Color {
+        colors::color(match self {
+            Severity::Info => palette.text,
+            Severity::Warning => palette.warning,
+            Severity::Critical => palette.danger,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MontyEvent {
+    /// Wall time, as the log shows it.
+    pub time: DateTime<Utc>,
+    pub severity: Severity,
+    pub text: String,
+}
+
+/// Audit trail of the significant things that happened during a session,
+/// newest first.
+#[derive(Default)]
+pub struct EventLog {
+    events: VecDeque<MontyEvent>,
+}
+
+impl EventLog {
+    pub fn push(&mut self, severity: Severity, text: impl Into<String>) {
+        self.push_at(Utc::now(), severity, text);
+    }
+
+    /// Logs something that started before now, like a finished excursion,
+    /// at wall time `time`.
+    pub fn push_at(&mut self, time: DateTime<Utc>, severity: Severity, text: impl Into<String>) {
+        self.events.push_front(MontyEvent {
+            time,
+            severity,
+            text: text.into(),
+        });
+        self.events.truncate(EVENT_LOG_CAPACITY);
+    }
+
+    /// Newest first.
+    pub fn iter(&self) -> impl Iterator<Item = &MontyEvent> {
+        self.events.iter()
+    }
+
+    pub fn view(&self, palette: &ChartPalette) -> Element<'_, Message> {
+        let lines = self
+            .events
+            .iter()
+            .fold(Column::new().spacing(2), |col, ev| {
+                col.push(
+                    Text::new(format!(
+                        "{}  {}",
+                        ev.time.with_timezone(&Local).format("%H:%M:%S"),
+                        ev.text
+                    ))
+                    .size(14)
+                    .style(ev.severity.color(palette)),
+                )
+            });
+
+        Scrollable::new(lines)
+            .width(Length::Fill)
+            .height(Length::Fixed(160.0))
+            .into()
+    }
+}
+
+/// A stretch of samples at or above a threshold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Excursion {
+    pub start: Time,
+    /// Time of the first sample back below the threshold.
+    pub end: Time,
+    pub peak: i32,
+}
+
+impl Excursion {
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+}
+
+/// When a chart counts as over its threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThresholdRule {
+    /// Samples at or above this are over.
+    pub enter: i32,
+    /// Once over, samples must drop below `enter - hysteresis` to count as
+    /// back under, so a value hovering at the limit doesn't flicker.
+    pub hysteresis: i32,
+    /// How long a crossing must last before the state changes.
+    pub sustain: Duration,
+}
+
+/// A threshold crossing that took effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    /// The new state.
+    pub over: bool,
+    /// Time of the first sample of the crossing, before it was sustained.
+    pub since: Time,
+}
+
+/// Whether a series is over its threshold, with hysteresis and a minimum
+/// sustain time. Title coloring, alerts and the event log all read it
+/// through [`ExcursionTracker`], so they never disagree.
+#[derive(Debug, Default)]
+pub struct ThresholdState {
+    rule: Option<ThresholdRule>,
+    over: bool,
+    /// First sample of a crossing toward the other state that hasn't been
+    /// sustained yet.
+    crossing_since: Option<Time>,
+}
+
+impl ThresholdState {
+    /// Takes effect from the next sample; without a rule nothing is over.
+    pub fn set_rule(&mut self, rule: Option<ThresholdRule>) {
+        self.rule = rule;
+    }
+
+    pub fn rule(&self) -> Option<ThresholdRule> {
+        self.rule
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    /// Whether samples are over the threshold, but not for long enough yet.
+    pub fn entering(&self) -> bool {
+        !self.over && self.crossing_since.is_some()
+    }
+
+    /// Feeds one sample, returning the transition it completed, if any. A
+    /// crossing takes effect at the first sample `sustain` or more after
+    /// it began, however long the gap between the samples; any sample on
+    /// the old side of the threshold in between calls it off.
+    pub fn step(&mut self, value: i32, now: Time) -> Option<Transition> {
+        let Some(rule) = self.rule else {
+            self.crossing_since = None;
+            return std::mem::take(&mut self.over).then_some(Transition {
+                over: false,
+                since: now,
+            });
+        };
+
+        let crossing = if self.over {
+            value < rule.enter - rule.hysteresis
+        } else {
+            value >= rule.enter
+        };
+        if !crossing {
+            self.crossing_since = None;
+            return None;
+        }
+        let since = *self.crossing_since.get_or_insert(now);
+        if now - since < rule.sustain {
+            return None;
+        }
+        self.over = !self.over;
+        self.crossing_since = None;
+        Some(Transition {
+            over: self.over,
+            since,
+        })
+    }
+}
+
+/// Coalesces samples above a threshold into one excursion each, so a
+/// sustained alert is logged once with its duration and peak instead of
+/// once per sample.
+#[derive(Debug, Default)]
+pub struct ExcursionTracker {
+    state: ThresholdState,
+    /// The excursion under way, from its first sample over the threshold;
+    /// tracked while it is still being sustained, but not active yet.
+    current: Option<Excursion>,
+}
+
+impl ExcursionTracker {
+    pub fn set_rule(&mut self, rule: Option<ThresholdRule>) {
+        self.state.set_rule(rule);
+    }
+
+    pub fn rule(&self) -> Option<ThresholdRule> {
+        self.state.rule()
+    }
+
+    /// Feeds one sample, returning the excursion it ended, if any.
+    pub fn observe(&mut self, time: Time, value: i32) -> Option<Excursion> {
+        let transition = self.state.step(value, time);
+        if self.state.is_over() || self.state.entering() {
+            let current = self.current.get_or_insert(Excursion {
+                start: time,
+                end: time,
+                peak: value,
+            });
+            current.end = time;
+            current.peak = current.peak.max(value);
+            return None;
+        }
+        // Either the excursion ended, or it was never sustained.
+        let excursion = self.current.take()?;
+        match transition {
+            Some(Transition { over: false, since }) => Some(Excursion {
+                end: since,
+                ..excursion
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn active(&self) -> Option<&Excursion> {
+        self.current.as_ref().filter(|_| self.state.is_over())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> Time {
+        Time::default() + Duration::seconds(1_700_000_000 + secs)
+    }
+
+    fn rule(enter: i32, hysteresis: i32, sustain_secs: i64) -> Option<ThresholdRule> {
+        Some(ThresholdRule {
+            enter,
+            hysteresis,
+            sustain: Duration::seconds(sustain_secs),
+        })
+    }
+
+    fn tracker(threshold: Option<i32>) -> ExcursionTracker {
+        let mut tracker = ExcursionTracker::default();
+        tracker.set_rule(threshold.and_then(|enter| rule(enter, 0, 0)));
+        tracker
+    }
+
+    fn run(samples: &[i32], threshold: Option<i32>) -> Vec<Excursion> {
+        let mut tracker = tracker(threshold);
+        samples
+            .iter()
+            .enumerate()
+            .filter_map(|(i, value)| tracker.observe(at(i as i64), *value))
+            .collect()
+    }
+
+    /// The state after each of `samples`, taken at the given seconds.
+    fn states(rule: Option<ThresholdRule>, samples: &[(i64, i32)]) -> Vec<bool> {
+        let mut state = ThresholdState::default();
+        state.set_rule(rule);
+        samples
+            .iter()
+            .map(|&(secs, value)| {
+                state.step(value, at(secs));
+                state.is_over()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn below_threshold_never_fires() {
+        assert!(run(&[10, 20, 30, 20], Some(90)).is_empty());
+    }
+
+    #[test]
+    fn sustained_excursion_is_one_event() {
+        let excursions = run(&[80, 95, 97, 99, 96, 80], Some(95));
+        assert_eq!(
+            excursions,
+            vec![Excursion {
+                start: at(1),
+                end: at(5),
+                peak: 99
+            }]
+        );
+        assert_eq!(excursions[0].duration(), Duration::seconds(4));
+    }
+
+    #[test]
+    fn repeated_crossings_are_separate_events() {
+        let excursions = run(&[96, 80, 97, 98, 80, 80, 99, 70], Some(95));
+        let spans: Vec<_> = excursions
+            .iter()
+            .map(|e| (e.start, e.end, e.peak))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![(at(0), at(1), 96), (at(2), at(4), 98), (at(6), at(7), 99)]
+        );
+    }
+
+    #[test]
+    fn value_equal_to_threshold_counts_as_over() {
+        assert_eq!(run(&[95, 94], Some(95)).len(), 1);
+    }
+
+    #[test]
+    fn unfinished_excursion_stays_active() {
+        let mut tracker = tracker(Some(95));
+        assert_eq!(tracker.observe(at(0), 99), None);
+        assert_eq!(tracker.observe(at(1), 100), None);
+        assert_eq!(tracker.active().map(|e| e.peak), Some(100));
+    }
+
+    #[test]
+    fn removing_the_threshold_ends_the_excursion() {
+        let mut tracker = tracker(Some(95));
+        tracker.observe(at(0), 99);
+        tracker.set_rule(None);
+        assert!(tracker.observe(at(1), 99).is_some());
+        assert!(tracker.active().is_none());
+    }
+
+    #[test]
+    fn without_hysteresis_oscillation_at_the_boundary_flips_every_sample() {
+        assert_eq!(
+            states(rule(95, 0, 0), &[(0, 95), (1, 94), (2, 95), (3, 94)]),
+            [true, false, true, false]
+        );
+    }
+
+    #[test]
+    fn hysteresis_holds_the_state_through_oscillation_at_the_boundary() {
+        let samples = [
+            (0, 95),
+            (1, 94),
+            (2, 95),
+            (3, 92),
+            (4, 94),
+            (5, 91),
+            (6, 94),
+        ];
+        assert_eq!(
+            states(rule(95, 3, 0), &samples),
+            [true, true, true, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn exit_needs_to_drop_below_the_hysteresis_band() {
+        // 92 is exactly `enter - hysteresis`, still inside the band.
+        assert_eq!(
+            states(rule(95, 3, 0), &[(0, 96), (1, 92), (2, 91)]),
+            [true, true, false]
+        );
+    }
+
+    #[test]
+    fn crossings_must_be_sustained() {
+        assert_eq!(
+            states(
+                rule(95, 0, 3),
+                &[
+                    (0, 99),
+                    (1, 99),
+                    (2, 99),
+                    (3, 99),
+                    (4, 80),
+                    (5, 80),
+                    (6, 99),
+                    (7, 80)
+                ]
+            ),
+            [false, false, false, true, true, true, true, true]
+        );
+        // Bouncing back before the sustain time calls the crossing off.
+        assert_eq!(
+            states(
+                rule(95, 0, 2),
+                &[(0, 99), (1, 99), (2, 80), (3, 99), (4, 99), (5, 99)]
+            ),
+            [false, false, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn sustain_counts_time_not_samples_across_long_gaps() {
+        // One sample before and one after a long gap is enough.
+        assert_eq!(states(rule(95, 0, 5), &[(0, 99), (60, 99)]), [false, true]);
+        // A lone sample over the threshold never is.
+        assert_eq!(states(rule(95, 0, 5), &[(0, 99), (60, 80)]), [false, false]);
+        // Leaving takes the same sustain time as entering.
+        assert_eq!(
+            states(rule(95, 0, 5), &[(0, 99), (5, 99), (6, 80), (100, 80)]),
+            [false, true, true, false]
+        );
+    }
+
+    #[test]
+    fn transitions_report_when_the_crossing_began() {
+        let mut state = ThresholdState::default();
+        state.set_rule(rule(95, 2, 2));
+        assert_eq!(state.step(99, at(0)), None);
+        assert!(state.entering());
+        assert_eq!(
+            state.step(96, at(2)),
+            Some(Transition {
+                over: true,
+                since: at(0)
+            })
+        );
+        assert_eq!(state.step(90, at(3)), None);
+        assert_eq!(
+            state.step(92, at(5)),
+            Some(Transition {
+                over: false,
+                since: at(3)
+            })
+        );
+        assert_eq!(state.step(92, at(6)), None);
+    }
+
+    #[test]
+    fn excursions_span_the_whole_sustained_crossing() {
+        let mut tracker = ExcursionTracker::default();
+        tracker.set_rule(rule(95, 5, 2));
+        let finished: Vec<_> = [99, 96, 97, 91, 97, 85, 80, 80]
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, value)| {
+                let finished = tracker.observe(at(i as i64), value);
+                assert_eq!(tracker.active().is_some(), (2..7).contains(&i), "at {}", i);
+                finished
+            })
+            .collect();
+        assert_eq!(
+            finished,
+            [Excursion {
+                start: at(0),
+                end: at(5),
+                peak: 99
+            }]
+        );
+
+        // A crossing that isn't sustained is no excursion at all.
+        let mut tracker = ExcursionTracker::default();
+        tracker.set_rule(rule(95, 0, 2));
+        assert_eq!(tracker.observe(at(0), 99), None);
+        assert!(tracker.active().is_none());
+        assert_eq!(tracker.observe(at(1), 80), None);
+        assert_eq!(tracker.observe(at(2), 80), None);
+    }
+}
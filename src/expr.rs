@@ -0,0 +1,291 @@
+//! Arithmetic over metric readings for the derived charts of the config,
+//! like `freq * usage / 100 / watts`: numbers, metric keys, `+ - * /`,
+//! unary minus and parentheses, with the usual precedence.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Metric(String),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Byte offset into the expression.
+    pub at: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at column {}", self.message, self.at + 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+impl Expr {
+    /// Parses `source`, accepting only identifiers `known` says are metrics.
+    pub fn parse(source: &str, known: impl Fn(&str) -> bool) -> Result<Expr, ParseError> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            end: source.len(),
+            known: &known,
+        };
+        let expr = parser.sum()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(expr),
+            Some((at, _)) => Err(ParseError {
+                at: *at,
+                message: "expected an operator".to_string(),
+            }),
+        }
+    }
+
+    /// The value for the given readings; `None` for a missing reading or a
+    /// division by zero, which leaves a gap in the chart.
+    pub fn eval(&self, reading: &impl Fn(&str) -> Option<f64>) -> Option<f64> {
+        match self {
+            Expr::Number(value) => Some(*value),
+            Expr::Metric(key) => reading(key),
+            Expr::Neg(expr) => expr.eval(reading).map(|value| -value),
+            Expr::Binary(lhs, op, rhs) => {
+                let (lhs, rhs) = (lhs.eval(reading)?, rhs.eval(reading)?);
+                match op {
+                    Op::Add => Some(lhs + rhs),
+                    Op::Sub => Some(lhs - rhs),
+                    Op::Mul => Some(lhs * rhs),
+                    Op::Div if rhs == 0.0 => None,
+                    Op::Div => Some(lhs / rhs),
+                }
+            }
+        }
+    }
+
+    /// Every metric the expression reads.
+    pub fn metrics(&self) -> Vec<&str> {
+        match self {
+            Expr::Number(_) => Vec::new(),
+            Expr::Metric(key) => vec![key.as_str()],
+            Expr::Neg(expr) => expr.metrics(),
+            Expr::Binary(lhs, _, rhs) => {
+                let mut metrics = lhs.metrics();
+                metrics.extend(rhs.metrics());
+                metrics
+            }
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(at, c)) = chars.peek() {
+        let token = match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            _ if c.is_ascii_digit() || c == '.' => {
+                let mut end = at;
+                while let Some(&(i, c)) = chars
+                    .peek()
+                    .filter(|(_, c)| c.is_ascii_digit() || *c == '.')
+                {
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                let number = source[at..end].parse().map_err(|_| ParseError {
+                    at,
+                    message: format!("invalid number `{}`", &source[at..end]),
+                })?;
+                tokens.push((at, Token::Number(number)));
+                continue;
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let mut end = at;
+                while let Some(&(i, c)) = chars
+                    .peek()
+                    .filter(|(_, c)| c.is_alphanumeric() || *c == '_')
+                {
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                tokens.push((at, Token::Ident(source[at..end].to_string())));
+                continue;
+            }
+            _ => {
+                return Err(ParseError {
+                    at,
+                    message: format!("unexpected `{}`", c),
+                })
+            }
+        };
+        chars.next();
+        tokens.push((at, token));
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent, one method per precedence level.
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    pos: usize,
+    /// Offset reported for errors at the end of the input.
+    end: usize,
+    known: &'a dyn Fn(&str) -> bool,
+}
+
+impl Parser<'_> {
+    /// `term (('+' | '-') term)*`
+    fn sum(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.term()?;
+        while let Some(op) = self.next_op(&[Op::Add, Op::Sub]) {
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.term()?));
+        }
+        Ok(expr)
+    }
+
+    /// `factor (('*' | '/') factor)*`
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.factor()?;
+        while let Some(op) = self.next_op(&[Op::Mul, Op::Div]) {
+            expr = Expr::Binary(Box::new(expr), op, Box::new(self.factor()?));
+        }
+        Ok(expr)
+    }
+
+    /// `'-' factor | number | metric | '(' sum ')'`
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        let Some((at, token)) = self.tokens.get(self.pos) else {
+            return Err(self.error(self.end, "unexpected end of expression"));
+        };
+        self.pos += 1;
+        match token {
+            Token::Op(Op::Sub) => Ok(Expr::Neg(Box::new(self.factor()?))),
+            Token::Number(value) => Ok(Expr::Number(*value)),
+            Token::Ident(key) if (self.known)(key) => Ok(Expr::Metric(key.clone())),
+            Token::Ident(key) => Err(self.error(*at, &format!("unknown metric `{}`", key))),
+            Token::Open => {
+                let expr = self.sum()?;
+                match self.tokens.get(self.pos) {
+                    Some((_, Token::Close)) => {
+                        self.pos += 1;
+                        Ok(expr)
+                    }
+                    Some((at, _)) => Err(self.error(*at, "expected `)`")),
+                    None => Err(self.error(self.end, "expected `)`")),
+                }
+            }
+            Token::Op(_) | Token::Close => Err(self.error(*at, "expected a value")),
+        }
+    }
+
+    fn next_op(&mut self, ops: &[Op]) -> Option<Op> {
+        match self.tokens.get(self.pos) {
+            Some((_, Token::Op(op))) if ops.contains(op) => {
+                self.pos += 1;
+                Some(*op)
+            }
+            _ => None,
+        }
+    }
+
+    fn error(&self, at: usize, message: &str) -> ParseError {
+        ParseError {
+            at,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(key: &str) -> bool {
+        ["usage", "freq", "watts", "temp"].contains(&key)
+    }
+
+    fn eval(source: &str) -> Option<f64> {
+        let readings = |key: &str| match key {
+            "usage" => Some(50.0),
+            "freq" => Some(3000.0),
+            "watts" => Some(0.0),
+            _ => None,
+        };
+        Expr::parse(source, known).unwrap().eval(&readings)
+    }
+
+    fn error(source: &str) -> String {
+        Expr::parse(source, known).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn follows_precedence_and_associativity() {
+        assert_eq!(eval("1 + 2 * 3"), Some(7.0));
+        assert_eq!(eval("(1 + 2) * 3"), Some(9.0));
+        assert_eq!(eval("10 - 4 - 3"), Some(3.0));
+        assert_eq!(eval("64 / 4 / 2"), Some(8.0));
+        assert_eq!(eval("2 * -3 + 1"), Some(-5.0));
+        assert_eq!(eval("--2.5"), Some(2.5));
+        assert_eq!(eval("-(1 + 1) * 2"), Some(-4.0));
+    }
+
+    #[test]
+    fn reads_metrics() {
+        assert_eq!(eval("freq * usage / 100"), Some(1500.0));
+        assert_eq!(
+            Expr::parse("freq * usage / 100 / watts", known)
+                .unwrap()
+                .metrics(),
+            ["freq", "usage", "watts"]
+        );
+    }
+
+    #[test]
+    fn division_by_zero_and_missing_readings_are_gaps() {
+        assert_eq!(eval("freq / watts"), None);
+        assert_eq!(eval("1 / (usage - 50)"), None);
+        assert_eq!(eval("temp + 1"), None);
+        assert_eq!(eval("0 / freq"), Some(0.0));
+    }
+
+    #[test]
+    fn reports_errors_with_their_position() {
+        assert_eq!(error("freq / wats"), "unknown metric `wats` at column 8");
+        assert_eq!(error("freq *"), "unexpected end of expression at column 7");
+        assert_eq!(error("(freq + 1"), "expected `)` at column 10");
+        assert_eq!(error("freq usage"), "expected an operator at column 6");
+        assert_eq!(error("freq % 2"), "unexpected `%` at column 6");
+        assert_eq!(error("1.2.3"), "invalid number `1.2.3` at column 1");
+        assert_eq!(error("* 2"), "expected a value at column 1");
+        assert_eq!(error(""), "unexpected end of expression at column 1");
+    }
+}
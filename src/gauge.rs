@@ -0,0 +1,107 @@
+use std::f32::consts::PI;
+
+use iced::{
+    alignment,
+    mouse::Cursor,
+    widget::{
+        canvas::{self, path::Arc, Frame, Geometry, LineCap, Path, Program, Stroke},
+        Canvas,
+    },
+    Color, Element, Length, Point, Radians, Rectangle, Renderer, Theme,
+};
+
+use crate::{config::Gradient, Message};
+
+/// Semi-circular speedometer-style gauge of a single current value.
+pub struct GaugeWidget {
+    value: i32,
+    max_value: i32,
+    /// The value as shown, with its unit.
+    label: String,
+    gradient: Gradient,
+}
+
+impl GaugeWidget {
+    pub fn new(value: i32, max_value: i32, label: String) -> Self {
+        Self {
+            value,
+            max_value,
+            label,
+            gradient: Gradient::default(),
+        }
+    }
+
+    pub fn view<'a>(self, size: f32) -> Element<'a, Message> {
+        Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::Fixed(size))
+            .into()
+    }
+
+    fn fraction(&self) -> f32 {
+        if self.max_value <= 0 {
+            return 0.0;
+        }
+        (self.value as f32 / self.max_value as f32).clamp(0.0, 1.0)
+    }
+}
+
+impl Program<Message> for GaugeWidget {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let width = (bounds.height * 0.18).min(bounds.width * 0.09);
+        let radius = (bounds.width / 2.0).min(bounds.height * 0.85) - width;
+        let center = Point::new(bounds.width / 2.0, bounds.height * 0.85);
+
+        // Angles run clockwise from the positive x-axis, so the upper half
+        // goes from PI (left) to 2 * PI (right).
+        let arc = |fraction: f32| {
+            Path::new(|builder| {
+                builder.arc(Arc {
+                    center,
+                    radius,
+                    start_angle: Radians(PI),
+                    end_angle: Radians(PI + PI * fraction),
+                })
+            })
+        };
+        let stroke = |color: Color| Stroke {
+            width,
+            line_cap: LineCap::Butt,
+            ..Stroke::default().with_color(color)
+        };
+
+        let palette = theme.extended_palette();
+        frame.stroke(&arc(1.0), stroke(palette.background.strong.color));
+
+        let fraction = self.fraction();
+        if fraction > 0.0 {
+            let color = self.gradient.color_at(fraction);
+            frame.stroke(
+                &arc(fraction),
+                stroke(Color::from_rgb8(color.0, color.1, color.2)),
+            );
+        }
+
+        frame.fill_text(canvas::Text {
+            content: self.label.clone(),
+            position: center,
+            color: palette.background.base.text,
+            size: (radius * 0.35).into(),
+            horizontal_alignment: alignment::Horizontal::Center,
+            vertical_alignment: alignment::Vertical::Bottom,
+            ..canvas::Text::default()
+        });
+
+        vec![frame.into_geometry()]
+    }
+}
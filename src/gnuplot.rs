@@ -0,0 +1,127 @@
+//! Charts as self-contained gnuplot scripts, for offline analysis.
+
+use std::{
+    env,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::Local;
+
+use crate::{clock, SimpleChart};
+
+/// A fresh `$XDG_DATA_HOME/monty/gnuplot-<timestamp>` directory name,
+/// falling back to `~/.local/share`.
+pub fn export_dir() -> Option<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))?;
+    let name = format!("gnuplot-{}", Local::now().format("%Y%m%d-%H%M%S"));
+    Some(data_home.join("monty").join(name))
+}
+
+/// Writes the chart's data and a `plot` command drawing it the way monty
+/// does: a filled area under a line in the chart's color, over its range,
+/// with its markers as dashed vertical lines.
+pub fn export_gnuplot(chart: &SimpleChart, metric_name: &str, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut out = BufWriter::new(File::create(path)?);
+
+    writeln!(out, "# {} exported by monty", metric_name)?;
+    writeln!(out, "$data << EOD")?;
+    for (time, value) in chart.raw_data().iter().rev() {
+        writeln!(
+            out,
+            "{} {}",
+            clock::wall(*time)
+                .with_timezone(&Local)
+                .format("%Y-%m-%dT%H:%M:%S"),
+            chart.reading(*value as f64)
+        )?;
+    }
+    writeln!(out, "EOD")?;
+    writeln!(out)?;
+
+    let color = format!(
+        "#{:02x}{:02x}{:02x}",
+        chart.color.0, chart.color.1, chart.color.2
+    );
+    writeln!(out, "set title \"{}\"", metric_name)?;
+    writeln!(out, "set xdata time")?;
+    writeln!(out, "set timefmt \"%Y-%m-%dT%H:%M:%S\"")?;
+    writeln!(out, "set format x \"%H:%M:%S\"")?;
+    writeln!(out, "set ylabel \"{}\"", chart.unit.trim())?;
+    if chart.log_scale {
+        writeln!(out, "set logscale y")?;
+    } else {
+        writeln!(
+            out,
+            "set yrange [{}:{}]",
+            chart.reading(chart.min_value.into()),
+            chart.reading(chart.y_max().into())
+        )?;
+    }
+    writeln!(out, "set grid")?;
+    for (time, label) in &chart.markers {
+        let time = clock::wall(*time)
+            .with_timezone(&Local)
+            .format("%Y-%m-%dT%H:%M:%S");
+        writeln!(
+            out,
+            "set arrow from \"{time}\", graph 0 to \"{time}\", graph 1 nohead dt 2"
+        )?;
+        writeln!(
+            out,
+            "set label \"{}\" at \"{time}\", graph 1 rotate by 90 right offset 1,-0.5",
+            label.replace('\\', "\\\\").replace('"', "\\\"")
+        )?;
+    }
+    writeln!(out, "set style fill transparent solid 0.175 noborder")?;
+    writeln!(
+        out,
+        "plot $data using 1:2 with filledcurves y1=0 lc rgb \"{color}\" notitle, \\\n     \
+         $data using 1:2 with lines lw 2 lc rgb \"{color}\" title \"{}\"",
+        metric_name
+    )?;
+
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::Time;
+
+    #[test]
+    fn script_has_data_and_plot() {
+        let at = |secs: i64| Time::default() + chrono::Duration::seconds(1_700_000_000 + secs);
+        let mut chart = SimpleChart::new([(at(1), 150), (at(0), 120)].into_iter(), "".into(), 400);
+        chart.scale = 100;
+        chart.markers = vec![(at(0), "say \"hi\"".into())];
+        let path = env::temp_dir().join(format!("monty-gnuplot-{}.gnuplot", std::process::id()));
+
+        export_gnuplot(&chart, "ipc", &path).unwrap();
+        let script = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let data: Vec<_> = script
+            .lines()
+            .skip_while(|line| *line != "$data << EOD")
+            .skip(1)
+            .take_while(|line| *line != "EOD")
+            .map(|line| line.split_once(' ').unwrap().1)
+            .collect();
+        // Oldest first, as readings rather than stored samples.
+        assert_eq!(data, ["1.2", "1.5"]);
+        assert!(script.contains("set xdata time\n"));
+        assert!(script.contains("set format x \"%H:%M:%S\"\n"));
+        assert!(script.contains("set yrange [0:4]\n"));
+        assert!(script.contains("plot $data using 1:2"));
+        assert!(script.contains(" graph 1 nohead dt 2\n"));
+        assert!(script.contains("set label \"say \\\"hi\\\"\" at "));
+    }
+}
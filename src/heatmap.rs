@@ -0,0 +1,452 @@
+use std::{collections::VecDeque, fs};
+
+use chrono::Duration;
+use iced::{
+    widget::canvas::{Cache, Frame, Geometry},
+    Element, Length, Size,
+};
+use plotters::{coord::Shift, prelude::*};
+use plotters_iced::{Chart, ChartWidget, DrawingBackend, Renderer};
+
+use crate::{
+    clock::{Time, TimeAxis},
+    colors::{self, ChartPalette},
+    config::Gradient,
+    Message,
+};
+
+const LEGEND_HEIGHT: u32 = 40;
+
+/// Per-core usage over time: one row per logical CPU, one column per sample.
+pub struct CpuHeatMap {
+    cache: Cache,
+    times: VecDeque<Time>,
+    cores: Vec<VecDeque<f32>>,
+    limit: Duration,
+    gradient: Gradient,
+    /// Cores excluded from the scheduler with `isolcpus=`, as the kernel's
+    /// CPU list (e.g. `4-7`); empty if none.
+    pub isolated: String,
+    isolated_cores: Vec<usize>,
+    palette: ChartPalette,
+}
+
+impl CpuHeatMap {
+    pub fn new(core_count: usize, gradient: Gradient) -> Self {
+        let isolated = fs::read_to_string("/sys/devices/system/cpu/isolated").unwrap_or_default();
+        Self {
+            cache: Cache::new(),
+            times: VecDeque::new(),
+            cores: vec![VecDeque::new(); core_count],
+            limit: Duration::seconds(60),
+            gradient,
+            isolated_cores: parse_cpu_list(&isolated),
+            isolated: isolated.trim().to_string(),
+            palette: ChartPalette::default(),
+        }
+    }
+
+    pub fn push_data(&mut self, time: Time, usages: impl Iterator<Item = f32>) {
+        self.times.push_front(time);
+        for (core, usage) in self.cores.iter_mut().zip(usages) {
+            core.push_front(usage);
+        }
+
+        while self
+            .times
+            .back()
+            .is_some_and(|oldest| time - *oldest > self.limit)
+        {
+            self.times.pop_back();
+            for core in &mut self.cores {
+                core.truncate(self.times.len());
+            }
+        }
+        self.cache.clear();
+    }
+
+    pub fn set_limit(&mut self, limit: std::time::Duration) {
+        self.limit = Duration::from_std(limit).unwrap_or(self.limit);
+        self.cache.clear();
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        self.cache.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.times.clear();
+        for core in &mut self.cores {
+            core.clear();
+        }
+        self.cache.clear();
+    }
+
+    pub fn view(&self, height: f32) -> Element<'_, Message> {
+        ChartWidget::new(self)
+            .width(Length::Fill)
+            .height(Length::Fixed(height))
+            .into()
+    }
+}
+
+impl Chart<Message> for CpuHeatMap {
+    type State = ();
+
+    #[inline]
+    fn draw<R: Renderer, F: Fn(&mut Frame)>(
+        &self,
+        renderer: &R,
+        bounds: Size,
+        draw_fn: F,
+    ) -> Geometry {
+        renderer.draw_cache(&self.cache, bounds, draw_fn)
+    }
+
+    fn draw_chart<DB: DrawingBackend>(&self, state: &Self::State, root: DrawingArea<DB, Shift>) {
+        let (_, height) = root.dim_in_pixel();
+        let (map, legend) = root.split_vertically(height.saturating_sub(LEGEND_HEIGHT));
+
+        self.build_chart(state, ChartBuilder::on(&map));
+        self.draw_legend(&legend);
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut chart: ChartBuilder<DB>) {
+        let newest_time = self.times.front().copied().unwrap_or_default();
+        let oldest_time = newest_time - self.limit;
+        let core_count = self.cores.len() as i32;
+
+        let mut chart = chart
+            .x_label_area_size(0)
+            .y_label_area_size(60)
+            .margin(20)
+            .build_cartesian_2d(TimeAxis::from(oldest_time..newest_time), 0..core_count)
+            .expect("failed to build chart");
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .axis_style(ShapeStyle::from(self.palette.axis()).stroke_width(1))
+            .y_labels(self.cores.len())
+            .y_label_style(("sans-serif", 13).into_font().color(&self.palette.label()))
+            .y_label_formatter(&|core| format!("CPU {}", core))
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        // Each sample spans until the next newer one; the newest spans up to
+        // the right edge.
+        let spans = self.times.iter().enumerate().map(|(i, start)| {
+            (
+                i,
+                *start,
+                if i == 0 {
+                    newest_time
+                } else {
+                    self.times[i - 1]
+                },
+            )
+        });
+
+        chart
+            .draw_series(spans.flat_map(|(i, start, end)| {
+                self.cores
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(core, usages)| {
+                        let usage = *usages.get(i)?;
+                        let color = self.gradient.color_at(usage / 100.0);
+                        Some(Rectangle::new(
+                            [(start, core as i32), (end, core as i32 + 1)],
+                            color.filled(),
+                        ))
+                    })
+            }))
+            .expect("failed to draw chart data");
+
+        self.draw_isolated(&mut chart);
+    }
+}
+
+impl CpuHeatMap {
+    /// Outlines the rows of isolated cores. Called once every cell is
+    /// filled, so no cell covers the outline.
+    fn draw_isolated<DB: DrawingBackend>(
+        &self,
+        chart: &mut ChartContext<
+            '_,
+            DB,
+            Cartesian2d<TimeAxis, plotters::coord::types::RangedCoordi32>,
+        >,
+    ) {
+        let (oldest_time, newest_time) = (chart.x_range().start, chart.x_range().end);
+        chart
+            .draw_series(self.isolated_cores.iter().map(|&core| {
+                Rectangle::new(
+                    [(oldest_time, core as i32), (newest_time, core as i32 + 1)],
+                    colors::rgb(self.palette.warning).stroke_width(2),
+                )
+            }))
+            .expect("failed to draw isolated cores");
+    }
+
+    /// Color scale from 0 to 100 % below the map.
+    fn draw_legend<DB: DrawingBackend>(&self, area: &DrawingArea<DB, Shift>) {
+        let mut legend = ChartBuilder::on(area)
+            .margin_left(80)
+            .margin_right(20)
+            .x_label_area_size(20)
+            .build_cartesian_2d(0..100, 0..1)
+            .expect("failed to build legend");
+
+        legend
+            .configure_mesh()
+            .disable_mesh()
+            .disable_y_axis()
+            .x_labels(5)
+            .x_label_style(("sans-serif", 13).into_font().color(&self.palette.label()))
+            .x_label_formatter(&|usage| format!("{}%", usage))
+            .draw()
+            .expect("failed to draw legend mesh");
+
+        legend
+            .draw_series((0..100).map(|usage| {
+                let color = self.gradient.color_at(usage as f32 / 100.0);
+                Rectangle::new([(usage, 0), (usage + 1, 1)], color.filled())
+            }))
+            .expect("failed to draw legend");
+    }
+}
+
+/// Blue at the lowest frequency seen, through green and yellow, to red at
+/// the highest.
+pub fn rainbow() -> Gradient {
+    Gradient {
+        stops: vec![
+            (0.0, [40, 60, 255]),
+            (0.25, [0, 200, 255]),
+            (0.5, [0, 210, 60]),
+            (0.75, [250, 220, 0]),
+            (1.0, [240, 0, 0]),
+        ],
+    }
+}
+
+/// Per-core clock speed over the last minute, like `turbostat` prints it:
+/// one row per logical CPU, one column per sample.
+pub struct FrequencyHeatMap {
+    cache: Cache,
+    times: VecDeque<Time>,
+    /// MHz per core, newest first.
+    cores: Vec<VecDeque<u64>>,
+    limit: Duration,
+    gradient: Gradient,
+    palette: ChartPalette,
+}
+
+impl FrequencyHeatMap {
+    pub fn new(core_count: usize) -> Self {
+        Self {
+            cache: Cache::new(),
+            times: VecDeque::new(),
+            cores: vec![VecDeque::new(); core_count],
+            limit: Duration::seconds(60),
+            gradient: rainbow(),
+            palette: ChartPalette::default(),
+        }
+    }
+
+    pub fn push_data(&mut self, time: Time, frequencies: impl Iterator<Item = u64>) {
+        self.times.push_front(time);
+        for (core, mhz) in self.cores.iter_mut().zip(frequencies) {
+            core.push_front(mhz);
+        }
+
+        while self
+            .times
+            .back()
+            .is_some_and(|oldest| time - *oldest > self.limit)
+        {
+            self.times.pop_back();
+            for core in &mut self.cores {
+                core.truncate(self.times.len());
+            }
+        }
+        self.cache.clear();
+    }
+
+    pub fn set_limit(&mut self, limit: std::time::Duration) {
+        self.limit = Duration::from_std(limit).unwrap_or(self.limit);
+        self.cache.clear();
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        self.cache.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.times.clear();
+        for core in &mut self.cores {
+            core.clear();
+        }
+        self.cache.clear();
+    }
+
+    /// MHz per core, newest first.
+    pub fn cores(&self) -> &[VecDeque<u64>] {
+        &self.cores
+    }
+
+    pub fn view(&self, height: f32) -> Element<'_, Message> {
+        ChartWidget::new(self)
+            .width(Length::Fill)
+            .height(Length::Fixed(height))
+            .into()
+    }
+
+    /// Lowest and highest frequency in the window, which the gradient spans.
+    fn range(&self) -> (u64, u64) {
+        let all = self.cores.iter().flatten().copied();
+        let min = all.clone().min().unwrap_or_default();
+        let max = all.max().unwrap_or_default().max(min + 1);
+        (min, max)
+    }
+
+    fn color(&self, mhz: u64, (min, max): (u64, u64)) -> RGBColor {
+        self.gradient
+            .color_at((mhz - min) as f32 / (max - min) as f32)
+    }
+}
+
+impl Chart<Message> for FrequencyHeatMap {
+    type State = ();
+
+    #[inline]
+    fn draw<R: Renderer, F: Fn(&mut Frame)>(
+        &self,
+        renderer: &R,
+        bounds: Size,
+        draw_fn: F,
+    ) -> Geometry {
+        renderer.draw_cache(&self.cache, bounds, draw_fn)
+    }
+
+    fn draw_chart<DB: DrawingBackend>(&self, state: &Self::State, root: DrawingArea<DB, Shift>) {
+        let (_, height) = root.dim_in_pixel();
+        let (map, legend) = root.split_vertically(height.saturating_sub(LEGEND_HEIGHT));
+
+        self.build_chart(state, ChartBuilder::on(&map));
+        self.draw_legend(&legend);
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut chart: ChartBuilder<DB>) {
+        let newest_time = self.times.front().copied().unwrap_or_default();
+        let oldest_time = newest_time - self.limit;
+        let core_count = self.cores.len() as i32;
+        let range = self.range();
+
+        let mut chart = chart
+            .x_label_area_size(0)
+            .y_label_area_size(60)
+            .margin(20)
+            .build_cartesian_2d(TimeAxis::from(oldest_time..newest_time), 0..core_count)
+            .expect("failed to build chart");
+
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .disable_y_mesh()
+            .axis_style(ShapeStyle::from(self.palette.axis()).stroke_width(1))
+            .y_labels(self.cores.len())
+            .y_label_style(("sans-serif", 13).into_font().color(&self.palette.label()))
+            .y_label_formatter(&|core| format!("CPU {}", core))
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        let spans = self.times.iter().enumerate().map(|(i, start)| {
+            (
+                i,
+                *start,
+                if i == 0 {
+                    newest_time
+                } else {
+                    self.times[i - 1]
+                },
+            )
+        });
+
+        chart
+            .draw_series(spans.flat_map(|(i, start, end)| {
+                self.cores
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(core, frequencies)| {
+                        let mhz = *frequencies.get(i)?;
+                        Some(Rectangle::new(
+                            [(start, core as i32), (end, core as i32 + 1)],
+                            self.color(mhz, range).filled(),
+                        ))
+                    })
+            }))
+            .expect("failed to draw chart data");
+    }
+}
+
+impl FrequencyHeatMap {
+    /// Color scale from the lowest to the highest frequency below the map.
+    fn draw_legend<DB: DrawingBackend>(&self, area: &DrawingArea<DB, Shift>) {
+        let (min, max) = self.range();
+        let mut legend = ChartBuilder::on(area)
+            .margin_left(80)
+            .margin_right(20)
+            .x_label_area_size(20)
+            .build_cartesian_2d(min..max, 0..1)
+            .expect("failed to build legend");
+
+        legend
+            .configure_mesh()
+            .disable_mesh()
+            .disable_y_axis()
+            .x_labels(5)
+            .x_label_style(("sans-serif", 13).into_font().color(&self.palette.label()))
+            .x_label_formatter(&|mhz| format!("{} MHz", mhz))
+            .draw()
+            .expect("failed to draw legend mesh");
+
+        let step = ((max - min) / 100).max(1);
+        legend
+            .draw_series((min..max).step_by(step as usize).map(|mhz| {
+                Rectangle::new(
+                    [(mhz, 0), ((mhz + step).min(max), 1)],
+                    self.color(mhz, (min, max)).filled(),
+                )
+            }))
+            .expect("failed to draw legend");
+    }
+}
+
+/// Parses a kernel CPU list such as `0-3,8,10-11`.
+fn parse_cpu_list(list: &str) -> Vec<usize> {
+    list.trim()
+        .split(',')
+        .filter_map(|part| match part.split_once('-') {
+            Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+            None => part.parse().ok().map(|cpu| cpu..=cpu),
+        })
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cpu_lists_expand_ranges() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), [0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list("\n"), Vec::<usize>::new());
+        assert_eq!(parse_cpu_list(""), Vec::<usize>::new());
+    }
+}
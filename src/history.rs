@@ -0,0 +1,139 @@
+use std::{
+    collections::VecDeque,
+    env,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::DateTime;
+
+use crate::clock::{self, Time};
+
+const MAGIC: &[u8; 8] = b"MONTYHST";
+const VERSION: u32 = 1;
+/// Upper bound on stored points per series, roughly an hour at 10 Hz.
+const MAX_POINTS_PER_SERIES: usize = 36_000;
+
+pub type Series = Vec<(Time, i32)>;
+
+/// Chart buffers persisted from a previous session, newest point first in
+/// every series.
+pub struct Snapshot {
+    pub series: Vec<(String, Series)>,
+}
+
+impl Snapshot {
+    /// Wall-clock span covered by the snapshot across all series, rounded
+    /// up to whole minutes.
+    pub fn minutes(&self) -> i64 {
+        let points = self.series.iter().flat_map(|(_, s)| s.iter().map(|p| p.0));
+        match (points.clone().min(), points.max()) {
+            (Some(oldest), Some(newest)) => ((newest - oldest).num_seconds() + 59) / 60,
+            _ => 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.iter().all(|(_, s)| s.is_empty())
+    }
+}
+
+/// `$XDG_STATE_HOME/monty/history.bin`, falling back to `~/.local/state`.
+pub fn snapshot_path() -> Option<PathBuf> {
+    let state_home = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/state")))?;
+    Some(state_home.join("monty").join("history.bin"))
+}
+
+/// Moves the snapshot at `path` to `history.previous.bin` beside it, so
+/// the new session's snapshots can't overwrite it before the user chose
+/// whether to restore it, and an ignored offer stays on disk.
+pub fn set_aside(path: &Path) -> io::Result<()> {
+    fs::rename(path, path.with_file_name("history.previous.bin"))
+}
+
+/// Writes the snapshot next to its destination and renames it into place,
+/// so a crash mid-write never leaves a truncated file behind.
+pub fn save<'a>(
+    path: &Path,
+    series: impl Iterator<Item = (&'a str, &'a VecDeque<(Time, i32)>)>,
+) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("bin.tmp");
+
+    let mut out = BufWriter::new(File::create(&tmp)?);
+    out.write_all(MAGIC)?;
+    out.write_all(&VERSION.to_le_bytes())?;
+
+    let series: Vec<_> = series.collect();
+    out.write_all(&(series.len() as u32).to_le_bytes())?;
+    for (name, points) in series {
+        let points = points.iter().take(MAX_POINTS_PER_SERIES);
+        out.write_all(&(name.len() as u16).to_le_bytes())?;
+        out.write_all(name.as_bytes())?;
+        out.write_all(&(points.len() as u32).to_le_bytes())?;
+        for (time, value) in points {
+            out.write_all(&clock::wall(*time).timestamp_millis().to_le_bytes())?;
+            out.write_all(&value.to_le_bytes())?;
+        }
+    }
+    out.into_inner()?.sync_all()?;
+
+    fs::rename(tmp, path)
+}
+
+pub fn load(path: &Path) -> io::Result<Snapshot> {
+    let mut input = BufReader::new(File::open(path)?);
+
+    let mut magic = [0; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a monty history file"));
+    }
+    if read_u32(&mut input)? != VERSION {
+        return Err(invalid("unsupported history file version"));
+    }
+
+    let count = read_u32(&mut input)?;
+    let mut series = Vec::new();
+    for _ in 0..count {
+        let mut len = [0; 2];
+        input.read_exact(&mut len)?;
+        let mut name = vec![0; u16::from_le_bytes(len) as usize];
+        input.read_exact(&mut name)?;
+        let name = String::from_utf8(name).map_err(|_| invalid("bad series name"))?;
+
+        let points = read_u32(&mut input)? as usize;
+        if points > MAX_POINTS_PER_SERIES {
+            return Err(invalid("series exceeds size bound"));
+        }
+        let mut data = Vec::with_capacity(points);
+        for _ in 0..points {
+            let mut ms = [0; 8];
+            input.read_exact(&mut ms)?;
+            let mut value = [0; 4];
+            input.read_exact(&mut value)?;
+            let time = DateTime::from_timestamp_millis(i64::from_le_bytes(ms))
+                .ok_or_else(|| invalid("bad timestamp"))?;
+            data.push((clock::from_wall(time), i32::from_le_bytes(value)));
+        }
+        series.push((name, data));
+    }
+
+    Ok(Snapshot { series })
+}
+
+fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    input.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
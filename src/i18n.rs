@@ -0,0 +1,157 @@
+//! UI strings by key, in the language of the config's `language` or the
+//! environment's `LANG`. Each language is a table like `locales/en.toml`:
+//! the built-in ones are compiled in, and any other can be dropped into
+//! `$XDG_CONFIG_HOME/monty/locales/` as `<code>.toml`. Missing keys fall
+//! back to English.
+
+use std::{collections::HashMap, env, fs, sync::OnceLock};
+
+use color_eyre::eyre::{Result as EyreResult, WrapErr};
+use serde::Deserialize;
+
+use crate::config::Config;
+
+/// Compiled-in languages by code.
+const BUILT_IN: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.toml")),
+    ("de", include_str!("../locales/de.toml")),
+];
+
+#[derive(Debug, Deserialize)]
+struct Locale {
+    #[serde(default = "default_separator")]
+    decimal_separator: String,
+    strings: HashMap<String, String>,
+}
+
+fn default_separator() -> String {
+    ".".to_string()
+}
+
+static ENGLISH: OnceLock<Locale> = OnceLock::new();
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+fn english() -> &'static Locale {
+    ENGLISH.get_or_init(|| toml::from_str(BUILT_IN[0].1).expect("invalid built-in locale"))
+}
+
+fn locale() -> &'static Locale {
+    LOCALE.get().unwrap_or_else(english)
+}
+
+/// Picks the language for the rest of the run: `language` if set, else
+/// the environment's. Only the first call has an effect. Returns an error
+/// for a language file that can't be read, and keeps English then.
+pub fn init(language: Option<&str>) -> EyreResult<()> {
+    let code = match language {
+        Some(language) => language.to_string(),
+        None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .map_or("en".to_string(), |value| language_code(&value).to_string()),
+    };
+    if code == "en" {
+        return Ok(());
+    }
+    let user_file = Config::path()
+        .map(|path| {
+            path.with_file_name("locales")
+                .join(format!("{}.toml", code))
+        })
+        .filter(|path| path.exists());
+    let text = match (&user_file, BUILT_IN.iter().find(|(c, _)| *c == code)) {
+        (Some(path), _) => fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read {}", path.display()))?,
+        (None, Some((_, text))) => text.to_string(),
+        // No translation; English it is.
+        (None, None) => return Ok(()),
+    };
+    let locale = toml::from_str(&text).wrap_err_with(|| format!("invalid locale `{}`", code))?;
+    let _ = LOCALE.set(locale);
+    Ok(())
+}
+
+/// `de` for `de_DE.UTF-8`; English for `C` and `POSIX`.
+fn language_code(value: &str) -> &str {
+    let code = value.split(['_', '.', '@']).next().unwrap_or_default();
+    match code {
+        "" | "C" | "POSIX" => "en",
+        code => code,
+    }
+}
+
+/// The string for `key`, if any language has one.
+pub fn lookup(key: &str) -> Option<&'static str> {
+    locale()
+        .strings
+        .get(key)
+        .or_else(|| english().strings.get(key))
+        .map(String::as_str)
+}
+
+/// The string for `key`, or the key itself if it has none.
+pub fn t(key: &'static str) -> &'static str {
+    lookup(key).unwrap_or(key)
+}
+
+/// The string for `key` with its `{name}` placeholders filled in.
+pub fn tf(key: &'static str, args: &[(&str, &str)]) -> String {
+    fill(t(key), args)
+}
+
+fn fill(template: &str, args: &[(&str, &str)]) -> String {
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+}
+
+/// A formatted number with the language's decimal separator.
+pub fn number(formatted: &str) -> String {
+    with_separator(formatted, &locale().decimal_separator)
+}
+
+fn with_separator(formatted: &str, separator: &str) -> String {
+    formatted.replacen('.', separator, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_locales_translate_every_key() {
+        for (code, text) in BUILT_IN {
+            let locale: Locale = toml::from_str(text).unwrap();
+            for key in english().strings.keys() {
+                assert!(locale.strings.contains_key(key), "{} lacks {}", code, key);
+            }
+        }
+    }
+
+    #[test]
+    fn language_codes_come_from_locale_names() {
+        assert_eq!(language_code("de_DE.UTF-8"), "de");
+        assert_eq!(language_code("fr"), "fr");
+        assert_eq!(language_code("sr_RS@latin"), "sr");
+        assert_eq!(language_code("C.UTF-8"), "en");
+        assert_eq!(language_code("POSIX"), "en");
+    }
+
+    #[test]
+    fn placeholders_and_separators_are_filled() {
+        assert_eq!(
+            fill(
+                "{label} (of {cpus} CPU quota)",
+                &[("label", "CPU"), ("cpus", "2.5")]
+            ),
+            "CPU (of 2.5 CPU quota)"
+        );
+        assert_eq!(fill("{missing}", &[]), "{missing}");
+        assert_eq!(with_separator("1234.56", ","), "1234,56");
+        assert_eq!(with_separator("42", ","), "42");
+        assert_eq!(t("header.copy"), "Copy");
+        assert_eq!(t("no.such.key"), "no.such.key");
+    }
+}
@@ -0,0 +1,235 @@
+//! `--ipc-socket`: monty's readings as newline-delimited JSON on a Unix
+//! socket, for widgets like Conky or eww. A client gets the latest sample
+//! on connecting, then one line per sample with the charts that were due:
+//!
+//! `{"freq":3400.0,"time":"2024-05-01T12:00:00.500Z","usage":12.5,...}`
+//!
+//! keyed like the config, in the metrics' own units, and stamped with the
+//! wall time the sample was taken at. Writing the line
+//! `{"request":"history"}` asks for every chart's buffer, which comes as
+//! `{"history":{"usage":[["2024-05-01T12:00:00.000Z",12.5],...],...}}` within
+//! a sample interval.
+
+use std::{
+    io,
+    os::unix::net::UnixStream as StdUnixStream,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::mpsc,
+};
+
+/// Lines queued per client; a client that falls further behind misses
+/// samples rather than growing monty's memory.
+const QUEUE: usize = 64;
+
+#[derive(Default)]
+struct Shared {
+    /// The latest sample line, for clients that connect in between.
+    latest: Option<Arc<str>>,
+    clients: Vec<mpsc::Sender<Arc<str>>>,
+    /// Clients waiting for the history.
+    history_requests: Vec<mpsc::Sender<Arc<str>>>,
+}
+
+pub struct IpcServer {
+    shared: Arc<Mutex<Shared>>,
+    path: PathBuf,
+}
+
+impl IpcServer {
+    /// Binds the socket at `path`, replacing a stale one. Clients are
+    /// served by the returned future, which must run on the tokio runtime
+    /// and only returns on an error.
+    pub fn bind(
+        path: &Path,
+    ) -> io::Result<(Self, impl std::future::Future<Output = io::Result<()>>)> {
+        if path.exists() {
+            if StdUnixStream::connect(path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    "another monty is serving it",
+                ));
+            }
+            std::fs::remove_file(path)?;
+        }
+        let listener = std::os::unix::net::UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let server = Self {
+            shared: shared.clone(),
+            path: path.to_path_buf(),
+        };
+        let serve = async move {
+            let listener = UnixListener::from_std(listener)?;
+            loop {
+                let (stream, _) = listener.accept().await?;
+                // A client that hangs up mid-write is its own problem.
+                tokio::spawn(serve_client(stream, shared.clone()));
+            }
+        };
+        Ok((server, serve))
+    }
+
+    /// Sends a sample line to every client.
+    pub fn publish(&self, line: String) {
+        let line: Arc<str> = line.into();
+        let mut shared = self.shared.lock().unwrap();
+        shared.latest = Some(line.clone());
+        shared.clients.retain(|client| {
+            !matches!(
+                client.try_send(line.clone()),
+                Err(mpsc::error::TrySendError::Closed(_))
+            )
+        });
+    }
+
+    /// Whether a client is waiting for `send_history`.
+    pub fn history_requested(&self) -> bool {
+        !self.shared.lock().unwrap().history_requests.is_empty()
+    }
+
+    pub fn send_history(&self, line: String) {
+        let line: Arc<str> = line.into();
+        for client in self.shared.lock().unwrap().history_requests.drain(..) {
+            let _ = client.try_send(line.clone());
+        }
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+async fn serve_client(stream: UnixStream, shared: Arc<Mutex<Shared>>) -> io::Result<()> {
+    let (sender, mut lines) = mpsc::channel(QUEUE);
+    let latest = {
+        let mut shared = shared.lock().unwrap();
+        shared.clients.push(sender.clone());
+        shared.latest.clone()
+    };
+    let (read, mut write) = stream.into_split();
+    let mut requests = BufReader::new(read).lines();
+    if let Some(latest) = latest {
+        write_line(&mut write, &latest).await?;
+    }
+    loop {
+        tokio::select! {
+            line = lines.recv() => match line {
+                Some(line) => write_line(&mut write, &line).await?,
+                None => return Ok(()),
+            },
+            request = requests.next_line() => match request? {
+                Some(request) if is_history_request(&request) => {
+                    shared.lock().unwrap().history_requests.push(sender.clone());
+                }
+                Some(_) => {
+                    write_line(&mut write, r#"{"error":"unknown request"}"#).await?;
+                }
+                None => return Ok(()),
+            },
+        }
+    }
+}
+
+async fn write_line(write: &mut (impl AsyncWriteExt + Unpin), line: &str) -> io::Result<()> {
+    write.write_all(line.as_bytes()).await?;
+    write.write_all(b"\n").await
+}
+
+fn is_history_request(request: &str) -> bool {
+    serde_json::from_str::<Value>(request).is_ok_and(|request| request["request"] == "history")
+}
+
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// One sample line: the latest reading of every chart sampled, by key.
+pub fn sample_line(time: DateTime<Utc>, readings: &[(&str, f64)]) -> String {
+    let mut sample = Map::new();
+    sample.insert("time".to_string(), json!(timestamp(time)));
+    for (key, value) in readings {
+        sample.insert(key.to_string(), json!(value));
+    }
+    Value::Object(sample).to_string()
+}
+
+/// The reply to a history request: every buffer, oldest first, by key.
+pub fn history_line<'a>(
+    buffers: impl Iterator<Item = (&'a str, Vec<(DateTime<Utc>, f64)>)>,
+) -> String {
+    let history: Map<String, Value> = buffers
+        .map(|(key, points)| {
+            let points = points
+                .into_iter()
+                .map(|(time, value)| json!([timestamp(time), value]))
+                .collect();
+            (key.to_string(), Value::Array(points))
+        })
+        .collect();
+    json!({ "history": history }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use tokio::io::AsyncBufReadExt;
+
+    use super::*;
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    #[test]
+    fn lines_are_keyed_by_metric() {
+        assert_eq!(
+            sample_line(at(0), &[("usage", 12.5), ("freq", 3400.0)]),
+            r#"{"freq":3400.0,"time":"2023-11-14T22:13:20.000Z","usage":12.5}"#
+        );
+        assert_eq!(
+            history_line([("temp", vec![(at(0), 40.0), (at(1), 41.0)])].into_iter()),
+            r#"{"history":{"temp":[["2023-11-14T22:13:20.000Z",40.0],["2023-11-14T22:13:21.000Z",41.0]]}}"#
+        );
+        assert!(is_history_request(r#"{"request": "history"}"#));
+        assert!(!is_history_request("history"));
+    }
+
+    #[test]
+    fn clients_get_the_latest_sample_then_new_ones() {
+        let path = std::env::temp_dir().join(format!("monty-test-{}.sock", std::process::id()));
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            let (server, serve) = IpcServer::bind(&path).unwrap();
+            tokio::spawn(serve);
+            server.publish("first".to_string());
+
+            let stream = UnixStream::connect(&path).await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            assert_eq!(lines.next_line().await.unwrap().unwrap(), "first");
+
+            write_line(&mut write, r#"{"request":"history"}"#)
+                .await
+                .unwrap();
+            while !server.history_requested() {
+                tokio::task::yield_now().await;
+            }
+            server.publish("second".to_string());
+            server.send_history("history".to_string());
+            assert_eq!(lines.next_line().await.unwrap().unwrap(), "second");
+            assert_eq!(lines.next_line().await.unwrap().unwrap(), "history");
+        });
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,325 @@
+use std::{
+    fs, io,
+    time::{Duration, Instant},
+};
+
+use iced::{
+    widget::{
+        canvas::{Cache, Frame, Geometry},
+        Column, Text,
+    },
+    Element, Length, Size,
+};
+use plotters::{coord::Shift, prelude::*};
+use plotters_iced::{Chart, ChartWidget, DrawingBackend, Renderer};
+
+use crate::{colors::ChartPalette, Message};
+
+/// Rates over shorter spans mostly show when the sample happened to land.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// IRQ lines shown.
+const TOP: usize = 5;
+
+/// One line of `/proc/interrupts`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IrqCounts {
+    /// The number, or a name like `NMI` or `LOC`.
+    irq: String,
+    /// Cumulative count on each CPU.
+    per_cpu: Vec<u64>,
+    /// Controller, trigger and device, e.g. `IR-PCI-MSI 524288-edge nvme0q0`.
+    description: String,
+}
+
+/// An IRQ line's interrupts per second since the previous read.
+#[derive(Debug, Clone, PartialEq)]
+struct IrqRate {
+    irq: String,
+    description: String,
+    rate: f64,
+    /// The CPU that took most of them.
+    busiest_cpu: usize,
+}
+
+/// The IRQ lines firing most often, as one bar each; an interrupt storm
+/// shows as one towering over the rest.
+pub struct TopIrqPanel {
+    cache: Cache,
+    last: Option<(Instant, Vec<IrqCounts>)>,
+    rates: Vec<IrqRate>,
+    palette: ChartPalette,
+}
+
+impl TopIrqPanel {
+    pub fn new() -> Self {
+        Self {
+            cache: Cache::new(),
+            last: None,
+            rates: Vec::new(),
+            palette: ChartPalette::default(),
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        self.cache.clear();
+    }
+
+    /// Re-reads the counters once they're due; the first read only primes
+    /// them.
+    pub fn update(&mut self) -> io::Result<()> {
+        let now = Instant::now();
+        if self
+            .last
+            .as_ref()
+            .is_some_and(|(last_time, _)| now.duration_since(*last_time) < UPDATE_INTERVAL)
+        {
+            return Ok(());
+        }
+
+        let counts =
+            parse_interrupts(&fs::read_to_string("/proc/interrupts")?).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "no CPU columns in /proc/interrupts",
+                )
+            })?;
+        if let Some((last_time, last_counts)) = &self.last {
+            let elapsed = now.duration_since(*last_time).as_secs_f64();
+            self.rates = top_rates(last_counts, &counts, elapsed, TOP);
+            self.cache.clear();
+        }
+        self.last = Some((now, counts));
+        Ok(())
+    }
+
+    pub fn view(&self, height: f32) -> Element<'_, Message> {
+        Column::new()
+            .spacing(10)
+            .padding(20)
+            .width(Length::Fill)
+            .push(Text::new("Top interrupts").size(24))
+            .push(
+                ChartWidget::new(self)
+                    .width(Length::Fill)
+                    .height(Length::Fixed(height)),
+            )
+            .into()
+    }
+
+    fn bar_label(&self, index: i32) -> String {
+        let Some(rate) = usize::try_from(index).ok().and_then(|i| self.rates.get(i)) else {
+            return String::new();
+        };
+        format!(
+            "{} {} (CPU {})",
+            rate.irq, rate.description, rate.busiest_cpu
+        )
+    }
+}
+
+impl Chart<Message> for TopIrqPanel {
+    type State = ();
+
+    #[inline]
+    fn draw<R: Renderer, F: Fn(&mut Frame)>(
+        &self,
+        renderer: &R,
+        bounds: Size,
+        draw_fn: F,
+    ) -> Geometry {
+        renderer.draw_cache(&self.cache, bounds, draw_fn)
+    }
+
+    fn draw_chart<DB: DrawingBackend>(&self, state: &Self::State, root: DrawingArea<DB, Shift>) {
+        self.build_chart(state, ChartBuilder::on(&root));
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut chart: ChartBuilder<DB>) {
+        let max_rate = self.rates.first().map_or(0.0, |rate| rate.rate).max(1.0);
+
+        let mut chart = chart
+            .x_label_area_size(30)
+            .y_label_area_size(260)
+            .margin(20)
+            .build_cartesian_2d(0.0..max_rate * 1.1, (0..TOP as i32).into_segmented())
+            .expect("failed to build chart");
+
+        let label_style = ("sans-serif", 13).into_font().color(&self.palette.label());
+        chart
+            .configure_mesh()
+            .disable_y_mesh()
+            .bold_line_style(self.palette.grid())
+            .light_line_style(self.palette.fine_grid())
+            .axis_style(ShapeStyle::from(self.palette.axis()).stroke_width(1))
+            .x_labels(5)
+            .x_label_style(label_style.clone())
+            .x_label_formatter(&|rate| format!("{:.0}/s", rate))
+            .y_labels(TOP)
+            .y_label_style(label_style)
+            .y_label_formatter(&|bar| match bar {
+                SegmentValue::CenterOf(bar) => self.bar_label(*bar),
+                _ => String::new(),
+            })
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        let color = crate::colors::rgb(self.palette.primary);
+        chart
+            .draw_series(self.rates.iter().enumerate().map(|(index, rate)| {
+                let index = index as i32;
+                let mut bar = Rectangle::new(
+                    [
+                        (0.0, SegmentValue::Exact(index)),
+                        (rate.rate, SegmentValue::Exact(index + 1)),
+                    ],
+                    color.filled(),
+                );
+                bar.set_margin(2, 2, 0, 0);
+                bar
+            }))
+            .expect("failed to draw chart data");
+    }
+}
+
+/// Every IRQ line; `None` without the header naming the CPU columns.
+fn parse_interrupts(text: &str) -> Option<Vec<IrqCounts>> {
+    let mut lines = text.lines();
+    let cpus = lines
+        .next()?
+        .split_whitespace()
+        .filter(|column| column.starts_with("CPU"))
+        .count();
+    if cpus == 0 {
+        return None;
+    }
+    Some(
+        lines
+            .filter_map(|line| {
+                let (irq, rest) = line.split_once(':')?;
+                let mut fields = rest.split_whitespace().peekable();
+                // `ERR` and `MIS` have a single count, not one per CPU.
+                let mut per_cpu = Vec::with_capacity(cpus);
+                while per_cpu.len() < cpus {
+                    let Some(count) = fields.peek().and_then(|field| field.parse().ok()) else {
+                        break;
+                    };
+                    per_cpu.push(count);
+                    fields.next();
+                }
+                Some(IrqCounts {
+                    irq: irq.trim().to_string(),
+                    per_cpu,
+                    description: fields.collect::<Vec<_>>().join(" "),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// The `n` lines with the highest rate between two reads, highest first.
+/// Lines that appeared in between, e.g. a hotplugged device, wait for the
+/// next read.
+fn top_rates(
+    previous: &[IrqCounts],
+    current: &[IrqCounts],
+    elapsed_secs: f64,
+    n: usize,
+) -> Vec<IrqRate> {
+    let elapsed_secs = elapsed_secs.max(1e-3);
+    let mut rates: Vec<_> = current
+        .iter()
+        .filter_map(|line| {
+            let before = previous.iter().find(|before| before.irq == line.irq)?;
+            let deltas: Vec<u64> = line
+                .per_cpu
+                .iter()
+                .zip(&before.per_cpu)
+                .map(|(count, before)| count.saturating_sub(*before))
+                .collect();
+            let (busiest_cpu, _) = deltas
+                .iter()
+                .enumerate()
+                .max_by_key(|(cpu, delta)| (**delta, std::cmp::Reverse(*cpu)))?;
+            let total: u64 = deltas.iter().sum();
+            (total > 0).then(|| IrqRate {
+                irq: line.irq.clone(),
+                description: line.description.clone(),
+                rate: total as f64 / elapsed_secs,
+                busiest_cpu,
+            })
+        })
+        .collect();
+    rates.sort_by(|a, b| b.rate.total_cmp(&a.rate));
+    rates.truncate(n);
+    rates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BEFORE: &str = "\
+           CPU0       CPU1
+  0:         37          0   IO-APIC    2-edge      timer
+  8:          0          1   IO-APIC    8-edge      rtc0
+ 24:       1000       5000   PCI-MSI 524288-edge      nvme0q0
+ 25:        100        200   PCI-MSI 1048576-edge      enp3s0
+NMI:          2          3   Non-maskable interrupts
+ERR:          0
+";
+
+    const AFTER: &str = "\
+           CPU0       CPU1
+  0:         37          0   IO-APIC    2-edge      timer
+  8:          0          3   IO-APIC    8-edge      rtc0
+ 24:       1010       5090   PCI-MSI 524288-edge      nvme0q0
+ 25:        150       9200   PCI-MSI 1048576-edge      enp3s0
+NMI:          2          3   Non-maskable interrupts
+ERR:          0
+";
+
+    #[test]
+    fn parses_per_cpu_counts_and_descriptions() {
+        let lines = parse_interrupts(BEFORE).unwrap();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(
+            lines[2],
+            IrqCounts {
+                irq: "24".to_string(),
+                per_cpu: vec![1000, 5000],
+                description: "PCI-MSI 524288-edge nvme0q0".to_string(),
+            }
+        );
+        assert_eq!(lines[4].description, "Non-maskable interrupts");
+        assert_eq!(lines[5].per_cpu, [0]);
+        assert_eq!(parse_interrupts("no header\n"), None);
+    }
+
+    #[test]
+    fn ranks_lines_by_rate_over_all_cpus() {
+        let before = parse_interrupts(BEFORE).unwrap();
+        let after = parse_interrupts(AFTER).unwrap();
+        let rates = top_rates(&before, &after, 2.0, 2);
+        assert_eq!(
+            rates,
+            [
+                IrqRate {
+                    irq: "25".to_string(),
+                    description: "PCI-MSI 1048576-edge enp3s0".to_string(),
+                    rate: 4525.0,
+                    busiest_cpu: 1,
+                },
+                IrqRate {
+                    irq: "24".to_string(),
+                    description: "PCI-MSI 524288-edge nvme0q0".to_string(),
+                    rate: 50.0,
+                    busiest_cpu: 1,
+                },
+            ]
+        );
+        // Idle lines are left out rather than padding the top.
+        assert_eq!(top_rates(&before, &after, 1.0, TOP).len(), 3);
+    }
+}
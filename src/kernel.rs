@@ -0,0 +1,66 @@
+use std::{fs, time::Instant};
+
+/// A counter of kernel activity in `/proc/stat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    /// `ctxt`
+    ContextSwitches,
+    /// `intr`, whose first field is the total.
+    Interrupts,
+}
+
+/// Context switches and interrupts per second, from the `ctxt` and `intr`
+/// counters in `/proc/stat`.
+pub struct ProcStatExtReader {
+    /// Each counter at its previous read, by `Counter`.
+    last: [Option<(Instant, u64)>; 2],
+}
+
+impl ProcStatExtReader {
+    /// `None` if `/proc/stat` can't be read or lacks the counters.
+    pub fn probe() -> Option<Self> {
+        parse_counters(&fs::read_to_string("/proc/stat").ok()?)?;
+        Some(Self { last: [None; 2] })
+    }
+
+    /// Per second since the last read of `counter`; the first read only
+    /// primes it.
+    pub fn rate(&mut self, counter: Counter) -> Option<f64> {
+        let now = Instant::now();
+        let (ctxt, intr) = parse_counters(&fs::read_to_string("/proc/stat").ok()?)?;
+        let count = match counter {
+            Counter::ContextSwitches => ctxt,
+            Counter::Interrupts => intr,
+        };
+        let (last_time, last_count) = self.last[counter as usize].replace((now, count))?;
+        let elapsed = now.duration_since(last_time).as_secs_f64().max(1e-3);
+        Some(count.saturating_sub(last_count) as f64 / elapsed)
+    }
+}
+
+/// The cumulative `ctxt` and `intr` totals; `intr`'s first field is the
+/// total, the rest are per-IRQ counts.
+fn parse_counters(stat: &str) -> Option<(u64, u64)> {
+    let field = |key: &str| {
+        stat.lines().find_map(|line| {
+            line.strip_prefix(key)?
+                .split_whitespace()
+                .next()?
+                .parse()
+                .ok()
+        })
+    };
+    Some((field("ctxt ")?, field("intr ")?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_totals_from_proc_stat() {
+        let stat = "cpu  1 2 3 4\nintr 5000 12 0 7\nctxt 123456\nbtime 1700000000\n";
+        assert_eq!(parse_counters(stat), Some((123456, 5000)));
+        assert_eq!(parse_counters("cpu 1 2 3\n"), None);
+    }
+}
@@ -0,0 +1,146 @@
+//! Two widgets drawn on top of each other in the same bounds, so a chart
+//! can keep its cached drawing while a cheap overlay follows the mouse.
+
+use iced::{
+    advanced::{
+        layout::{self, Layout, Limits},
+        renderer,
+        widget::{tree, Tree, Widget},
+        Clipboard, Shell,
+    },
+    event, mouse, Element, Event, Length, Rectangle, Renderer, Size, Theme,
+};
+
+/// `top` over `base`. `top` gets `base`'s size and is drawn in a layer of
+/// its own, so its text and fills cover the base's rather than being
+/// batched with them. Both see every event, `base` first.
+pub struct Layers<'a, Message> {
+    base: Element<'a, Message>,
+    top: Element<'a, Message>,
+}
+
+impl<'a, Message> Layers<'a, Message> {
+    pub fn new(base: Element<'a, Message>, top: Element<'a, Message>) -> Self {
+        Self { base, top }
+    }
+}
+
+impl<Message> Widget<Message, Theme, Renderer> for Layers<'_, Message> {
+    fn size(&self) -> Size<Length> {
+        self.base.as_widget().size()
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.base), Tree::new(&self.top)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(&[&self.base, &self.top]);
+    }
+
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::stateless()
+    }
+
+    fn layout(&self, tree: &mut Tree, renderer: &Renderer, limits: &Limits) -> layout::Node {
+        let base = self
+            .base
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits);
+        let size = base.size();
+        let top =
+            self.top
+                .as_widget()
+                .layout(&mut tree.children[1], renderer, &Limits::new(size, size));
+        layout::Node::with_children(size, vec![base, top])
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let mut children = layout.children();
+        let (base, top) = (children.next().unwrap(), children.next().unwrap());
+        let status = self.base.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event.clone(),
+            base,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        );
+        status.merge(self.top.as_widget_mut().on_event(
+            &mut tree.children[1],
+            event,
+            top,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        ))
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let base = layout.children().next().unwrap();
+        self.base
+            .as_widget()
+            .mouse_interaction(&tree.children[0], base, cursor, viewport, renderer)
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        let mut children = layout.children();
+        let (base, top) = (children.next().unwrap(), children.next().unwrap());
+        self.base.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            base,
+            cursor,
+            viewport,
+        );
+        iced::advanced::Renderer::with_layer(renderer, layout.bounds(), |renderer| {
+            self.top.as_widget().draw(
+                &tree.children[1],
+                renderer,
+                theme,
+                style,
+                top,
+                cursor,
+                viewport,
+            );
+        });
+    }
+}
+
+impl<'a, Message: 'a> From<Layers<'a, Message>> for Element<'a, Message> {
+    fn from(layers: Layers<'a, Message>) -> Self {
+        Element::new(layers)
+    }
+}
@@ -0,0 +1,4936 @@
+mod affinity;
+mod amdgpu;
+#[cfg(feature = "aperf")]
+mod aperf;
+#[doc(hidden)]
+pub mod bench;
+mod cgroup;
+mod check;
+mod clock;
+mod colors;
+mod compare;
+mod config;
+mod corebars;
+#[cfg(feature = "dbus")]
+mod dbus;
+mod disks;
+mod dump;
+mod events;
+mod expr;
+mod gauge;
+mod gnuplot;
+mod heatmap;
+mod history;
+mod i18n;
+mod ipc;
+mod irq;
+mod kernel;
+mod layers;
+mod memprofile;
+mod memtimeline;
+mod metrics;
+mod multichart;
+mod palette;
+mod perf;
+mod perfdata;
+mod ping;
+mod platform;
+mod plugin;
+mod prefixes;
+mod procpower;
+mod rapl;
+mod residency;
+mod settings;
+mod sources;
+mod stats;
+mod stress;
+mod turbo;
+mod units;
+mod virt;
+mod watchdog;
+
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, VecDeque},
+    fs::{self, File},
+    io::{self, Read, Seek},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, SyncSender},
+        Arc, Mutex,
+    },
+    thread::{self, sleep},
+    time::{Duration, Instant, SystemTime},
+};
+
+use chrono::Local;
+use clap::{Parser, Subcommand};
+use color_eyre::eyre::Result as EyreResult;
+use iced::{
+    clipboard, event,
+    keyboard::{self, key, Key, Modifiers},
+    mouse,
+    multi_window::Application,
+    theme,
+    time::every,
+    widget::{
+        canvas::{self, Cache, Frame, Geometry},
+        container, mouse_area, pick_list, text_input, Button, Column, Container, ProgressBar, Row,
+        Scrollable, Text,
+    },
+    window, Alignment, Color, Command, Element, Event, Font, Length, Rectangle, Settings, Size,
+    Subscription, Theme,
+};
+use plotters::{
+    coord::ranged1d::{Ranged, ValueFormatter},
+    prelude::{Cartesian2d, ChartContext},
+    style::RGBColor,
+};
+use plotters_iced::{Chart, ChartBuilder, ChartWidget, DrawingBackend, Renderer};
+use sysinfo::{CpuRefreshKind, RefreshKind, System};
+
+use affinity::CpuAffinityView;
+use amdgpu::DrmAmdGpuReader;
+#[cfg(feature = "aperf")]
+use aperf::{AperfReader, EffectiveFrequency};
+use cgroup::{CgroupMonitor, CpuQuota};
+use clock::{Time, TimeAxis};
+use colors::ChartPalette;
+use compare::CompareMode;
+use config::{ByteUnits, ChartConfig, Config, Gradient, TraceStyle};
+use corebars::CoreBars;
+#[cfg(feature = "dbus")]
+use dbus::DbusService;
+use disks::DiskPanel;
+use dump::StateDump;
+use events::{EventLog, Excursion, ExcursionTracker, Severity, ThresholdRule};
+use gauge::GaugeWidget;
+use heatmap::{CpuHeatMap, FrequencyHeatMap};
+use ipc::IpcServer;
+use irq::TopIrqPanel;
+use kernel::{Counter, ProcStatExtReader};
+use layers::Layers;
+use memprofile::MemoryProfile;
+use memtimeline::MemoryTimeline;
+use metrics::MetricDescriptor;
+use multichart::MultiChart;
+use palette::{CommandPalette, PaletteMessage};
+use perf::PerfEventReader;
+use perfdata::HotSpot;
+use ping::PingMonitor;
+use platform::PlatformHints;
+use plugin::Plugin;
+use prefixes::Prefix;
+use procpower::{ProcessChange, ProcessPower, ProcessWatch};
+use rapl::{PowerFilter, RawReading};
+use residency::FrequencyResidency;
+use settings::{SettingsMessage, SettingsPanel, Window};
+use sources::{
+    GpuSource, HwpMode, IntelPstate, LMSensors, PowerLimits, PowerSource, SampleSource, TempSource,
+};
+use stats::StatReport;
+use stress::{StepStats, StressTest};
+use turbo::TurboHistogram;
+use units::{Unit, UnitConverter};
+use virt::Environment;
+use watchdog::Watchdog;
+
+#[derive(Debug, Default, Parser)]
+#[command(version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Subcommands>,
+    /// Chart the resident size of every memory mapping of PID over time
+    #[arg(long, value_name = "PID")]
+    memory_profile: Option<u32>,
+    /// Chart CPU and memory use of a cgroup v2, e.g. /sys/fs/cgroup/user.slice
+    #[arg(long, value_name = "PATH")]
+    cgroup: Option<PathBuf>,
+    /// Allow changing hardware settings such as the package power limits
+    #[arg(long)]
+    allow_control: bool,
+    /// Show two saved history files side by side, aligned on their start
+    #[arg(long, num_args = 2, value_names = ["A", "B"])]
+    compare_sessions: Vec<PathBuf>,
+    /// Mark the hot spots of a `perf record` file on the CPU usage chart;
+    /// record with `-k CLOCK_MONOTONIC` for exact times
+    #[arg(long, value_name = "PATH")]
+    perf_data: Option<PathBuf>,
+    /// Print which data sources are available, and why not, then exit
+    #[arg(long)]
+    check: bool,
+    /// Write sources, config, the last session's history and build info to
+    /// PATH as JSON for a bug report, then exit
+    #[arg(long, value_name = "PATH")]
+    dump_state: Option<PathBuf>,
+    /// Show a state dump offline, as the reporter saw it
+    #[arg(long, value_name = "PATH")]
+    load_state: Option<PathBuf>,
+    /// The dump named by `load_state`, read before the window opens.
+    #[arg(skip)]
+    loaded_state: Option<StateDump>,
+    /// Chart the metric of a shared library built against monty-lib; may
+    /// be given more than once
+    #[arg(long, value_name = "PATH")]
+    plugin: Vec<PathBuf>,
+    /// Open the window at this size, e.g. 1600x900, instead of the one in
+    /// the config
+    #[arg(long, value_name = "WxH", value_parser = parse_size)]
+    window_size: Option<[u32; 2]>,
+    /// Don't let the window shrink below this size, e.g. 800x600
+    #[arg(long, value_name = "WxH", value_parser = parse_size)]
+    min_window_size: Option<[u32; 2]>,
+    /// Show the raw difference between the last two samples of every
+    /// chart, and the last RAPL counter reading
+    #[arg(long)]
+    debug_overlay: bool,
+    /// Open a borderless, translucent window above the others, e.g. over
+    /// a game; translucent on Wayland only
+    #[arg(long)]
+    overlay: bool,
+    /// Keep the window above the others
+    #[arg(long)]
+    always_on_top: bool,
+    /// Window hints for the display server, worked out before the window
+    /// opens.
+    #[arg(skip)]
+    platform: PlatformHints,
+    /// Serve every sample as a line of JSON on a Unix socket at PATH
+    ///
+    /// A client gets the latest sample on connecting, then one line per
+    /// sample, like {"time":"2024-05-01T12:00:00.500Z","usage":12.5,...},
+    /// keyed like the config and in the metrics' own units. Sending the
+    /// line {"request":"history"} returns every chart's buffer as
+    /// {"history":{"usage":[["2024-05-01T12:00:00.000Z",12.5],...],...}}.
+    /// See examples/ipc_client.rs.
+    #[arg(long, value_name = "PATH")]
+    ipc_socket: Option<PathBuf>,
+    /// Ping HOST every second and chart the round-trip time
+    #[arg(long, value_name = "HOST")]
+    ping: Option<String>,
+    /// The libraries named by `plugin`, loaded before the window opens.
+    #[arg(skip)]
+    loaded_plugins: Vec<Plugin>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Subcommands {
+    /// Probe everything monty relies on, suggest fixes, and exit nonzero
+    /// if it can't run
+    Doctor,
+}
+
+/// Everything `monty` does, from parsing the command line on.
+pub fn run() -> EyreResult<()> {
+    let mut args = Args::parse();
+    // Without a GUI, nothing else holds libsensors.
+    let probes = || {
+        let sensors = sources::init_sensors();
+        check::probes(sensors.as_ref().map_err(String::as_str))
+    };
+    if let Some(Subcommands::Doctor) = args.command {
+        let probes = probes();
+        print!("{}", check::report(&probes));
+        if probes.iter().any(check::Probe::failed) {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if args.check {
+        print!("{}", check::report(&probes()));
+        return Ok(());
+    }
+    if let Some(path) = &args.dump_state {
+        dump::from_last_session(&probes()).save(path)?;
+        println!("Wrote {}", path.display());
+        return Ok(());
+    }
+    if let Some(path) = &args.load_state {
+        args.loaded_state = Some(StateDump::load(path)?);
+    }
+    args.loaded_plugins = args
+        .plugin
+        .iter()
+        .map(|path| Plugin::load(path))
+        .collect::<EyreResult<_>>()?;
+
+    // Errors are reported once the window is open and the config is loaded
+    // again.
+    let config = Config::load().unwrap_or_default();
+    if let Err(e) = i18n::init(config.language.as_deref()) {
+        eprintln!("{:#}", e);
+    }
+    let size = |[width, height]: [u32; 2]| Size::new(width as f32, height as f32);
+    let mut window = window::Settings {
+        size: args
+            .window_size
+            .or(config.window_size)
+            .map_or(window::Settings::default().size, size),
+        min_size: args.min_window_size.or(config.min_window_size).map(size),
+        // The size is saved on the way out.
+        exit_on_close_request: false,
+        ..window::Settings::default()
+    };
+    args.platform = PlatformHints::detect(
+        args.overlay || config.overlay,
+        args.always_on_top || config.always_on_top,
+    );
+    args.platform.apply(&mut window);
+    Monty::run(Settings {
+        window,
+        ..Settings::with_flags(args)
+    })?;
+    Ok(())
+}
+
+/// `WIDTHxHEIGHT` in pixels.
+fn parse_size(text: &str) -> Result<[u32; 2], String> {
+    let (width, height) = text
+        .split_once(['x', 'X'])
+        .ok_or("expected WIDTHxHEIGHT, e.g. 1600x900")?;
+    let parse = |side: &str| match side.trim().parse() {
+        Ok(0) | Err(_) => Err(format!("invalid size `{}`", side)),
+        Ok(pixels) => Ok(pixels),
+    };
+    Ok([parse(width)?, parse(height)?])
+}
+
+struct Monty {
+    config: Config,
+    theme: Theme,
+    chart: SystemChart,
+    events: EventLog,
+    show_events: bool,
+    show_alerts: bool,
+    show_kernel: bool,
+    /// The data sources panel, probed when it was opened.
+    sources: Option<Vec<check::Probe>>,
+    focused: bool,
+    /// Last windowed size the user resized to, saved to the config on
+    /// exit.
+    window_size: Option<[u32; 2]>,
+    /// `--window-size`, until the window opens at it. The flag only
+    /// applies to this run, so that size isn't saved.
+    cli_window_size: Option<[u32; 2]>,
+    /// Charts popped out into windows of their own, with each window's
+    /// last size.
+    popped_out: HashMap<window::Id, (ChartId, [u32; 2])>,
+    /// Wall display mode: controls are hidden and the charts enlarged.
+    fullscreen: bool,
+    /// Set by `--allow-control`; nothing is ever written to the hardware
+    /// without it.
+    allow_control: bool,
+    settings: Option<SettingsPanel>,
+    view_mode: ViewMode,
+    pending_restore: Option<history::Snapshot>,
+    memory_profile: Option<MemoryProfile>,
+    cgroup: Option<CgroupMonitor>,
+    /// Unset once `/proc/interrupts` can't be read.
+    irqs: Option<TopIrqPanel>,
+    compare: CompareMode,
+    /// Label being typed for a new marker, while the input is open.
+    marker_input: Option<String>,
+    palette: Option<CommandPalette>,
+    stress: Option<StressTest>,
+    /// Results of the last stress ramp, until dismissed.
+    stress_summary: Option<Vec<StepStats>>,
+    /// Chart whose statistics are shown, kept current as samples arrive.
+    stats: Option<ChartId>,
+    /// Everything the command palette can run, by name.
+    actions: Vec<(String, Message)>,
+    last_snapshot: Instant,
+    snapshot_failed: bool,
+    watchdog: Watchdog,
+    /// Part of the tick subscription's identity; bumping it restarts the
+    /// subscription.
+    tick_generation: u64,
+    /// Where the state dump being shown came from; nothing is sampled or
+    /// saved while set.
+    offline: Option<String>,
+    debug_overlay: bool,
+    /// Unset without `--ipc-socket`, or once the socket fails.
+    ipc: Option<IpcServer>,
+    /// Unset without a session bus, or once the service stops.
+    #[cfg(feature = "dbus")]
+    dbus: Option<DbusService>,
+    platform: PlatformHints,
+}
+
+impl Application for Monty {
+    type Executor = tokio::runtime::Runtime;
+    type Flags = Args;
+    type Message = Message;
+    type Theme = Theme;
+
+    fn new(flags: Args) -> (Monty, Command<Self::Message>) {
+        let mut events = EventLog::default();
+        let config = match &flags.loaded_state {
+            Some(dump) => {
+                dump.replay_events(&mut events);
+                let mut config = dump.config().unwrap_or_else(|e| {
+                    events.push(Severity::Warning, format!("{:#}", e));
+                    Config::default()
+                });
+                config.offline = true;
+                config
+            }
+            None => {
+                events.push(Severity::Info, "Monitoring started");
+                Config::load().unwrap_or_else(|e| {
+                    events.push(Severity::Warning, format!("{:#}", e));
+                    Config::default()
+                })
+            }
+        };
+
+        for error in metrics::register_derived(&config.derived) {
+            events.push(Severity::Warning, error);
+        }
+        let (plugins, errors) = metrics::register_plugins(flags.loaded_plugins);
+        for error in errors {
+            events.push(Severity::Warning, error);
+        }
+        for (_, plugin) in &plugins {
+            events.push(Severity::Info, format!("Loaded plugin `{}`", plugin.name));
+        }
+        let theme = config.theme();
+
+        let memory_profile = flags.memory_profile.map(|pid| {
+            events.push(Severity::Info, format!("Profiling memory of PID {}", pid));
+            MemoryProfile::new(
+                pid,
+                Duration::from_secs(config.window_secs.into()),
+                config.byte_units,
+            )
+        });
+
+        let mut chart = SystemChart::new(&config, &theme);
+        chart.plugins = plugins;
+        let mut ping = None;
+        if let Some(host) = flags.ping.filter(|_| flags.loaded_state.is_none()) {
+            let (monitor, echoes) = PingMonitor::start(host);
+            chart.ping = Some(monitor);
+            chart.layout = chart.visible_layout(&config);
+            ping = Some(echoes);
+        }
+        if let Some(dump) = &flags.loaded_state {
+            chart.show_offline(dump.buffers());
+        } else if let Err(e) = &chart.sensors {
+            events.push(
+                Severity::Warning,
+                format!(
+                    "libsensors unavailable ({}), reading temperatures from /sys only",
+                    e
+                ),
+            );
+        }
+        let cgroup = flags
+            .cgroup
+            .map(|path| CgroupMonitor::new(path, chart.sys.cpus().len(), config.byte_units));
+
+        let pending_restore = history::snapshot_path()
+            .filter(|_| flags.loaded_state.is_none())
+            .and_then(|path| {
+                let snapshot = history::load(&path).ok()?;
+                // This session's snapshots go where it was.
+                if let Err(e) = history::set_aside(&path) {
+                    events.push(
+                        Severity::Warning,
+                        format!("Failed to set the last session's history aside: {}", e),
+                    );
+                }
+                Some(snapshot)
+            })
+            .filter(|snapshot| !snapshot.is_empty());
+
+        let mut monty = Monty {
+            chart,
+            theme,
+            config,
+            events,
+            show_events: false,
+            show_alerts: false,
+            show_kernel: false,
+            sources: None,
+            focused: true,
+            window_size: None,
+            cli_window_size: flags.window_size,
+            popped_out: HashMap::new(),
+            fullscreen: false,
+            allow_control: flags.allow_control,
+            debug_overlay: flags.debug_overlay,
+            platform: flags.platform,
+            settings: None,
+            view_mode: if !flags.compare_sessions.is_empty() {
+                ViewMode::Compare
+            } else if memory_profile.is_some() {
+                ViewMode::Memory
+            } else {
+                ViewMode::Charts
+            },
+            pending_restore,
+            memory_profile,
+            cgroup,
+            irqs: Some(TopIrqPanel::new()),
+            compare: CompareMode::default(),
+            marker_input: None,
+            palette: None,
+            stress: None,
+            stress_summary: None,
+            stats: None,
+            actions: Monty::actions(),
+            last_snapshot: Instant::now(),
+            snapshot_failed: false,
+            watchdog: Watchdog::start(),
+            tick_generation: 0,
+            offline: flags.loaded_state.as_ref().map(StateDump::origin),
+            ipc: None,
+            #[cfg(feature = "dbus")]
+            dbus: None,
+        };
+        monty.apply_palette();
+        let mut commands: Vec<_> = flags
+            .compare_sessions
+            .into_iter()
+            .map(|path| Command::perform(async { path }, Message::LoadCompareSession))
+            .collect();
+        if let Some(path) = flags.perf_data {
+            commands.push(Command::perform(async { path }, Message::LoadPerfData));
+        }
+        let popped_out: Vec<_> = monty
+            .chart
+            .series()
+            .filter_map(|(id, _)| Some((id, monty.config.chart(id).popped_out?)))
+            .collect();
+        for (id, size) in popped_out {
+            commands.push(monty.pop_out(id, size));
+        }
+        if let Some(path) = flags.ipc_socket.filter(|_| monty.offline.is_none()) {
+            match IpcServer::bind(&path) {
+                Ok((server, serve)) => {
+                    monty.ipc = Some(server);
+                    commands.push(Command::perform(serve, |result| {
+                        Message::IpcStopped(
+                            result.err().map_or_else(String::new, |e| e.to_string()),
+                        )
+                    }));
+                }
+                Err(e) => monty.events.push(
+                    Severity::Warning,
+                    format!("Failed to open {}: {}", path.display(), e),
+                ),
+            }
+        }
+        if let Some(echoes) = ping {
+            commands.push(Command::perform(echoes, |result| {
+                Message::PingStopped(result.err().map_or_else(String::new, |e| e.to_string()))
+            }));
+        }
+        #[cfg(feature = "dbus")]
+        if monty.offline.is_none() {
+            match DbusService::session() {
+                Ok((service, serve)) => {
+                    monty.dbus = Some(service);
+                    commands.push(Command::perform(serve, |result| {
+                        Message::DbusStopped(
+                            result.err().map_or_else(String::new, |e| e.to_string()),
+                        )
+                    }));
+                }
+                Err(e) => monty.events.push(
+                    Severity::Info,
+                    format!("Not serving {} on D-Bus: {}", dbus::NAME, e),
+                ),
+            }
+        }
+        (monty, Command::batch(commands))
+    }
+
+    fn title(&self, window: window::Id) -> String {
+        match self.popped_out.get(&window) {
+            Some((id, _)) => format!("MontY: {}", id.label()),
+            None => String::from("MontY"),
+        }
+    }
+
+    fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
+        match message {
+            Message::Tick | Message::SampleNow if self.offline.is_some() => {}
+            Message::Tick | Message::SampleNow => {
+                let force = matches!(message, Message::SampleNow);
+                if self
+                    .watchdog
+                    .watch(|| self.chart.update(&mut self.events, force))
+                {
+                    if self.view_mode == ViewMode::Residency {
+                        self.chart.residency.recompute(self.chart.freq_map.cores());
+                    }
+                    if self.view_mode == ViewMode::Affinity {
+                        self.chart.affinity.sample();
+                    }
+                    self.sample_memory_profile();
+                    self.sample_cgroup();
+                    self.sample_kernel();
+                    self.sample_stress();
+                    #[cfg(feature = "dbus")]
+                    self.publish_dbus();
+                }
+                self.publish_sample();
+                self.save_snapshot();
+                if self.watchdog.take_timeout() {
+                    return self.update(Message::UpdateTimeout);
+                }
+            }
+            Message::IpcStopped(e) => {
+                self.events.push(
+                    Severity::Warning,
+                    format!("Stopped serving the IPC socket: {}", e),
+                );
+                self.ipc = None;
+            }
+            Message::PingStopped(e) => {
+                self.events
+                    .push(Severity::Warning, format!("Stopped pinging: {}", e));
+                self.chart.ping = None;
+            }
+            #[cfg(feature = "dbus")]
+            Message::DbusStopped(e) => {
+                self.events.push(
+                    Severity::Warning,
+                    format!("Stopped serving {} on D-Bus: {}", dbus::NAME, e),
+                );
+                self.dbus = None;
+            }
+            Message::UpdateTimeout => {
+                self.events.push(
+                    Severity::Warning,
+                    "Sampling blocked the UI for over 2 s; restarted the sampler",
+                );
+                self.chart.recover_from_stall();
+                self.tick_generation += 1;
+            }
+            Message::RestoreHistory => {
+                if let Some(snapshot) = self.pending_restore.take() {
+                    let minutes = snapshot.minutes();
+                    for (name, points) in snapshot.series {
+                        if let Some((_, chart)) =
+                            self.chart.series_mut().find(|(id, _)| id.key() == name)
+                        {
+                            chart.merge_history(points);
+                        }
+                    }
+                    self.events.push(
+                        Severity::Info,
+                        format!(
+                            "Restored {} minutes of history from previous session",
+                            minutes
+                        ),
+                    );
+                }
+            }
+            Message::DiscardHistory => {
+                self.pending_restore = None;
+            }
+            Message::ClearHistory => {
+                self.chart.clear_history();
+            }
+            Message::CopySnapshot => {
+                return clipboard::write(self.chart.summary_line());
+            }
+            Message::DumpState => {
+                return self.probe_sources(Message::DumpProbed);
+            }
+            Message::DumpProbed(probes) => {
+                let Some(path) = dump::default_path() else {
+                    return Command::none();
+                };
+                let dump = StateDump::new(
+                    &self.config,
+                    &probes,
+                    self.chart
+                        .series()
+                        .map(|(id, chart)| (id.key(), chart.raw_data(), &chart.markers[..])),
+                    self.events.iter(),
+                );
+                match dump.save(&path) {
+                    Ok(()) => self.events.push(
+                        Severity::Info,
+                        format!("Dumped state to {}", path.display()),
+                    ),
+                    Err(e) => self.events.push(Severity::Warning, format!("{:#}", e)),
+                }
+            }
+            Message::ExportGnuplot => {
+                let Some(dir) = gnuplot::export_dir() else {
+                    return Command::none();
+                };
+                match self.chart.export_gnuplot(&dir) {
+                    Ok(()) => self.events.push(
+                        Severity::Info,
+                        format!("Exported gnuplot scripts to {}", dir.display()),
+                    ),
+                    Err(e) => self.events.push(
+                        Severity::Warning,
+                        format!("Failed to export gnuplot scripts: {}", e),
+                    ),
+                }
+            }
+            Message::RunStressTest => {
+                let threads = self.chart.sys.cpus().len();
+                self.stress = Some(StressTest::start(
+                    threads,
+                    Duration::from_secs(self.config.stress_ramp_secs.into()),
+                ));
+                self.stress_summary = None;
+                self.events.push(
+                    Severity::Info,
+                    format!("Started stress ramp up to {} threads", threads),
+                );
+                self.chart
+                    .add_marker(clock::now(), "Stress: 1 thread".to_string());
+            }
+            Message::StopStressTest => {
+                if self.stress.take().is_some() {
+                    self.events.push(Severity::Info, "Stopped stress ramp");
+                    self.chart
+                        .add_marker(clock::now(), "Stress: stopped".to_string());
+                }
+            }
+            Message::DismissStressSummary => {
+                self.stress_summary = None;
+            }
+            Message::ShowStats(id) => {
+                self.chart.menu = None;
+                self.stats = Some(id);
+            }
+            Message::CloseStats => {
+                self.stats = None;
+            }
+            Message::OpenPalette => {
+                self.palette = Some(CommandPalette::new());
+                return text_input::focus(palette::input_id());
+            }
+            Message::ClosePalette => {
+                self.palette = None;
+            }
+            Message::Palette(message) => {
+                if let Some(palette) = &mut self.palette {
+                    if let Some(action) = palette.update(message, &self.actions) {
+                        self.palette = None;
+                        return self.update(action);
+                    }
+                }
+            }
+            Message::OpenMarkerInput => {
+                self.marker_input = Some(String::new());
+                return text_input::focus(marker_input_id());
+            }
+            Message::MarkerInput(label) => {
+                self.marker_input = Some(label);
+            }
+            Message::CancelMarker => {
+                self.marker_input = None;
+            }
+            Message::AddMarker(label) => {
+                self.marker_input = None;
+                let label = match label.trim() {
+                    "" => format!("Marker {}", self.chart.markers.len() + 1),
+                    label => label.to_string(),
+                };
+                self.events
+                    .push(Severity::Info, format!("Marker: {}", label));
+                self.chart.add_marker(clock::now(), label);
+            }
+            Message::LoadCompareSession(path) => match history::load(&path) {
+                Ok(snapshot) => self.compare.load(path, snapshot),
+                Err(e) => self.events.push(
+                    Severity::Warning,
+                    format!("Failed to load session {}: {}", path.display(), e),
+                ),
+            },
+            Message::LoadPerfData(path) => {
+                return Command::perform(perfdata::load(path.clone()), move |result| {
+                    Message::PerfDataLoaded(path, result.map_err(|e| e.to_string()))
+                });
+            }
+            Message::PerfDataLoaded(path, Ok(spots)) => {
+                let marked = self.chart.mark_hot_spots(&spots);
+                self.events.push(
+                    Severity::Info,
+                    format!(
+                        "Marked {} of {} perf hot spots from {} on the CPU usage chart",
+                        marked,
+                        spots.len(),
+                        path.display()
+                    ),
+                );
+            }
+            Message::PerfDataLoaded(path, Err(e)) => self.events.push(
+                Severity::Warning,
+                format!("Failed to load {}: {}", path.display(), e),
+            ),
+            Message::ToggleGauge(id) => {
+                let chart = self.config.chart_mut(id);
+                chart.gauge = !chart.gauge;
+                self.chart.apply_config(&self.config, &self.theme);
+                if let Err(e) = self.config.save() {
+                    self.events.push(Severity::Warning, format!("{:#}", e));
+                }
+            }
+            Message::OpenChartMenu(id) => {
+                self.chart.menu = Some(id);
+            }
+            Message::CloseChartMenu => {
+                self.chart.menu = None;
+            }
+            Message::ChartAction(id, action) => {
+                self.chart.menu = None;
+                match action {
+                    ChartAction::Reset => self.chart.simple_chart_mut(id).clear(),
+                    ChartAction::CopyCsv => {
+                        return clipboard::write(self.chart.simple_chart(id).csv());
+                    }
+                    ChartAction::SetWindow(window) => {
+                        self.config.chart_mut(id).window_secs = Some(window.0);
+                    }
+                    ChartAction::ToggleAutoScale => {
+                        let auto_scale = self.chart.simple_chart(id).auto_scale;
+                        self.config.chart_mut(id).auto_scale = Some(!auto_scale);
+                    }
+                    ChartAction::SetUnit(unit) => {
+                        self.config.chart_mut(id).unit = Some(unit);
+                    }
+                }
+                if matches!(
+                    action,
+                    ChartAction::SetWindow(_)
+                        | ChartAction::ToggleAutoScale
+                        | ChartAction::SetUnit(_)
+                ) {
+                    self.chart.apply_config(&self.config, &self.theme);
+                    if let Err(e) = self.config.save() {
+                        self.events.push(Severity::Warning, format!("{:#}", e));
+                    }
+                }
+            }
+            Message::DragStart(id) => self.chart.start_drag(id),
+            Message::TileEntered(id) => {
+                self.chart.hovered = Some(id);
+                if self.chart.drag.is_some() {
+                    self.chart.drop_target = Some(id);
+                }
+            }
+            Message::TileExited(id) => {
+                if self.chart.drop_target == Some(id) {
+                    self.chart.drop_target = None;
+                }
+                // The next tile may have been entered first.
+                if self.chart.hovered == Some(id) {
+                    self.chart.hovered = None;
+                    self.chart.set_cursor(None);
+                }
+            }
+            Message::CursorAt(time) => self.chart.set_cursor(time),
+            Message::DragEnd => {
+                if let Some(layout) = self.chart.end_drag() {
+                    self.config.layout =
+                        Some(layout.iter().map(|id| id.key().to_string()).collect());
+                    if let Err(e) = self.config.save() {
+                        self.events.push(Severity::Warning, format!("{:#}", e));
+                    }
+                }
+            }
+            Message::ToggleEvents => {
+                self.show_events = !self.show_events;
+            }
+            Message::ToggleSources => {
+                if self.sources.take().is_none() {
+                    return self.probe_sources(Message::SourcesProbed);
+                }
+            }
+            Message::SourcesProbed(probes) => {
+                self.sources = Some(probes);
+            }
+            Message::ToggleAlerts => {
+                self.show_alerts = !self.show_alerts;
+            }
+            Message::ToggleOverlayAll => {
+                self.chart.overlay_all = !self.chart.overlay_all;
+            }
+            Message::ToggleKernelActivity => {
+                self.show_kernel = !self.show_kernel;
+            }
+            Message::ViewModeSelected(mode) => {
+                self.view_mode = mode;
+                if mode == ViewMode::Residency {
+                    self.chart.residency.recompute(self.chart.freq_map.cores());
+                }
+            }
+            Message::FocusChanged(focused) => {
+                self.focused = focused;
+            }
+            Message::WindowResized(window, size) => {
+                if let Some((_, popped_size)) = self.popped_out.get_mut(&window) {
+                    *popped_size = size;
+                } else if self.cli_window_size.take() == Some(size) {
+                    // The window opening at the size from the command line.
+                } else if !self.fullscreen {
+                    // Fullscreen isn't a size to come back to.
+                    self.window_size = Some(size);
+                }
+            }
+            Message::PopOut(id) => {
+                self.chart.menu = None;
+                if self.popped_out.values().any(|(popped, _)| *popped == id) {
+                    return Command::none();
+                }
+                self.config.chart_mut(id).popped_out = Some(POP_OUT_SIZE);
+                self.chart.apply_config(&self.config, &self.theme);
+                if let Err(e) = self.config.save() {
+                    self.events.push(Severity::Warning, format!("{:#}", e));
+                }
+                return self.pop_out(id, POP_OUT_SIZE);
+            }
+            // Closing a chart's own window puts it back in the main one.
+            Message::CloseRequested(window) if self.popped_out.contains_key(&window) => {
+                if let Some((id, _)) = self.popped_out.remove(&window) {
+                    self.config.chart_mut(id).popped_out = None;
+                    self.chart.apply_config(&self.config, &self.theme);
+                    if let Err(e) = self.config.save() {
+                        self.events.push(Severity::Warning, format!("{:#}", e));
+                    }
+                }
+                return window::close(window);
+            }
+            Message::CloseRequested(id) => {
+                let mut changed = false;
+                if let Some(size) = self
+                    .window_size
+                    .filter(|&size| Some(size) != self.config.window_size)
+                {
+                    self.config.window_size = Some(size);
+                    changed = true;
+                }
+                // Popped-out charts open again next time, at their size.
+                for &(chart, size) in self.popped_out.values() {
+                    changed |= self.config.chart_mut(chart).popped_out.replace(size) != Some(size);
+                }
+                if changed {
+                    if let Err(e) = self.config.save() {
+                        eprintln!("Failed to save the window sizes: {:#}", e);
+                    }
+                }
+                // Otherwise up to 10 s of history would be lost.
+                self.write_snapshot();
+                // monty exits once its last window is closed.
+                return Command::batch(
+                    self.popped_out
+                        .keys()
+                        .map(|&window| window::close(window))
+                        .chain([window::close(id)]),
+                );
+            }
+            Message::ToggleFullscreen => {
+                self.fullscreen = !self.fullscreen;
+                self.chart.height_scale = if self.fullscreen {
+                    FULLSCREEN_SCALE
+                } else {
+                    1.0
+                };
+                self.chart.apply_config(&self.config, &self.theme);
+                return window::change_mode(
+                    window::Id::MAIN,
+                    if self.fullscreen {
+                        window::Mode::Fullscreen
+                    } else {
+                        window::Mode::Windowed
+                    },
+                );
+            }
+            Message::OpenSettings => {
+                let mut settings = SettingsPanel::new(&self.config);
+                if self.allow_control {
+                    settings.edit_power_limits(self.chart.power_limits);
+                }
+                self.settings = Some(settings);
+            }
+            Message::SetPowerLimit(constraint, watts) if self.allow_control => {
+                return Command::perform(
+                    async move {
+                        PowerLimits::write(constraint, watts)
+                            .map(|()| constraint)
+                            .map_err(|e| format!("{:#}", e))
+                    },
+                    Message::PowerLimitWritten,
+                );
+            }
+            Message::SetPowerLimit(..) => {}
+            Message::PowerLimitWritten(result) => {
+                // Read back what the firmware actually accepted.
+                self.chart.read_power_limits();
+                let limits = self.chart.power_limits;
+                match result {
+                    Ok(constraint) => self.events.push(
+                        Severity::Info,
+                        format!(
+                            "PL{} set to {:.0} W",
+                            constraint + 1,
+                            [limits.pl1, limits.pl2][constraint].unwrap_or_default()
+                        ),
+                    ),
+                    Err(e) => self.events.push(Severity::Warning, e),
+                }
+                if let Some(settings) = &mut self.settings {
+                    settings.edit_power_limits(limits);
+                }
+            }
+            Message::CloseSettings => {
+                self.settings = None;
+                if let Err(e) = self.config.save() {
+                    self.events.push(Severity::Warning, format!("{:#}", e));
+                }
+            }
+            Message::Settings(message) => {
+                if let Some(settings) = &mut self.settings {
+                    settings.update(&mut self.config, message);
+                    self.theme = self.config.theme();
+                    self.chart.apply_config(&self.config, &self.theme);
+                    if let Some(profile) = &mut self.memory_profile {
+                        profile.set_window(Duration::from_secs(self.config.window_secs.into()));
+                        profile.set_byte_units(self.config.byte_units);
+                    }
+                    if let Some(cgroup) = &mut self.cgroup {
+                        cgroup.set_byte_units(self.config.byte_units);
+                    }
+                    self.apply_palette();
+                }
+            }
+        }
+        Command::none()
+    }
+
+    fn view(&self, window: window::Id) -> Element<'_, Self::Message> {
+        if let Some(&(id, [_, height])) = self.popped_out.get(&window) {
+            return self.chart.pop_out_view(id, height as f32);
+        }
+        let header = Row::new()
+            .spacing(20)
+            .align_items(Alignment::Center)
+            .push(
+                Text::new(i18n::t("header.title"))
+                    .size(22)
+                    .font(Font::default()),
+            )
+            .push(Text::new(self.chart.cpu_brand().to_string()))
+            .push(pick_list(
+                ViewMode::ALL,
+                Some(self.view_mode),
+                Message::ViewModeSelected,
+            ))
+            .push(Button::new(Text::new(i18n::t("header.events"))).on_press(Message::ToggleEvents))
+            .push(
+                Button::new(Text::new(i18n::t("header.settings"))).on_press(Message::OpenSettings),
+            )
+            .push_maybe(self.config.manual_sampling.then(|| {
+                Button::new(Text::new(i18n::t("header.sample_now"))).on_press(Message::SampleNow)
+            }))
+            .push(Button::new(Text::new(i18n::t("header.copy"))).on_press(Message::CopySnapshot))
+            .push(Button::new(Text::new(i18n::t("header.clear"))).on_press(Message::ClearHistory))
+            .push(Button::new(Text::new(i18n::t("header.dump_state"))).on_press(Message::DumpState))
+            .push(Text::new(i18n::tf(
+                "header.energy",
+                &[(
+                    "wh",
+                    &i18n::number(&format!("{:.2}", self.chart.energy_wh())),
+                )],
+            )));
+
+        let mut content = Column::new()
+            .spacing(20)
+            .align_items(Alignment::Center)
+            .width(Length::Fill)
+            .height(Length::Fill);
+        if !self.fullscreen {
+            content = content.push(header);
+        }
+
+        if let Some(origin) = &self.offline {
+            content = content.push(Text::new(i18n::tf("offline", &[("origin", origin)])).size(16));
+        }
+
+        if self.debug_overlay {
+            content = content.push(self.chart.debug_overlay());
+        }
+
+        if let Some(palette) = &self.palette {
+            content = content.push(palette.view(&self.actions));
+        }
+
+        if let Some(label) = &self.marker_input {
+            content = content.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .width(Length::Fixed(480.0))
+                    .push(
+                        text_input(i18n::t("marker.placeholder"), label)
+                            .id(marker_input_id())
+                            .on_input(Message::MarkerInput)
+                            .on_submit(Message::AddMarker(label.clone())),
+                    )
+                    .push(
+                        Button::new(Text::new(i18n::t("marker.add")))
+                            .on_press(Message::AddMarker(label.clone())),
+                    )
+                    .push(
+                        Button::new(Text::new(i18n::t("marker.cancel")))
+                            .on_press(Message::CancelMarker),
+                    ),
+            );
+        }
+
+        if let Some(stress) = &self.stress {
+            content = content.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new(i18n::tf(
+                        "stress.running",
+                        &[("threads", &stress.threads().to_string())],
+                    )))
+                    .push(
+                        Button::new(Text::new(i18n::t("stress.stop")))
+                            .on_press(Message::StopStressTest),
+                    ),
+            );
+        }
+        if let Some(stats) = &self.stress_summary {
+            content = content.push(stress::summary_view(stats));
+        }
+        if let Some(id) = self.stats {
+            let series = self.chart.simple_chart(id);
+            let decimals = series.decimals() + 1;
+            content = content.push(
+                series
+                    .statistics_report()
+                    .view(i18n::tf("stats.title", &[("label", id.label())]), |value| {
+                        format!("{:.*}{}", decimals, value, series.unit)
+                    }),
+            );
+        }
+
+        if let Some(snapshot) = self.pending_restore.as_ref().filter(|_| !self.fullscreen) {
+            let minutes = snapshot.minutes();
+            content = content.push(
+                Row::new()
+                    .spacing(10)
+                    .align_items(Alignment::Center)
+                    .push(Text::new(i18n::tf(
+                        "restore.prompt",
+                        &[("minutes", &minutes.to_string())],
+                    )))
+                    .push(
+                        Button::new(Text::new(i18n::t("restore.restore")))
+                            .on_press(Message::RestoreHistory),
+                    )
+                    .push(
+                        Button::new(Text::new(i18n::t("restore.discard")))
+                            .on_press(Message::DiscardHistory),
+                    ),
+            );
+        }
+
+        content = content.push(match (&self.settings, self.view_mode) {
+            (Some(settings), _) => settings.view(&self.config),
+            (None, ViewMode::Charts) => match &self.cgroup {
+                Some(cgroup) => Column::new()
+                    .push(cgroup.view(self.chart.chart_height))
+                    .push(self.chart.view())
+                    .into(),
+                None => self.chart.view(),
+            },
+            (None, ViewMode::Heatmap) => self.chart.heatmap_view(),
+            (None, ViewMode::FreqMap) => self.chart.freq_map_view(),
+            (None, ViewMode::Residency) => self.chart.residency_view(),
+            (None, ViewMode::CoreBars) => self.chart.core_bars_view(),
+            (None, ViewMode::Gauges) => self.chart.gauges_view(),
+            (None, ViewMode::MemoryTimeline) => self.chart.memory_view(),
+            (None, ViewMode::TurboHistogram) => self.chart.turbo_view(),
+            (None, ViewMode::Affinity) => self.chart.affinity_view(),
+            (None, ViewMode::Compare) => self.compare.view(self.chart.chart_height),
+            (None, ViewMode::Memory) => match &self.memory_profile {
+                Some(profile) => profile.view(self.chart.chart_height),
+                None => Container::new(Text::new(i18n::t("memory_profile.hint")))
+                    .padding(20)
+                    .into(),
+            },
+        });
+
+        if self.settings.is_none() && !self.fullscreen {
+            content = content.push(
+                Button::new(Text::new(expander(
+                    self.show_alerts,
+                    i18n::t("expander.alerts"),
+                )))
+                .on_press(Message::ToggleAlerts),
+            );
+            if self.show_alerts {
+                content = content.push(self.chart.alerts.view(&self.chart.palette));
+            }
+
+            if self.irqs.is_some() {
+                content = content.push(
+                    Button::new(Text::new(expander(
+                        self.show_kernel,
+                        i18n::t("expander.kernel"),
+                    )))
+                    .on_press(Message::ToggleKernelActivity),
+                );
+            }
+            if let Some(irqs) = self.irqs.as_ref().filter(|_| self.show_kernel) {
+                content = content.push(irqs.view(self.chart.chart_height));
+            }
+        }
+
+        if self.show_events && !self.fullscreen {
+            content = content.push(self.events.view(&self.chart.palette));
+        }
+
+        if let Some(probes) = self.sources.as_deref().filter(|_| !self.fullscreen) {
+            content = content.push(
+                Container::new(
+                    Text::new(check::report(probes))
+                        .size(13)
+                        .font(Font::MONOSPACE),
+                )
+                .padding(10)
+                .style(iced::theme::Container::Box),
+            );
+        }
+
+        Container::new(content)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(5)
+            .center_x()
+            .center_y()
+            .into()
+    }
+
+    fn subscription(&self) -> Subscription<Self::Message> {
+        const FPS: u64 = 50;
+        // Nobody is watching the redraws while unfocused, so only wake up
+        // for samples (or less often, if configured).
+        let tick = if self.focused {
+            Duration::from_millis(500 / FPS)
+        } else {
+            self.config
+                .unfocused_interval_ms
+                .map_or(self.chart.fastest_interval(), |ms| {
+                    Duration::from_millis(ms.into())
+                })
+        };
+        let mut subscriptions = vec![
+            keyboard::on_key_press(Monty::handle_key),
+            event::listen_with(|event, _| match event {
+                Event::Window(_, window::Event::Focused) => Some(Message::FocusChanged(true)),
+                Event::Window(_, window::Event::Unfocused) => Some(Message::FocusChanged(false)),
+                Event::Window(id, window::Event::Resized { width, height }) => {
+                    Some(Message::WindowResized(id, [width, height]))
+                }
+                Event::Window(id, window::Event::CloseRequested) => {
+                    Some(Message::CloseRequested(id))
+                }
+                _ => None,
+            }),
+        ];
+
+        if !self.config.manual_sampling && self.offline.is_none() {
+            subscriptions.push(
+                every(tick)
+                    .with(self.tick_generation)
+                    .map(|_| Message::Tick),
+            );
+        }
+
+        // The drop can happen anywhere, so listen for the release globally
+        // while a chart is being dragged.
+        if self.chart.drag.is_some() {
+            subscriptions.push(event::listen_with(|event, _| match event {
+                Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                    Some(Message::DragEnd)
+                }
+                _ => None,
+            }));
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    fn theme(&self, _window: window::Id) -> Self::Theme {
+        self.theme.clone()
+    }
+
+    fn style(&self) -> theme::Application {
+        self.platform.style()
+    }
+}
+
+/// How often the power limits and intel_pstate settings are re-read.
+const POWER_LIMITS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How much taller charts get in fullscreen.
+const FULLSCREEN_SCALE: f32 = 1.5;
+/// `[width, height]` a chart's window first opens at when popped out.
+const POP_OUT_SIZE: [u32; 2] = [900, 450];
+/// Height of a popped-out chart's window taken by its title and padding.
+const POP_OUT_CHROME: f32 = 70.0;
+/// Points drawn per trace in low quality mode; plenty for a small screen.
+const LOW_QUALITY_POINTS: usize = 120;
+
+/// Label of a button showing or hiding a section.
+fn expander(open: bool, label: &str) -> String {
+    format!("{} {}", if open { "▾" } else { "▸" }, label)
+}
+
+/// The intel_pstate mode, and whether turbo is disabled, as a badge.
+fn pstate_badge<'a>(pstate: IntelPstate) -> Element<'a, Message> {
+    let mode = match pstate.mode {
+        HwpMode::Active => "active",
+        HwpMode::Passive => "passive",
+        HwpMode::Off => "off",
+    };
+    let turbo = if pstate.no_turbo { ", turbo off" } else { "" };
+    Container::new(Text::new(format!("intel_pstate: {}{}", mode, turbo)).size(13))
+        .padding([2, 8])
+        .style(iced::theme::Container::Box)
+        .into()
+}
+
+fn marker_input_id() -> text_input::Id {
+    text_input::Id::new("marker")
+}
+
+impl Monty {
+    fn sample_memory_profile(&mut self) {
+        let Some(profile) = &mut self.memory_profile else {
+            return;
+        };
+        match profile.update() {
+            Ok(()) => return,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => self.events.push(
+                Severity::Info,
+                format!("Process {} exited, stopped profiling it", profile.pid),
+            ),
+            Err(e) => self.events.push(
+                Severity::Warning,
+                format!("Stopped profiling PID {}: {}", profile.pid, e),
+            ),
+        }
+        self.memory_profile = None;
+    }
+
+    fn sample_cgroup(&mut self) {
+        let Some(cgroup) = &mut self.cgroup else {
+            return;
+        };
+        if let Err(e) = cgroup.update() {
+            self.events.push(
+                Severity::Warning,
+                format!("Stopped tracking cgroup {}: {}", cgroup.path.display(), e),
+            );
+            self.cgroup = None;
+        }
+    }
+
+    fn sample_kernel(&mut self) {
+        if let Some(Err(e)) = self.irqs.as_mut().map(TopIrqPanel::update) {
+            self.events.push(
+                Severity::Warning,
+                format!("Stopped ranking interrupts: {}", e),
+            );
+            self.irqs = None;
+        }
+    }
+
+    /// Hands the chart palette to the views outside `SystemChart`.
+    fn apply_palette(&mut self) {
+        let palette = self.chart.palette;
+        if let Some(profile) = &mut self.memory_profile {
+            profile.set_palette(palette);
+        }
+        if let Some(cgroup) = &mut self.cgroup {
+            cgroup.set_palette(palette);
+        }
+        if let Some(irqs) = &mut self.irqs {
+            irqs.set_palette(palette);
+        }
+        self.compare.set_palette(palette);
+    }
+
+    fn sample_stress(&mut self) {
+        let Some(stress) = &mut self.stress else {
+            return;
+        };
+        let latest = |id| {
+            self.chart
+                .series()
+                .find(|(series, _)| *series == id)
+                .filter(|_| self.chart.available(id))
+                .and_then(|(_, chart)| chart.latest())
+        };
+        match stress.sample(
+            latest(ChartId::Watts),
+            latest(ChartId::Temp),
+            latest(ChartId::Freq),
+        ) {
+            stress::Progress::Running => {}
+            stress::Progress::Stepped(threads) => {
+                self.chart
+                    .add_marker(clock::now(), format!("Stress: {} threads", threads));
+            }
+            stress::Progress::Finished(stats) => {
+                self.stress = None;
+                self.stress_summary = Some(stats);
+                self.events.push(Severity::Info, "Stress ramp finished");
+                self.chart
+                    .add_marker(clock::now(), "Stress: done".to_string());
+            }
+        }
+    }
+
+    fn handle_key(key: Key, modifiers: Modifiers) -> Option<Message> {
+        match key.as_ref() {
+            Key::Character("c") if modifiers.command() => Some(Message::CopySnapshot),
+            Key::Character("p") if modifiers.command() => Some(Message::OpenPalette),
+            Key::Named(key::Named::ArrowUp) => Some(Message::Palette(PaletteMessage::Move(-1))),
+            Key::Named(key::Named::ArrowDown) => Some(Message::Palette(PaletteMessage::Move(1))),
+            Key::Named(key::Named::Escape) => Some(Message::ClosePalette),
+            Key::Character("m") if modifiers.is_empty() => Some(Message::OpenMarkerInput),
+            Key::Character("r") if modifiers.is_empty() => Some(Message::SampleNow),
+            Key::Named(key::Named::F11) => Some(Message::ToggleFullscreen),
+            _ => None,
+        }
+    }
+
+    /// Actions offered by the command palette. Anything that is a plain
+    /// message belongs here.
+    fn actions() -> Vec<(String, Message)> {
+        let mut actions = vec![
+            ("Copy snapshot".to_string(), Message::CopySnapshot),
+            ("Sample now".to_string(), Message::SampleNow),
+            ("Clear history".to_string(), Message::ClearHistory),
+            ("Export gnuplot scripts".to_string(), Message::ExportGnuplot),
+            (
+                "Dump state for a bug report".to_string(),
+                Message::DumpState,
+            ),
+            ("Add marker".to_string(), Message::OpenMarkerInput),
+            ("Open settings".to_string(), Message::OpenSettings),
+            ("Toggle events".to_string(), Message::ToggleEvents),
+            ("Toggle data sources".to_string(), Message::ToggleSources),
+            ("Toggle threshold alerts".to_string(), Message::ToggleAlerts),
+            (
+                "Toggle kernel activity".to_string(),
+                Message::ToggleKernelActivity,
+            ),
+            ("Toggle fullscreen".to_string(), Message::ToggleFullscreen),
+            ("Run stress ramp".to_string(), Message::RunStressTest),
+            (
+                "Toggle single overlay chart".to_string(),
+                Message::ToggleOverlayAll,
+            ),
+        ];
+        actions.extend(
+            ViewMode::ALL
+                .iter()
+                .map(|&mode| (format!("View: {}", mode), Message::ViewModeSelected(mode))),
+        );
+        actions.extend(metrics::ids().map(|id| {
+            (
+                format!("Toggle gauge: {}", id.label()),
+                Message::ToggleGauge(id),
+            )
+        }));
+        actions.extend(metrics::ids().map(|id| {
+            (
+                format!("Statistics: {}", id.label()),
+                Message::ShowStats(id),
+            )
+        }));
+        actions
+    }
+
+    /// Persists the chart buffers every 10s so a crash doesn't lose the
+    /// evidence. A previous session's history waiting to be restored was
+    /// set aside at startup, so it isn't overwritten.
+    fn save_snapshot(&mut self) {
+        if self.last_snapshot.elapsed() >= Duration::from_secs(10) {
+            self.write_snapshot();
+        }
+    }
+
+    /// Hands the charts sampled in the last update, and the buffers if
+    /// asked for, to the IPC clients.
+    fn publish_sample(&mut self) {
+        let sampled = self.chart.sampled.take();
+        let Some(ipc) = &self.ipc else {
+            return;
+        };
+        if let Some((time, due)) = sampled {
+            let readings: Vec<_> = self
+                .chart
+                .series()
+                .filter(|(id, _)| due.contains(id))
+                .filter_map(|(id, chart)| {
+                    Some((id.key(), chart.latest()? as f64 / chart.scale as f64))
+                })
+                .collect();
+            ipc.publish(ipc::sample_line(clock::wall(time), &readings));
+        }
+        if ipc.history_requested() {
+            ipc.send_history(ipc::history_line(self.chart.series().map(|(id, chart)| {
+                let points = chart
+                    .raw_data()
+                    .iter()
+                    .rev()
+                    .map(|(time, value)| (clock::wall(*time), *value as f64 / chart.scale as f64))
+                    .collect();
+                (id.key(), points)
+            })));
+        }
+    }
+
+    /// Hands the headline readings and this tick's alerts to the D-Bus
+    /// service.
+    #[cfg(feature = "dbus")]
+    fn publish_dbus(&self) {
+        let Some(service) = &self.dbus else {
+            return;
+        };
+        let chart = |id| {
+            self.chart
+                .series()
+                .find_map(|(other, chart)| (other == id).then_some(chart))
+        };
+        let reading =
+            |id| chart(id).and_then(|chart| Some(chart.latest()? as f64 / chart.scale as f64));
+        service.publish(dbus::Readings {
+            cpu_usage: reading(ChartId::Usage),
+            frequency_mhz: reading(ChartId::Freq),
+            temperature_c: reading(ChartId::Temp),
+            power_w: reading(ChartId::Watts),
+        });
+        for (id, threshold, excursion) in &self.chart.new_alerts {
+            let scale = chart(*id).map_or(1.0, |chart| chart.scale as f64);
+            service.alert(dbus::Alert {
+                metric: id.key().to_string(),
+                threshold: *threshold as f64 / scale,
+                peak: excursion.peak as f64 / scale,
+                seconds: excursion.duration().num_seconds(),
+            });
+        }
+    }
+
+    /// Probes the data sources in the background and hands them to
+    /// `done`. Only the libsensors probes run here, with the chart's own
+    /// handle, since a second one can't be initialized.
+    fn probe_sources(&self, done: fn(Vec<check::Probe>) -> Message) -> Command<Message> {
+        let sensors = check::sensor_probes(self.chart.sensors.as_ref().map_err(String::as_str));
+        Command::perform(
+            async move {
+                tokio::task::spawn_blocking(move || check::probes_with(sensors))
+                    .await
+                    .unwrap_or_default()
+            },
+            done,
+        )
+    }
+
+    /// Opens a window of `[width, height]` showing just the chart.
+    fn pop_out(&mut self, id: ChartId, [width, height]: [u32; 2]) -> Command<Message> {
+        let mut settings = window::Settings {
+            size: Size::new(width as f32, height as f32),
+            exit_on_close_request: false,
+            ..window::Settings::default()
+        };
+        self.platform.apply(&mut settings);
+        let (window, open) = window::spawn(settings);
+        self.popped_out.insert(window, (id, [width, height]));
+        open
+    }
+
+    /// Saves the history right away, e.g. on exit.
+    fn write_snapshot(&mut self) {
+        if self.offline.is_some() {
+            return;
+        }
+        self.last_snapshot = Instant::now();
+
+        let Some(path) = history::snapshot_path() else {
+            return;
+        };
+        let result = history::save(
+            &path,
+            self.chart
+                .series()
+                .map(|(id, chart)| (id.key(), chart.raw_data())),
+        );
+
+        match result {
+            Err(e) if !self.snapshot_failed => {
+                self.events
+                    .push(Severity::Warning, format!("Failed to save history: {}", e));
+                self.snapshot_failed = true;
+            }
+            Ok(()) => self.snapshot_failed = false,
+            Err(_) => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Tick,
+    /// Samples every chart right away, due or not; the only way samples
+    /// are taken with `manual_sampling`.
+    SampleNow,
+    /// A sample blocked the UI thread; see `Watchdog`.
+    UpdateTimeout,
+    /// The `--ipc-socket` server failed, with why.
+    IpcStopped(String),
+    /// `--ping` failed, with why.
+    PingStopped(String),
+    /// The D-Bus service failed, with why.
+    #[cfg(feature = "dbus")]
+    DbusStopped(String),
+    ToggleEvents,
+    ToggleAlerts,
+    ToggleKernelActivity,
+    ToggleSources,
+    /// The data sources panel's probes are in.
+    SourcesProbed(Vec<check::Probe>),
+    ToggleOverlayAll,
+    RestoreHistory,
+    DiscardHistory,
+    CopySnapshot,
+    ClearHistory,
+    ExportGnuplot,
+    /// Writes a state dump for a bug report; see `dump`.
+    DumpState,
+    /// The dump's probes are in, so it can be written.
+    DumpProbed(Vec<check::Probe>),
+    RunStressTest,
+    StopStressTest,
+    DismissStressSummary,
+    ShowStats(ChartId),
+    CloseStats,
+    OpenChartMenu(ChartId),
+    CloseChartMenu,
+    ChartAction(ChartId, ChartAction),
+    DragStart(ChartId),
+    TileEntered(ChartId),
+    TileExited(ChartId),
+    /// The time under the cursor on a chart, for the crosshair.
+    CursorAt(Option<Time>),
+    DragEnd,
+    ViewModeSelected(ViewMode),
+    FocusChanged(bool),
+    WindowResized(window::Id, [u32; 2]),
+    /// Opens the chart in a window of its own.
+    PopOut(ChartId),
+    CloseRequested(window::Id),
+    ToggleFullscreen,
+    OpenPalette,
+    ClosePalette,
+    Palette(PaletteMessage),
+    OpenMarkerInput,
+    MarkerInput(String),
+    CancelMarker,
+    AddMarker(String),
+    ToggleGauge(ChartId),
+    LoadCompareSession(PathBuf),
+    /// Runs `perf script` on a `perf record` file to mark its hot spots.
+    LoadPerfData(PathBuf),
+    PerfDataLoaded(PathBuf, Result<Vec<HotSpot>, String>),
+    SetPowerLimit(usize, f64),
+    PowerLimitWritten(Result<usize, String>),
+    OpenSettings,
+    CloseSettings,
+    Settings(SettingsMessage),
+}
+
+/// Quick actions in a chart's right-click menu.
+#[derive(Debug, Clone, Copy)]
+enum ChartAction {
+    Reset,
+    CopyCsv,
+    SetWindow(Window),
+    ToggleAutoScale,
+    SetUnit(Unit),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ViewMode {
+    Charts,
+    Heatmap,
+    FreqMap,
+    Residency,
+    CoreBars,
+    Gauges,
+    MemoryTimeline,
+    TurboHistogram,
+    Affinity,
+    Memory,
+    Compare,
+}
+
+impl ViewMode {
+    const ALL: &'static [ViewMode] = &[
+        ViewMode::Charts,
+        ViewMode::Heatmap,
+        ViewMode::FreqMap,
+        ViewMode::Residency,
+        ViewMode::CoreBars,
+        ViewMode::Gauges,
+        ViewMode::MemoryTimeline,
+        ViewMode::TurboHistogram,
+        ViewMode::Affinity,
+        ViewMode::Memory,
+        ViewMode::Compare,
+    ];
+}
+
+impl std::fmt::Display for ViewMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(i18n::t(match self {
+            ViewMode::Charts => "view.charts",
+            ViewMode::Heatmap => "view.heatmap",
+            ViewMode::FreqMap => "view.freq_map",
+            ViewMode::Residency => "view.residency",
+            ViewMode::CoreBars => "view.core_bars",
+            ViewMode::Gauges => "view.gauges",
+            ViewMode::MemoryTimeline => "view.memory_timeline",
+            ViewMode::TurboHistogram => "view.turbo_histogram",
+            ViewMode::Affinity => "view.affinity",
+            ViewMode::Memory => "view.memory",
+            ViewMode::Compare => "view.compare",
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ChartId {
+    Usage,
+    Freq,
+    Temp,
+    Watts,
+    Headroom,
+    TempRate,
+    Ipc,
+    GpuMem,
+    Latency,
+    ContextSwitches,
+    Interrupts,
+    /// A chart from the config's `[[derived]]` list, by position.
+    Derived(usize),
+    /// A chart fed by a `--plugin` library, by registration order.
+    Plugin(usize),
+}
+
+impl ChartId {
+    /// In the UI's language, for the built-in metrics.
+    fn label(self) -> &'static str {
+        let descriptor = metrics::descriptor(self);
+        i18n::lookup(&format!("metric.{}", descriptor.key)).unwrap_or(descriptor.label)
+    }
+
+    /// Stable name used in the config file and history snapshots.
+    fn key(self) -> &'static str {
+        metrics::descriptor(self).key
+    }
+}
+
+struct SystemChart {
+    sys: System,
+    /// Why not, when libsensors isn't installed or configured; the sysfs
+    /// sources work without it.
+    sensors: Result<LMSensors, String>,
+    last_sample_time: Instant,
+    sample_interval: Duration,
+    /// One chart per registered metric, in registry order.
+    metrics: Vec<(&'static MetricDescriptor, SimpleChart)>,
+    /// Package temperature when the rate of change was last sampled.
+    last_temp: Option<(Instant, i32)>,
+    /// Package power as last read, for the TDP headroom.
+    last_watts: f64,
+    /// Unset when the kernel won't give us hardware counters.
+    perf: Option<PerfEventReader>,
+    /// Unset without access to the APERF/MPERF MSRs, when package
+    /// frequencies fall back to sysinfo's.
+    #[cfg(feature = "aperf")]
+    effective_freq: Option<EffectiveFrequency>,
+    /// Unset without `--ping`, or once pinging fails.
+    ping: Option<PingMonitor>,
+    /// Redraw every chart on each sample, not only those with new samples.
+    redraw_all: bool,
+    /// Sample above which the latency chart turns red: the chart's own
+    /// threshold, else `ping_threshold_ms` in samples.
+    latency_threshold: i32,
+    /// Libraries from `--plugin`, with their charts.
+    plugins: Vec<(ChartId, Plugin)>,
+    heatmap: CpuHeatMap,
+    freq_map: FrequencyHeatMap,
+    /// Only recomputed while its view is shown.
+    residency: FrequencyResidency,
+    core_bars: CoreBars,
+    /// Unset once `/proc/meminfo` can't be read.
+    memory: Option<MemoryTimeline>,
+    /// The frequency chart's samples over the whole session, by bin.
+    turbo: TurboHistogram,
+    disks: DiskPanel,
+    process_power: ProcessPower,
+    /// Only updated while shown; reading every process is not free.
+    affinity: CpuAffinityView,
+    chart_height: f32,
+    columns: usize,
+    /// Multiplies the configured chart height, e.g. in fullscreen.
+    height_scale: f32,
+    /// Every reading of the power thread, which samples faster than the UI.
+    power_samples: Receiver<(Time, f64)>,
+    /// Smoothing of the RAPL wattage, read by the power thread.
+    power_alpha: Arc<Mutex<f64>>,
+    /// Package energy consumed since launch (or the last clear), in joules.
+    energy_joules: Arc<Mutex<f64>>,
+    /// The power thread's last RAPL reading, before filtering.
+    power_raw: Arc<Mutex<Option<RawReading>>>,
+    /// Why the power thread stopped, until an update logs it.
+    power_error: Arc<Mutex<Option<io::Error>>>,
+    /// Package power limit in watts, 0 if it couldn't be read.
+    package_tdp: f64,
+    /// PL1 and PL2 drawn on the power chart; tools like `powercap-set`
+    /// change them at runtime, so they're re-read now and then.
+    power_limits: PowerLimits,
+    power_limits_read: Instant,
+    environment: Environment,
+    temp_source: Option<TempSource>,
+    power_source: Option<PowerSource>,
+    gpu: Option<GpuSource>,
+    amd_gpus: Vec<DrmAmdGpuReader>,
+    /// Unset without the counters in `/proc/stat`.
+    kernel: Option<ProcStatExtReader>,
+    /// Set when monty's cgroup has a CPU quota; usage is then relative to
+    /// the quota instead of the host, if `config.scale_usage_to_quota`.
+    cpu_quota: Option<CpuQuota>,
+    scale_to_quota: bool,
+    throttled: bool,
+    boost: Option<bool>,
+    /// Unset without the intel_pstate driver.
+    pstate: Option<IntelPstate>,
+    /// cpufreq's scaling_min_freq and scaling_max_freq in MHz.
+    scaling_limits: Option<(u64, u64)>,
+    hwmon_chips: Vec<String>,
+    /// DRM cards with their driver, like `card0 (amdgpu)`.
+    gpus: Vec<String>,
+    /// Processes of the names in `watch_processes`.
+    process_watch: ProcessWatch,
+    layout: Vec<ChartId>,
+    /// Draw every chart as one line on a single shared chart.
+    overlay_all: bool,
+    palette: ChartPalette,
+    drag: Option<ChartId>,
+    drop_target: Option<ChartId>,
+    /// The tile under the cursor.
+    hovered: Option<ChartId>,
+    /// Time under the cursor, crossed on every chart.
+    cursor_time: Option<Time>,
+    /// Chart whose right-click menu is open.
+    menu: Option<ChartId>,
+    /// One entry per finished threshold excursion.
+    alerts: EventLog,
+    /// The excursions finished this tick, with their thresholds.
+    new_alerts: Vec<(ChartId, i32, Excursion)>,
+    /// User annotations, until they scroll out of every chart.
+    markers: Vec<(Time, String)>,
+    /// Hot spots of a `perf record` file, marked on the CPU usage chart.
+    hot_spots: Vec<(Time, String)>,
+    /// When the last update sampled and which charts, until published.
+    sampled: Option<(Time, Vec<ChartId>)>,
+    /// Showing a state dump; every chart with data in it is available.
+    offline: bool,
+}
+
+/// `field` quoted for CSV if it has to be.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Whether a sample is due `elapsed` after the last one. The tick that
+/// drives sampling can fire a little early, and unfocused it runs at the
+/// sample interval itself, so a strict comparison would skip every other
+/// tick; a tenth of the interval is close enough.
+fn interval_elapsed(elapsed: Duration, interval: Duration) -> bool {
+    elapsed + interval / 10 >= interval
+}
+
+impl SystemChart {
+    fn new(config: &Config, theme: &Theme) -> Self {
+        let sys = System::new_with_specifics(
+            RefreshKind::new().with_cpu(CpuRefreshKind::new().with_cpu_usage()),
+        );
+        let sensors = sources::init_sensors();
+        let now = clock::now();
+        let cpu_freq = sources::average_frequency(&sys);
+        let environment = virt::detect();
+        // A guest's sensors belong to the host, so don't even try.
+        let mut temp_source = (!environment.is_virtualized())
+            .then(|| TempSource::probe(sensors.as_ref().ok()))
+            .flatten();
+        let pkg_temp = temp_source
+            .as_mut()
+            .map_or(0, |s| s.read(sensors.as_ref().ok()));
+
+        // Bounded so a stalled UI can't grow it forever; a minute at 10 Hz.
+        let (power_tx, power_samples) = mpsc::sync_channel(600);
+        let energy_joules = Arc::new(Mutex::new(0.0));
+        let power_raw = Arc::new(Mutex::new(None));
+        let power_alpha = Arc::new(Mutex::new(1.0));
+        let heatmap = CpuHeatMap::new(sys.cpus().len(), config.gradient.clone());
+        let affinity = CpuAffinityView::new(sys.cpus().len());
+        let freq_map = FrequencyHeatMap::new(sys.cpus().len());
+        let perf = PerfEventReader::open(sys.cpus().len()).ok();
+
+        // RAPL where there is one, otherwise a board power monitor. An MSR
+        // file whose RAPL registers can't be read counts as none, and the
+        // first update logs why.
+        let mut package_tdp = 0.0;
+        let mut rapl_error = None;
+        let msr_file = (cfg!(target_arch = "x86_64") && !environment.is_virtualized())
+            .then(|| sources::open_msr().ok())
+            .flatten()
+            .and_then(
+                |mut msr_file| match SystemChart::energy_unit(&mut msr_file) {
+                    Ok(energy_unit) => Some((msr_file, energy_unit)),
+                    Err(e) => {
+                        rapl_error = Some(e);
+                        None
+                    }
+                },
+            );
+        let power_error = Arc::new(Mutex::new(rapl_error));
+        #[cfg(feature = "aperf")]
+        let mut effective_freq = None;
+        let power_source = match msr_file {
+            Some((mut msr_file, energy_unit)) => {
+                package_tdp = SystemChart::get_package_tdp(&mut msr_file);
+                #[cfg(feature = "aperf")]
+                let aperf = aperf::open(sys.cpus().len())
+                    .ok()
+                    .map(|(reader, frequency)| {
+                        effective_freq = Some(frequency);
+                        reader
+                    });
+                SystemChart::spawn_power_thread(
+                    msr_file,
+                    energy_unit,
+                    #[cfg(feature = "aperf")]
+                    aperf,
+                    power_tx,
+                    energy_joules.clone(),
+                    power_alpha.clone(),
+                    power_raw.clone(),
+                    power_error.clone(),
+                );
+                Some(PowerSource::Msr)
+            }
+            None if !environment.is_virtualized() => {
+                sensors.as_ref().ok().and_then(PowerSource::probe_hwmon)
+            }
+            None => None,
+        };
+
+        let mut chart = Self {
+            sys,
+            sensors,
+            last_sample_time: Instant::now(),
+            sample_interval: Duration::from_millis(500),
+            metrics: metrics::descriptors()
+                .map(|metric| {
+                    let initial = match config.chart(metric.id).source.or(metric.source) {
+                        Some(SampleSource::AverageFreq) => Some(cpu_freq as i32),
+                        Some(SampleSource::PackageTemp) => Some(pkg_temp),
+                        Some(
+                            SampleSource::PkgWatts | SampleSource::TempRate | SampleSource::Ipc,
+                        ) => Some(0),
+                        Some(SampleSource::TdpHeadroom) => Some(100),
+                        #[cfg(feature = "aperf")]
+                        Some(SampleSource::PackageFreq(_)) => None,
+                        // Left to the first update. sysinfo computes usage
+                        // between two refreshes, so the CPU usage would
+                        // read 0 now.
+                        Some(
+                            SampleSource::GlobalCpu
+                            | SampleSource::Core(_)
+                            | SampleSource::CoreFreq(_)
+                            | SampleSource::CoreTemp(_)
+                            | SampleSource::GpuMemory
+                            | SampleSource::GpuTemp
+                            | SampleSource::PingLatency
+                            | SampleSource::ContextSwitches
+                            | SampleSource::Interrupts,
+                        )
+                        | None => None,
+                    };
+                    let mut chart = SimpleChart::new(
+                        initial.map(|value| (now, value)).into_iter(),
+                        metric.unit.into(),
+                        metric.default_max * metric.scale,
+                    );
+                    chart.min_value = metric.default_min * metric.scale;
+                    chart.scale = metric.scale;
+                    chart.track_staleness = true;
+                    chart.crosshair = true;
+                    (metric, chart)
+                })
+                .collect(),
+            last_temp: None,
+            last_watts: 0.0,
+            perf,
+            #[cfg(feature = "aperf")]
+            effective_freq,
+            ping: None,
+            latency_threshold: 0,
+            redraw_all: false,
+            plugins: Vec::new(),
+            package_tdp,
+            power_limits: PowerLimits::default(),
+            power_limits_read: Instant::now(),
+            environment,
+            temp_source,
+            power_source,
+            gpu: GpuSource::probe(),
+            amd_gpus: DrmAmdGpuReader::probe(),
+            kernel: ProcStatExtReader::probe(),
+            cpu_quota: CpuQuota::detect(),
+            scale_to_quota: false,
+            heatmap,
+            freq_map,
+            residency: FrequencyResidency::default(),
+            core_bars: CoreBars::new(config.gradient.clone()),
+            memory: Some(MemoryTimeline::new()),
+            turbo: TurboHistogram::new(),
+            disks: DiskPanel::new(),
+            process_power: ProcessPower::default(),
+            affinity,
+            chart_height: 300.0,
+            columns: 2,
+            height_scale: 1.0,
+            power_samples,
+            power_alpha,
+            energy_joules,
+            power_raw,
+            power_error,
+            throttled: false,
+            boost: SystemChart::get_boost_state(),
+            pstate: None,
+            scaling_limits: None,
+            hwmon_chips: SystemChart::get_hwmon_chips(),
+            gpus: SystemChart::get_gpus(),
+            process_watch: ProcessWatch::default(),
+            layout: config.layout(),
+            overlay_all: false,
+            palette: ChartPalette::default(),
+            drag: None,
+            drop_target: None,
+            hovered: None,
+            cursor_time: None,
+            menu: None,
+            alerts: EventLog::default(),
+            new_alerts: Vec::new(),
+            markers: Vec::new(),
+            hot_spots: Vec::new(),
+            sampled: None,
+            offline: false,
+        };
+
+        chart.apply_config(config, theme);
+        chart.read_power_limits();
+        chart.read_pstate();
+
+        chart
+    }
+
+    /// Labels PL1 and PL2 on the power chart, raising its range so that
+    /// both lines fit with some room above.
+    fn read_power_limits(&mut self) {
+        self.power_limits_read = Instant::now();
+        if self.environment.is_virtualized() {
+            return;
+        }
+        let limits = PowerLimits::read();
+        if limits == self.power_limits {
+            return;
+        }
+        self.power_limits = limits;
+
+        let watts = self.simple_chart_mut(ChartId::Watts);
+        watts.limits = [("PL1", limits.pl1), ("PL2", limits.pl2)]
+            .into_iter()
+            .filter_map(|(name, watts)| watts.map(|w| (w, format!("{} {:.0} W", name, w))))
+            .collect();
+        if let Some(highest) = limits.pl2.or(limits.pl1) {
+            let top = (highest * 1.2 / 10.0).ceil() as i32 * 10;
+            watts.max_value = watts.max_value.max(top);
+        }
+        watts.cache.clear();
+    }
+
+    /// Marks intel_pstate's performance bounds on the frequency chart, and
+    /// shades the range the cpufreq governor may pick from.
+    fn read_pstate(&mut self) {
+        let pstate = IntelPstate::read();
+        let scaling_limits = sources::scaling_limits();
+        if pstate == self.pstate && scaling_limits == self.scaling_limits {
+            return;
+        }
+        self.pstate = pstate;
+        self.scaling_limits = scaling_limits;
+
+        let mut limits = pstate
+            .filter(|pstate| pstate.mode != HwpMode::Off)
+            .zip(sources::max_frequency())
+            .map_or(Vec::new(), |(pstate, max_mhz)| pstate.perf_limits(max_mhz));
+        if let Some((min_mhz, max_mhz)) = scaling_limits {
+            limits.push((min_mhz as f64, format!("scaling min {} MHz", min_mhz)));
+            limits.push((max_mhz as f64, format!("scaling max {} MHz", max_mhz)));
+        }
+        let freq = self.simple_chart_mut(ChartId::Freq);
+        freq.limits = limits;
+        freq.range = scaling_limits.map(|(min_mhz, max_mhz)| [min_mhz as f64, max_mhz as f64]);
+        freq.cache.clear();
+    }
+
+    /// Joules per tick of the package energy counter, from
+    /// MSR_RAPL_POWER_UNIT (1/2^ESU J, bits 12:8).
+    fn energy_unit(msr_file: &mut File) -> io::Result<f64> {
+        let mut msr_res = [0; 8];
+        msr_file.seek(std::io::SeekFrom::Start(0x606))?;
+        msr_file.read_exact(&mut msr_res)?;
+        Ok(0.5f64.powi(((u64::from_le_bytes(msr_res) >> 8) & 0x1f) as i32))
+    }
+
+    /// Samples the package energy counter every 100ms, sending the
+    /// smoothed wattage and accumulating the energy used, and, with the
+    /// `aperf` feature, the APERF and MPERF counters if they can be read. Stops at the first MSR that
+    /// can't be read, leaving the error in `power_error`.
+    #[allow(clippy::too_many_arguments)]
+    fn spawn_power_thread(
+        mut msr_file: File,
+        energy_unit: f64,
+        #[cfg(feature = "aperf")] mut aperf: Option<AperfReader>,
+        power_tx: SyncSender<(Time, f64)>,
+        energy_joules: Arc<Mutex<f64>>,
+        power_alpha: Arc<Mutex<f64>>,
+        power_raw: Arc<Mutex<Option<RawReading>>>,
+        power_error: Arc<Mutex<Option<io::Error>>>,
+    ) {
+        thread::spawn(move || {
+            let mut read = |register| -> io::Result<u64> {
+                let mut msr_res = [0; 8];
+                msr_file.seek(std::io::SeekFrom::Start(register))?;
+                msr_file.read_exact(&mut msr_res)?;
+                Ok(u64::from_le_bytes(msr_res))
+            };
+
+            let mut filter = PowerFilter::new(energy_unit);
+            let mut pdraw = 0;
+            loop {
+                // MSR_PKG_ENERGY_STATUS, a 32-bit counter.
+                let new_pdraw = match read(0x611) {
+                    Ok(status) => status as u32,
+                    Err(e) => {
+                        *power_error.lock().unwrap() = Some(e);
+                        return;
+                    }
+                };
+
+                let alpha = *power_alpha.lock().unwrap();
+                if let Some(watts) = filter.observe(SystemTime::now(), new_pdraw, alpha) {
+                    // Drop the sample rather than block if nobody is draining.
+                    let _ = power_tx.try_send((clock::now(), watts));
+                }
+                *power_raw.lock().unwrap() = filter.last_raw();
+                if pdraw != 0 {
+                    *energy_joules.lock().unwrap() +=
+                        new_pdraw.wrapping_sub(pdraw) as f64 * energy_unit;
+                }
+
+                pdraw = new_pdraw;
+                #[cfg(feature = "aperf")]
+                if let Some(aperf) = &mut aperf {
+                    aperf.read();
+                }
+                sleep(Duration::from_millis(100));
+            }
+        });
+    }
+
+    /// Pushes the (possibly just edited) config into the live charts.
+    fn apply_config(&mut self, config: &Config, theme: &Theme) {
+        let window = Duration::from_secs(config.window_secs.into());
+        for (id, series) in self.series_mut() {
+            let chart_config = config.chart(id);
+            series.source = metrics::descriptor(id)
+                .source
+                .map(|own| chart_config.source.unwrap_or(own));
+            series.gradient = chart_config.heat_gradient.then(|| config.gradient.clone());
+            series.style = chart_config.style;
+            series.limit = chart_config
+                .window_secs
+                .map_or(window, |secs| Duration::from_secs(secs.into()));
+            series.set_thresholds(&chart_config);
+            series.ewma_alpha = chart_config.ewma_alpha;
+            series.log_scale = chart_config.log_scale;
+            series.gauge = chart_config.gauge;
+            series.auto_scale = chart_config
+                .auto_scale
+                .unwrap_or(metrics::descriptor(id).auto_scale);
+            series.floor_at_zero = !chart_config
+                .allow_negative
+                .unwrap_or(metrics::descriptor(id).default_min < 0);
+            series.precision = chart_config.precision;
+            series.title = chart_config.title;
+            let unit = metrics::descriptor(id).unit;
+            series.converter = Unit::from_suffix(unit)
+                .zip(chart_config.unit)
+                .and_then(|(from, to)| UnitConverter::new(from, to))
+                .filter(|converter| converter.from != converter.to);
+            series.unit = series
+                .converter
+                .map_or(unit.to_string(), |converter| converter.to.suffix());
+            series.smooth = config.smooth_scroll;
+            series.low_quality = config.low_quality;
+            // Samples only come on request (or not at all, offline), so no
+            // chart is ever late, and they are too far apart to read as a
+            // line.
+            series.track_staleness = !config.manual_sampling && !config.offline;
+            series.mark_samples = config.manual_sampling;
+            series.interval = Duration::from_millis(
+                chart_config
+                    .sample_interval_ms
+                    .unwrap_or(config.sample_interval_ms)
+                    .into(),
+            );
+        }
+        // Echoes come once a second however often the charts sample, so
+        // the chart only goes stale once a few in a row are lost.
+        let latency = self.simple_chart_mut(ChartId::Latency);
+        latency.interval = latency.interval.max(ping::INTERVAL);
+        let threshold = config
+            .chart(ChartId::Latency)
+            .threshold
+            .unwrap_or(config.ping_threshold_ms.into());
+        self.latency_threshold = latency.sample(threshold);
+        self.redraw_all = config.redraw_all_charts;
+        self.heatmap.set_limit(window);
+        if let Some(memory) = &mut self.memory {
+            memory.set_limit(window);
+            memory.byte_units = config.byte_units;
+        }
+        self.freq_map.set_limit(window);
+        self.residency.set_bins(config.frequency_bins.clone());
+        self.process_watch.set_names(&config.watch_processes);
+        self.disks.warning_percent = config.disk_warning_percent;
+        self.disks.byte_units = config.byte_units;
+        self.simple_chart_mut(ChartId::GpuMem).byte_units = Some(config.byte_units);
+        *self.power_alpha.lock().unwrap() = config.power_smoothing_alpha.clamp(0.01, 1.0).into();
+        self.chart_height = config.chart_height * self.height_scale;
+        self.columns = config.columns.clamp(1, 4) as usize;
+        self.layout = self.visible_layout(config);
+        self.sample_interval = Duration::from_millis(config.sample_interval_ms.into());
+        self.scale_to_quota = config
+            .scale_usage_to_quota
+            .unwrap_or(self.environment == Environment::Container);
+        self.apply_theme(theme, config);
+    }
+
+    /// Hands the configured palette to every chart and picks each line
+    /// color from the theme generated from it, unless the config sets one
+    /// explicitly.
+    fn apply_theme(&mut self, theme: &Theme, config: &Config) {
+        let extended = theme.extended_palette();
+        let palette = config.palette();
+        for (id, series) in self.series_mut() {
+            let metric = metrics::descriptor(id);
+            series.set_palette(palette);
+            series.color = match config.chart(id).color {
+                Some(color) => colors::rgb(color),
+                None => {
+                    let color = (metric.default_color)(extended).into_rgba8();
+                    RGBColor(color[0], color[1], color[2])
+                }
+            };
+            series.warn_above = metric
+                .warn_above
+                .map(|limit| (limit, colors::rgb(palette.warning)));
+        }
+        let threshold = self.latency_threshold;
+        self.simple_chart_mut(ChartId::Latency).warn_above =
+            Some((threshold, colors::rgb(palette.danger)));
+        self.heatmap.set_palette(palette);
+        self.freq_map.set_palette(palette);
+        self.residency.set_palette(palette);
+        self.core_bars.set_palette(palette);
+        self.turbo.set_palette(palette);
+        self.affinity.set_palette(palette);
+        if let Some(memory) = &mut self.memory {
+            memory.set_palette(palette);
+        }
+        self.palette = palette;
+    }
+
+    /// Replaces every buffer with a state dump's, dropping anything read
+    /// from this machine, like the power limits.
+    fn show_offline(&mut self, buffers: Vec<(String, history::Series)>) {
+        self.offline = true;
+        self.pstate = None;
+        self.scaling_limits = None;
+        for (_, series) in self.series_mut() {
+            series.clear();
+            series.limits.clear();
+            series.range = None;
+        }
+        for (key, points) in buffers {
+            if let Some((_, chart)) = self.series_mut().find(|(id, _)| id.key() == key) {
+                chart.merge_history(points);
+            }
+        }
+        // Line the charts up on the newest dumped sample, like live ones.
+        let newest = self
+            .series()
+            .filter_map(|(_, series)| series.raw_data().front().map(|(time, _)| *time))
+            .max();
+        for (_, series) in self.series_mut() {
+            series.anchor = newest;
+        }
+    }
+
+    /// Forgets what the last samples were relative to, so nothing is
+    /// computed across the gap a hung update left.
+    fn recover_from_stall(&mut self) {
+        self.last_temp = None;
+        self.last_watts = 0.0;
+        self.last_sample_time = Instant::now();
+    }
+
+    #[inline]
+    fn should_update(&self) -> bool {
+        interval_elapsed(self.last_sample_time.elapsed(), self.sample_interval)
+    }
+
+    fn simple_chart(&self, id: ChartId) -> &SimpleChart {
+        self.series()
+            .find_map(|(metric, chart)| (metric == id).then_some(chart))
+            .expect("every ChartId is registered")
+    }
+
+    /// The configured layout, without the latency chart unless pinging.
+    fn visible_layout(&self, config: &Config) -> Vec<ChartId> {
+        let mut layout = config.layout();
+        layout.retain(|&id| id != ChartId::Latency || self.ping.is_some());
+        layout.retain(|&id| config.chart(id).popped_out.is_none());
+        layout
+    }
+
+    fn simple_chart_mut(&mut self, id: ChartId) -> &mut SimpleChart {
+        self.series_mut()
+            .find_map(|(metric, chart)| (metric == id).then_some(chart))
+            .expect("every ChartId is registered")
+    }
+
+    fn series(&self) -> impl Iterator<Item = (ChartId, &SimpleChart)> {
+        self.metrics
+            .iter()
+            .map(|(metric, chart)| (metric.id, chart))
+    }
+
+    fn series_mut(&mut self) -> impl Iterator<Item = (ChartId, &mut SimpleChart)> {
+        self.metrics
+            .iter_mut()
+            .map(|(metric, chart)| (metric.id, chart))
+    }
+
+    fn set_cursor(&mut self, time: Option<Time>) {
+        if time == self.cursor_time {
+            return;
+        }
+        self.cursor_time = time;
+        for (_, series) in self.series_mut() {
+            series.cursor_time = time;
+        }
+    }
+
+    /// Samples the charts that are due, or all of them with `force`, each
+    /// from its source. The heatmap and event detection run at the global
+    /// rate; returns whether they did.
+    fn update(&mut self, events: &mut EventLog, force: bool) -> bool {
+        if let Some(e) = self.power_error.lock().unwrap().take() {
+            events.push(
+                Severity::Warning,
+                format!("Can't read package power from RAPL: {}", e),
+            );
+        }
+        let due: Vec<ChartId> = self
+            .series()
+            .filter(|(_, s)| force || s.due())
+            .map(|(id, _)| id)
+            .collect();
+        let global = force || self.should_update();
+        if due.is_empty() && !global {
+            // The x-axis moves even without a new sample, and so does the
+            // age on a stale chart.
+            let now = clock::now();
+            for (_, series) in self.series_mut() {
+                if series.stale_age_changed(now) || series.smooth {
+                    series.cache.clear();
+                }
+            }
+            return false;
+        }
+
+        let bound: Vec<(ChartId, SampleSource)> = due
+            .iter()
+            .filter_map(|&id| Some((id, self.simple_chart(id).source?)))
+            .collect();
+        if global || bound.iter().any(|(_, source)| source.reads_cpus()) {
+            self.sys.refresh_cpu();
+        }
+        let now = clock::now();
+
+        // Each source is read once however many charts show it, as the
+        // power thread's samples can only be taken once.
+        let mut readings = HashMap::new();
+        for (id, source) in bound {
+            let reading = readings
+                .entry(source)
+                .or_insert_with(|| self.read(source, now));
+            let chart = self.simple_chart_mut(id);
+            if let Some(max) = reading.max {
+                chart.max_value = (max * chart.scale as f64).round() as i32;
+            }
+            for &(time, value) in &reading.samples {
+                chart.push_data(time, (value * chart.scale as f64).round() as i32);
+            }
+            // Only what was sampled now, so a source without a new sample
+            // doesn't count its last one again.
+            if id == ChartId::Freq {
+                for &(_, mhz) in &reading.samples {
+                    self.turbo.record(mhz.max(0.0).round() as u64);
+                }
+            }
+        }
+
+        for id in due.iter().copied() {
+            let Some(expr) = metrics::expr(id) else {
+                continue;
+            };
+            let value = expr.eval(&|key| {
+                let input = self.simple_chart(metrics::id(key)?);
+                Some(input.latest()? as f64 / input.scale as f64)
+            });
+            // A division by zero breaks the trace rather than spiking it.
+            let chart = self.simple_chart_mut(id);
+            match value {
+                Some(value) if value.is_finite() => {
+                    let sample = (value * chart.scale as f64).round() as i32;
+                    chart.push_data(now, sample);
+                }
+                Some(_) => chart.push_break(now),
+                None => {}
+            }
+        }
+        let readings: Vec<_> = self
+            .plugins
+            .iter_mut()
+            .filter(|(id, _)| due.contains(id))
+            .filter_map(|(id, plugin)| match plugin.collect() {
+                Ok(value) => Some((*id, value?)),
+                Err(e) => {
+                    events.push(Severity::Warning, e.to_string());
+                    None
+                }
+            })
+            .collect();
+        for (id, value) in readings {
+            self.simple_chart_mut(id).push_data(now, value);
+        }
+
+        // Every chart ends at the newest sample of any chart, so different
+        // rates still line up on the time axis once redrawn.
+        let redraw_all = self.redraw_all;
+        for (id, series) in self.series_mut() {
+            if due.contains(&id) {
+                series.last_sample = Some(Instant::now());
+            }
+            series.end_update(now, redraw_all);
+        }
+        // Markers are kept for export; each chart prunes its own copy.
+        let cutoff = self.window_start(now);
+        self.hot_spots.retain(|(time, _)| *time >= cutoff);
+        if !due.is_empty() {
+            self.sampled = Some((now, due));
+        }
+
+        if !global {
+            return false;
+        }
+        self.last_sample_time = Instant::now();
+        self.heatmap
+            .push_data(now, self.sys.cpus().iter().map(|c| c.cpu_usage()));
+        self.freq_map
+            .push_data(now, sources::core_frequencies(&self.sys));
+        self.core_bars
+            .push_data(self.sys.cpus().iter().map(|c| c.cpu_usage()));
+        if let Some(Err(e)) = self.memory.as_mut().map(|memory| memory.update(now)) {
+            events.push(Severity::Warning, format!("Stopped charting memory: {}", e));
+            self.memory = None;
+        }
+        self.disks.update();
+        if self.source_available(SampleSource::PkgWatts) {
+            self.process_power.update(self.last_watts);
+        }
+        if self.power_limits_read.elapsed() >= POWER_LIMITS_INTERVAL {
+            self.read_power_limits();
+            self.read_pstate();
+        }
+
+        self.detect_events(sources::average_frequency(&self.sys), events);
+        true
+    }
+
+    /// The shortest interval any chart samples at.
+    fn fastest_interval(&self) -> Duration {
+        self.series()
+            .map(|(_, s)| s.interval)
+            .fold(self.sample_interval, Duration::min)
+    }
+
+    /// One `<key>.gnuplot` script per available chart in `dir`.
+    fn export_gnuplot(&self, dir: &Path) -> io::Result<()> {
+        for (id, series) in self.series().filter(|(id, _)| self.available(*id)) {
+            let path = dir.join(format!("{}.gnuplot", id.key()));
+            gnuplot::export_gnuplot(series, id.label(), &path)?;
+        }
+        Ok(())
+    }
+
+    /// The latest readings as a single line, for pasting into chat.
+    fn summary_line(&self) -> String {
+        let mut line = String::new();
+        for (id, series) in self.series().filter(|(id, _)| self.available(*id)) {
+            line += &format!(
+                "{} {}{} | ",
+                id.label(),
+                series.display(series.latest().unwrap_or_default().into()),
+                series.unit
+            );
+        }
+        line += &Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let start = self.window_start(clock::now());
+        for (time, label) in self.markers.iter().filter(|(time, _)| *time >= start) {
+            line += &format!(
+                " | {} {}",
+                clock::wall(*time).with_timezone(&Local).format("%H:%M:%S"),
+                label
+            );
+        }
+        line
+    }
+
+    /// Start of the longest chart window ending at `now`.
+    fn window_start(&self, now: Time) -> Time {
+        let longest = self
+            .series()
+            .map(|(_, series)| series.limit)
+            .max()
+            .unwrap_or_default();
+        now - chrono::Duration::from_std(longest).unwrap_or(chrono::Duration::zero())
+    }
+
+    fn add_marker(&mut self, time: Time, label: String) {
+        self.markers.push((time, label));
+        self.sync_markers();
+    }
+
+    /// Marks perf's hot spots on the CPU usage chart and returns how many
+    /// fall inside its window; the others would scroll out right away.
+    fn mark_hot_spots(&mut self, spots: &[HotSpot]) -> usize {
+        let now = clock::now();
+        let limit = self.simple_chart(ChartId::Usage).limit;
+        let cutoff = now - chrono::Duration::from_std(limit).unwrap_or(chrono::Duration::zero());
+        self.hot_spots = spots
+            .iter()
+            .map(|spot| {
+                let label = format!("perf: {}", spot.symbol);
+                (clock::from_monotonic(spot.start), label)
+            })
+            .filter(|(time, _)| (cutoff..=now).contains(time))
+            .collect();
+        self.sync_markers();
+        self.hot_spots.len()
+    }
+
+    /// Hands the markers to every chart, and perf's hot spots to the CPU
+    /// usage chart.
+    fn sync_markers(&mut self) {
+        let markers = self.markers.clone();
+        let hot_spots = self.hot_spots.clone();
+        for (id, series) in self.series_mut() {
+            series.markers = markers.clone();
+            if id == ChartId::Usage {
+                series.markers.extend(hot_spots.iter().cloned());
+            }
+            series.cache.clear();
+        }
+    }
+
+    /// Compares the current system state against the last sample and logs
+    /// anything worth keeping in the session's audit trail.
+    fn detect_events(&mut self, cpu_freq: u64, events: &mut EventLog) {
+        let mut alerts = Vec::new();
+        let mut new_alerts = Vec::new();
+        for (id, series) in self.series_mut() {
+            let threshold = series.excursions.rule().map_or(0, |rule| rule.enter);
+            for excursion in std::mem::take(&mut series.finished_excursions) {
+                alerts.push((
+                    excursion.start,
+                    series.alert_text(id.label(), threshold, &excursion),
+                ));
+                new_alerts.push((id, threshold, excursion));
+            }
+        }
+        for (time, text) in alerts {
+            self.alerts
+                .push_at(clock::wall(time), Severity::Warning, text);
+        }
+        self.new_alerts = new_alerts;
+
+        let throttled = cpu_freq == 399;
+        if throttled != self.throttled {
+            if throttled {
+                events.push(Severity::Critical, "CPU throttled to minimum frequency");
+            } else {
+                events.push(Severity::Info, "CPU frequency recovered from throttling");
+            }
+            self.throttled = throttled;
+        }
+
+        let boost = SystemChart::get_boost_state();
+        if boost != self.boost {
+            match boost {
+                Some(true) => events.push(Severity::Info, "CPU boost enabled"),
+                Some(false) => events.push(Severity::Warning, "CPU boost disabled"),
+                None => events.push(Severity::Warning, "CPU boost state unavailable"),
+            }
+            self.boost = boost;
+        }
+
+        let chips = SystemChart::get_hwmon_chips();
+        for chip in chips.iter().filter(|c| !self.hwmon_chips.contains(c)) {
+            events.push(Severity::Info, format!("Sensor chip added: {}", chip));
+        }
+        for chip in self.hwmon_chips.iter().filter(|c| !chips.contains(c)) {
+            events.push(Severity::Warning, format!("Sensor chip removed: {}", chip));
+        }
+        self.hwmon_chips = chips;
+
+        let gpus = SystemChart::get_gpus();
+        for gpu in gpus.iter().filter(|g| !self.gpus.contains(g)) {
+            events.push(Severity::Info, format!("GPU detected: {}", gpu));
+        }
+        for gpu in self.gpus.iter().filter(|g| !gpus.contains(g)) {
+            events.push(Severity::Warning, format!("GPU lost: {}", gpu));
+        }
+        self.gpus = gpus;
+
+        for change in self.process_watch.update() {
+            match change {
+                ProcessChange::Started { pid, name } => {
+                    events.push(
+                        Severity::Info,
+                        format!("Process started: {} ({})", name, pid),
+                    );
+                }
+                ProcessChange::Exited { pid, name } => {
+                    events.push(
+                        Severity::Info,
+                        format!("Process exited: {} ({})", name, pid),
+                    );
+                }
+            }
+        }
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        if self.overlay_all {
+            let series = self
+                .layout
+                .iter()
+                .filter(|id| self.available(**id))
+                .map(|&id| (id.label(), self.simple_chart(id)))
+                .collect();
+            return Container::new(
+                MultiChart::new(series, self.palette).view(self.chart_height * 2.0),
+            )
+            .padding(20)
+            .into();
+        }
+
+        let mut col = Column::new()
+            .width(Length::Fill)
+            .height(Length::Shrink)
+            .align_items(Alignment::Center);
+
+        for ids in self.layout.chunks(self.columns) {
+            let row = ids.iter().fold(
+                Row::new()
+                    .spacing(15)
+                    .padding(20)
+                    .width(Length::Fill)
+                    .height(Length::Shrink)
+                    .align_items(Alignment::Center),
+                |row, id| row.push(self.tile(*id)),
+            );
+            col = col.push(row);
+        }
+
+        Scrollable::new(col).height(Length::Shrink).into()
+    }
+
+    fn energy_wh(&self) -> f64 {
+        *self.energy_joules.lock().unwrap() / 3600.0
+    }
+
+    fn clear_history(&mut self) {
+        for (_, series) in self.series_mut() {
+            series.clear();
+        }
+        self.heatmap.clear();
+        self.freq_map.clear();
+        if let Some(memory) = &mut self.memory {
+            memory.clear();
+        }
+        self.turbo.clear();
+        self.markers.clear();
+        self.hot_spots.clear();
+        *self.energy_joules.lock().unwrap() = 0.0;
+    }
+
+    fn heatmap_view(&self) -> Element<'_, Message> {
+        let height = (self.sys.cpus().len() as f32 * 24.0 + 80.0).max(self.chart_height);
+        let isolated = if self.heatmap.isolated.is_empty() {
+            i18n::t("heatmap.none")
+        } else {
+            &self.heatmap.isolated
+        };
+        Scrollable::new(
+            Container::new(
+                Column::new()
+                    .spacing(5)
+                    .push(Text::new(i18n::tf(
+                        "heatmap.isolated",
+                        &[("cpus", isolated)],
+                    )))
+                    .push(self.heatmap.view(height)),
+            )
+            .width(Length::Fill)
+            .padding(20),
+        )
+        .height(Length::Shrink)
+        .into()
+    }
+
+    fn freq_map_view(&self) -> Element<'_, Message> {
+        let height = (self.sys.cpus().len() as f32 * 24.0 + 80.0).max(self.chart_height);
+        Scrollable::new(
+            Container::new(self.freq_map.view(height))
+                .width(Length::Fill)
+                .padding(20),
+        )
+        .height(Length::Shrink)
+        .into()
+    }
+
+    fn residency_view(&self) -> Element<'_, Message> {
+        let height = (self.sys.cpus().len() as f32 * 24.0 + 80.0).max(self.chart_height);
+        Scrollable::new(
+            Container::new(self.residency.view(height))
+                .width(Length::Fill)
+                .padding(20),
+        )
+        .height(Length::Shrink)
+        .into()
+    }
+
+    fn memory_view(&self) -> Element<'_, Message> {
+        match &self.memory {
+            Some(memory) => Container::new(memory.view(self.chart_height * 1.5))
+                .width(Length::Fill)
+                .padding(20)
+                .into(),
+            None => Container::new(Text::new(i18n::t("memory.unavailable")))
+                .padding(20)
+                .into(),
+        }
+    }
+
+    /// The frequency chart with the session's histogram of it underneath.
+    fn turbo_view(&self) -> Element<'_, Message> {
+        Scrollable::new(
+            Column::new().push(self.tile(ChartId::Freq)).push(
+                Container::new(self.turbo.view(self.chart_height))
+                    .width(Length::Fill)
+                    .padding(20),
+            ),
+        )
+        .height(Length::Shrink)
+        .into()
+    }
+
+    /// The busiest processes' affinity over the per-core heat map, to tell
+    /// a busy core from one that pinned processes are crowded onto.
+    fn affinity_view(&self) -> Element<'_, Message> {
+        let height = 10.0 * 28.0 + 80.0;
+        let heatmap_height = (self.sys.cpus().len() as f32 * 24.0 + 80.0).max(self.chart_height);
+        Scrollable::new(
+            Container::new(
+                Column::new()
+                    .spacing(5)
+                    .push(Text::new(i18n::t("affinity.hint")))
+                    .push(self.affinity.view(height))
+                    .push(self.heatmap.view(heatmap_height)),
+            )
+            .width(Length::Fill)
+            .padding(20),
+        )
+        .height(Length::Shrink)
+        .into()
+    }
+
+    fn core_bars_view(&self) -> Element<'_, Message> {
+        Container::new(self.core_bars.view(self.chart_height))
+            .width(Length::Fill)
+            .padding(20)
+            .into()
+    }
+
+    /// Current values as bars against each chart's range, without history.
+    fn gauges_view(&self) -> Element<'_, Message> {
+        let available = self.layout.iter().filter(|id| self.available(**id));
+        let col = available.fold(
+            Column::new().spacing(20).padding(20).width(Length::Fill),
+            |col, &id| {
+                let series = self.simple_chart(id);
+                let value = series.latest().unwrap_or(0);
+                let alert = self.alert(id);
+
+                let mut label = Text::new(format!(
+                    "{}: {}{} / {}{}",
+                    id.label(),
+                    series.display(value.into()),
+                    series.unit,
+                    series.display(series.max_value.into()),
+                    series.unit
+                ))
+                .size(24);
+                if let Some(color) = series.title_color(alert) {
+                    label = label.style(color);
+                }
+                let style = if alert || series.over_threshold() {
+                    iced::theme::ProgressBar::Danger
+                } else {
+                    iced::theme::ProgressBar::Primary
+                };
+
+                col.push(label).push(
+                    ProgressBar::new(0.0..=series.max_value as f32, value as f32)
+                        .height(Length::Fixed(40.0))
+                        .style(style),
+                )
+            },
+        );
+
+        let col = if self.available(ChartId::Watts) {
+            col.push(self.process_power.view())
+        } else {
+            col
+        };
+        Scrollable::new(col.push(self.disks.view()))
+            .height(Length::Shrink)
+            .into()
+    }
+
+    fn cpu_brand(&self) -> &str {
+        self.sys
+            .cpus()
+            .first()
+            .map_or("Generic CPU", |cpu| cpu.brand())
+    }
+
+    /// Whether a source for the metric was found at startup.
+    fn available(&self, id: ChartId) -> bool {
+        if self.offline {
+            return !self.simple_chart(id).raw_data().is_empty();
+        }
+        if let Some(source) = self.simple_chart(id).source {
+            return self.source_available(source);
+        }
+        match id {
+            ChartId::Derived(_) => metrics::expr(id).is_some_and(|expr| {
+                expr.metrics()
+                    .into_iter()
+                    .filter_map(metrics::id)
+                    .all(|input| self.available(input))
+            }),
+            _ => true,
+        }
+    }
+
+    /// Whether `source` was found at startup.
+    fn source_available(&self, source: SampleSource) -> bool {
+        match source {
+            SampleSource::GlobalCpu | SampleSource::AverageFreq => true,
+            SampleSource::Core(cpu) | SampleSource::CoreFreq(cpu) => cpu < self.sys.cpus().len(),
+            #[cfg(feature = "aperf")]
+            SampleSource::PackageFreq(package) => self
+                .effective_freq
+                .as_ref()
+                .map_or(package == 0, |frequency| package < frequency.packages()),
+            SampleSource::PackageTemp | SampleSource::TempRate => self.temp_source.is_some(),
+            // Physical cores aren't numbered like the CPUs; only a read
+            // can tell.
+            SampleSource::CoreTemp(_) => self.sensors.is_ok(),
+            SampleSource::PkgWatts => self.power_source.is_some(),
+            SampleSource::TdpHeadroom => self.package_tdp > 0.0,
+            SampleSource::Ipc => self.perf.is_some(),
+            SampleSource::GpuMemory => self.gpu.is_some(),
+            SampleSource::GpuTemp => !self.amd_gpus.is_empty(),
+            SampleSource::PingLatency => self.ping.is_some(),
+            SampleSource::ContextSwitches | SampleSource::Interrupts => self.kernel.is_some(),
+        }
+    }
+
+    /// What `source` reports now: usually one sample, but the power
+    /// thread's since the last read, and none where it has nothing.
+    fn read(&mut self, source: SampleSource, now: Time) -> Reading {
+        let value = match source {
+            SampleSource::GlobalCpu => {
+                match self.cpu_quota.as_mut().filter(|_| self.scale_to_quota) {
+                    Some(quota) => quota.usage_percent().map(f64::from),
+                    None => Some(self.sys.global_cpu_info().cpu_usage().into()),
+                }
+            }
+            SampleSource::Core(cpu) => self.sys.cpus().get(cpu).map(|c| c.cpu_usage().into()),
+            SampleSource::AverageFreq => Some(sources::average_frequency(&self.sys) as f64),
+            SampleSource::CoreFreq(cpu) => sources::core_frequencies(&self.sys)
+                .nth(cpu)
+                .map(|mhz| mhz as f64),
+            #[cfg(feature = "aperf")]
+            SampleSource::PackageFreq(package) => match &self.effective_freq {
+                Some(frequency) => frequency.take(package),
+                None => Some(sources::average_frequency(&self.sys) as f64),
+            },
+            SampleSource::PackageTemp => self
+                .temp_source
+                .as_mut()
+                .map(|source| source.read(self.sensors.as_ref().ok()).into()),
+            SampleSource::CoreTemp(core) => self
+                .sensors
+                .as_ref()
+                .ok()
+                .and_then(|sensors| sources::core_temp(sensors, core)),
+            SampleSource::PkgWatts => {
+                let samples: Vec<_> = match &self.power_source {
+                    Some(PowerSource::Msr) => self.power_samples.try_iter().collect(),
+                    Some(source) => source
+                        .read_hwmon(self.sensors.as_ref().ok())
+                        .map(|watts| (now, watts))
+                        .into_iter()
+                        .collect(),
+                    None => Vec::new(),
+                };
+                if let Some(&(_, watts)) = samples.last() {
+                    self.last_watts = watts;
+                }
+                return Reading { samples, max: None };
+            }
+            SampleSource::TdpHeadroom => (self.package_tdp > 0.0)
+                .then(|| (100.0 * (1.0 - self.last_watts / self.package_tdp)).clamp(0.0, 100.0)),
+            SampleSource::TempRate => self.temp_source.as_mut().and_then(|source| {
+                let temp = source.read(self.sensors.as_ref().ok());
+                let sampled = Instant::now();
+                let (last_time, last_temp) = self.last_temp.replace((sampled, temp))?;
+                let elapsed = sampled.duration_since(last_time).as_secs_f64().max(1e-3);
+                Some((temp - last_temp) as f64 / elapsed)
+            }),
+            SampleSource::Ipc => self.perf.as_mut().and_then(|perf| perf.ipc()),
+            SampleSource::GpuMemory => {
+                let memory = self.gpu.as_ref().and_then(|gpu| gpu.memory());
+                return Reading {
+                    samples: memory
+                        .map(|memory| (now, (memory.used / 1024) as f64))
+                        .into_iter()
+                        .collect(),
+                    max: memory.map(|memory| (memory.total / 1024) as f64),
+                };
+            }
+            SampleSource::GpuTemp => self
+                .get_gpu_temperatures()
+                .into_iter()
+                .flatten()
+                .max_by(f64::total_cmp),
+            SampleSource::ContextSwitches => self
+                .kernel
+                .as_mut()
+                .and_then(|kernel| kernel.rate(Counter::ContextSwitches)),
+            SampleSource::Interrupts => self
+                .kernel
+                .as_mut()
+                .and_then(|kernel| kernel.rate(Counter::Interrupts)),
+            SampleSource::PingLatency => {
+                let Some(ping) = &mut self.ping else {
+                    return Reading::default();
+                };
+                let samples = ping.take();
+                let loss = ping.loss_percent();
+                self.simple_chart_mut(ChartId::Latency).caption = loss.map(|loss| {
+                    i18n::tf(
+                        "tile.packet_loss",
+                        &[("loss", &i18n::number(&format!("{:.0}", loss)))],
+                    )
+                });
+                return Reading { samples, max: None };
+            }
+        };
+        Reading {
+            samples: value.map(|value| (now, value)).into_iter().collect(),
+            max: None,
+        }
+    }
+
+    /// Chart-specific alerts on top of the configurable thresholds.
+    fn alert(&self, id: ChartId) -> bool {
+        match id {
+            ChartId::Freq => self.throttled,
+            ChartId::Headroom => self.simple_chart(id).latest().is_some_and(|h| h < 10),
+            ChartId::Latency => self
+                .simple_chart(id)
+                .latest()
+                .is_some_and(|latency| latency > self.latency_threshold),
+            _ => false,
+        }
+    }
+
+    /// The chart under its title, or why it has no data.
+    fn chart_view(&self, id: ChartId, chart_height: f32) -> Element<'_, Message> {
+        match id {
+            _ if !self.available(id) => Container::new(Text::new(i18n::tf(
+                if self.environment.is_virtualized() {
+                    "tile.unavailable_virtualized"
+                } else {
+                    "tile.unavailable"
+                },
+                &[("label", id.label())],
+            )))
+            .padding(20)
+            .into(),
+            _ => {
+                let series = self.simple_chart(id);
+                let latest = series.latest().unwrap_or_default().into();
+                let (value, unit) = series.display_scaled(latest, latest);
+                let title = metrics::format_title(
+                    series
+                        .title
+                        .as_deref()
+                        .unwrap_or(metrics::descriptor(id).title),
+                    &[
+                        ("label", &self.label(id)),
+                        ("value", &i18n::number(&value)),
+                        ("unit", &unit),
+                        ("tdp", &format!("{:.0}", self.package_tdp)),
+                    ],
+                );
+                series.view(id, title, chart_height, series.title_color(self.alert(id)))
+            }
+        }
+    }
+
+    /// A popped-out chart, filling its window of `height` pixels.
+    fn pop_out_view(&self, id: ChartId, height: f32) -> Element<'_, Message> {
+        Container::new(self.chart_view(id, (height - POP_OUT_CHROME).max(100.0)))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(10)
+            .into()
+    }
+
+    fn tile(&self, id: ChartId) -> Element<'_, Message> {
+        let chart = self.chart_view(id, self.chart_height);
+
+        // While dragging, the picked-up tile is ghosted in place and the
+        // tile under the cursor is outlined as the drop target.
+        let appearance = match self.drag {
+            Some(dragged) if dragged == id => container::Appearance {
+                background: Some(
+                    Color {
+                        a: 0.08,
+                        ..colors::color(self.palette.text)
+                    }
+                    .into(),
+                ),
+                ..Default::default()
+            },
+            Some(_) if self.drop_target == Some(id) => {
+                container::Appearance::default().with_border(colors::color(self.palette.primary), 2)
+            }
+            _ => container::Appearance::default(),
+        };
+
+        let chart = match self.pstate.filter(|_| id == ChartId::Freq) {
+            Some(pstate) => Column::new()
+                .spacing(5)
+                .push(pstate_badge(pstate))
+                .push(chart)
+                .into(),
+            None => chart,
+        };
+
+        let chart = match self.menu {
+            Some(menu) if menu == id => Column::new()
+                .spacing(5)
+                .push(self.chart_menu(id))
+                .push(chart)
+                .into(),
+            _ => chart,
+        };
+
+        mouse_area(Container::new(chart).style(appearance))
+            .on_enter(Message::TileEntered(id))
+            .on_exit(Message::TileExited(id))
+            .on_right_press(Message::OpenChartMenu(id))
+            .into()
+    }
+
+    /// The chart's label; the frequency chart's names who picks the
+    /// P-states, and the usage chart's the quota it is relative to.
+    fn label(&self, id: ChartId) -> String {
+        let quota = self.cpu_quota.as_ref().filter(|_| self.scale_to_quota);
+        match self.pstate.and_then(|pstate| pstate.mode.controller()) {
+            Some(controller) if id == ChartId::Freq => format!("{} ({})", id.label(), controller),
+            _ => match quota.filter(|_| id == ChartId::Usage) {
+                Some(quota) => i18n::tf(
+                    "tile.quota",
+                    &[
+                        ("label", id.label()),
+                        ("cpus", &i18n::number(&format!("{:.1}", quota.cpus))),
+                    ],
+                ),
+                None => id.label().to_string(),
+            },
+        }
+    }
+
+    /// `--debug-overlay`: what the last tick changed, in raw samples, so a
+    /// conversion bug shows before it reaches a chart.
+    fn debug_overlay(&self) -> Element<'_, Message> {
+        Container::new(
+            Text::new(self.debug_lines().join("\n"))
+                .size(12)
+                .font(Font::MONOSPACE),
+        )
+        .padding([4, 8])
+        .style(iced::theme::Container::Box)
+        .into()
+    }
+
+    fn debug_lines(&self) -> Vec<String> {
+        let width = self
+            .series()
+            .map(|(id, _)| id.key().len())
+            .max()
+            .unwrap_or(0);
+        let mut lines: Vec<_> = self
+            .series()
+            .filter_map(|(id, series)| {
+                let (_, latest) = series.data_points.front()?;
+                let delta = series
+                    .last_delta()
+                    .map_or("-".to_string(), |delta| format!("{:+}", delta));
+                Some(format!(
+                    "{:width$}  {:>8}  Δ {:>6}",
+                    id.key(),
+                    latest,
+                    delta,
+                    width = width
+                ))
+            })
+            .collect();
+        if let Some(raw) = *self.power_raw.lock().unwrap() {
+            lines.push(format!(
+                "msr: Δ {} units in {} µs = {:.3} W",
+                raw.consumed,
+                raw.elapsed.as_micros(),
+                raw.watts
+            ));
+        }
+        lines
+    }
+
+    /// Quick actions for one chart, opened by right-clicking it.
+    fn chart_menu(&self, id: ChartId) -> Element<'_, Message> {
+        let series = self.simple_chart(id);
+        let action = move |action| Message::ChartAction(id, action);
+        let window = Window(series.limit.as_secs() as u32);
+        let units = Unit::from_suffix(metrics::descriptor(id).unit)
+            .map(Unit::alternatives)
+            .filter(|units| units.len() > 1);
+        let shown_unit = Unit::from_suffix(&series.unit);
+
+        let mut menu = Row::new()
+            .spacing(8)
+            .align_items(Alignment::Center)
+            .push(
+                Button::new(Text::new(i18n::t("menu.reset")).size(14))
+                    .on_press(action(ChartAction::Reset)),
+            )
+            .push(
+                Button::new(Text::new(i18n::t("menu.copy_csv")).size(14))
+                    .on_press(action(ChartAction::CopyCsv)),
+            )
+            .push(
+                Button::new(Text::new(i18n::t("menu.statistics")).size(14))
+                    .on_press(Message::ShowStats(id)),
+            )
+            .push(
+                Button::new(Text::new(i18n::t("menu.pop_out")).size(14))
+                    .on_press(Message::PopOut(id)),
+            )
+            .push(
+                pick_list(Window::CHOICES, Some(window), move |window| {
+                    action(ChartAction::SetWindow(window))
+                })
+                .text_size(14),
+            )
+            .push(
+                Button::new(
+                    Text::new(i18n::t(if series.auto_scale {
+                        "menu.fixed_scale"
+                    } else {
+                        "menu.auto_scale"
+                    }))
+                    .size(14),
+                )
+                .on_press(action(ChartAction::ToggleAutoScale)),
+            );
+        if let Some(units) = units {
+            menu = menu.push(
+                pick_list(units, shown_unit, move |unit| {
+                    action(ChartAction::SetUnit(unit))
+                })
+                .placeholder(i18n::t("menu.change_unit"))
+                .text_size(14),
+            );
+        }
+
+        Container::new(
+            menu.push(Button::new(Text::new("✕").size(14)).on_press(Message::CloseChartMenu)),
+        )
+        .padding(5)
+        .style(iced::theme::Container::Box)
+        .into()
+    }
+
+    fn start_drag(&mut self, id: ChartId) {
+        self.drag = Some(id);
+        self.drop_target = None;
+    }
+
+    /// Finishes a drag, swapping the dragged tile with the drop target.
+    /// Returns the new layout if it changed.
+    fn end_drag(&mut self) -> Option<Vec<ChartId>> {
+        let (from, to) = (self.drag.take()?, self.drop_target.take()?);
+        if from == to {
+            return None;
+        }
+        let a = self.layout.iter().position(|id| *id == from)?;
+        let b = self.layout.iter().position(|id| *id == to)?;
+        self.layout.swap(a, b);
+        Some(self.layout.clone())
+    }
+
+    /// Reads PL1 from MSR_PKG_POWER_LIMIT (bits 14:0), scaled by the power
+    /// unit from MSR_RAPL_POWER_UNIT (1/2^PU W, bits 3:0).
+    fn get_package_tdp(msr_file: &mut File) -> f64 {
+        let mut read = |register| {
+            let mut msr_res = [0; 8];
+            msr_file.seek(std::io::SeekFrom::Start(register)).ok()?;
+            msr_file.read_exact(&mut msr_res).ok()?;
+            Some(u64::from_le_bytes(msr_res))
+        };
+        match (read(0x606), read(0x610)) {
+            (Some(units), Some(limit)) => {
+                (limit & 0x7fff) as f64 * 0.5f64.powi((units & 0xf) as i32)
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn get_boost_state() -> Option<bool> {
+        if let Ok(boost) = fs::read_to_string("/sys/devices/system/cpu/cpufreq/boost") {
+            return Some(boost.trim() == "1");
+        }
+        fs::read_to_string("/sys/devices/system/cpu/intel_pstate/no_turbo")
+            .ok()
+            .map(|no_turbo| no_turbo.trim() == "0")
+    }
+
+    /// Every AMD GPU's temperature in °C, unset for a card that can't be
+    /// read.
+    fn get_gpu_temperatures(&self) -> Vec<Option<f64>> {
+        self.amd_gpus
+            .iter()
+            .map(DrmAmdGpuReader::temperature)
+            .collect()
+    }
+
+    fn get_hwmon_chips() -> Vec<String> {
+        let mut chips: Vec<String> = fs::read_dir("/sys/class/hwmon")
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| fs::read_to_string(entry.path().join("name")).ok())
+            .map(|name| name.trim().to_string())
+            .collect();
+        chips.sort();
+        chips
+    }
+
+    /// Every DRM card bound to a driver, like `card0 (amdgpu)`; not the
+    /// `card0-DP-1` connectors.
+    fn get_gpus() -> Vec<String> {
+        let mut gpus: Vec<String> = fs::read_dir("/sys/class/drm")
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let index = name.strip_prefix("card")?;
+                index.bytes().all(|b| b.is_ascii_digit()).then_some(())?;
+                let driver = fs::read_link(entry.path().join("device/driver")).ok()?;
+                Some(format!(
+                    "{} ({})",
+                    name,
+                    driver.file_name()?.to_string_lossy()
+                ))
+            })
+            .collect();
+        gpus.sort();
+        gpus
+    }
+}
+
+/// Samples of one source, in the unit of its metric.
+#[derive(Default)]
+struct Reading {
+    samples: Vec<(Time, f64)>,
+    /// Top of the range where the source knows it, e.g. the GPU's memory.
+    max: Option<f64>,
+}
+
+struct SimpleChart {
+    cache: Cache,
+    data_points: VecDeque<(Time, i32)>,
+    limit: Duration,
+    unit: String,
+    max_value: i32,
+    /// Bottom of the y-axis; below zero for metrics that can go negative.
+    min_value: i32,
+    /// Fit the top of the y-axis to the data instead of `max_value`.
+    auto_scale: bool,
+    /// Push negative samples as 0.
+    floor_at_zero: bool,
+    /// What `SystemChart::update` samples into it; `None` for derived
+    /// charts and plugins, which are filled otherwise.
+    source: Option<SampleSource>,
+    color: RGBColor,
+    /// Color the trace switches to while the newest value is above the
+    /// limit, e.g. a fast thermal ramp.
+    warn_above: Option<(i32, RGBColor)>,
+    gradient: Option<Gradient>,
+    /// Tracks the configured threshold; see `ThresholdState`.
+    excursions: ExcursionTracker,
+    /// Excursions that ended since the alerts were last collected.
+    finished_excursions: Vec<Excursion>,
+    ewma_alpha: Option<f32>,
+    log_scale: bool,
+    /// Show the current value on a gauge instead of the history.
+    gauge: bool,
+    /// Decimals shown in the title and axis labels.
+    precision: usize,
+    /// Title template of a registry chart; see `metrics::format_title`.
+    title: Option<String>,
+    /// A line under the title, e.g. the ping's packet loss.
+    caption: Option<String>,
+    /// Samples are the reading times this; see `MetricDescriptor::scale`.
+    scale: i32,
+    /// Samples are KiB, shown in these units instead of `unit`.
+    byte_units: Option<ByteUnits>,
+    /// Shows the samples in another unit; `unit` is then the shown one.
+    converter: Option<UnitConverter>,
+    smooth: bool,
+    style: TraceStyle,
+    /// Fewer gridlines, no fill and at most `LOW_QUALITY_POINTS` points.
+    low_quality: bool,
+    /// Draw a dot on every sample, so a lone one still shows.
+    mark_samples: bool,
+    palette: ChartPalette,
+    interval: Duration,
+    /// Mark the chart stale when samples stop arriving at `interval`.
+    track_staleness: bool,
+    last_sample: Option<Instant>,
+    /// Right edge of the x-axis when it isn't simply the newest point.
+    anchor: Option<Time>,
+    markers: Vec<(Time, String)>,
+    /// Where the trace is interrupted rather than drawn across, e.g. where
+    /// a derived chart's value wasn't a number.
+    breaks: Vec<Time>,
+    /// Report the time under the cursor, and draw `cursor_time`.
+    crosshair: bool,
+    cursor_time: Option<Time>,
+    /// Another series traced over this one, e.g. a compared session.
+    overlay: Option<(history::Series, RGBColor)>,
+    /// Labeled horizontal lines, e.g. power limits.
+    limits: Vec<(f64, String)>,
+    /// Boundaries of the green, yellow and red background bands.
+    bands: Option<[i32; 2]>,
+    /// Range shaded in the trace's color, e.g. the governor's frequency
+    /// limits.
+    range: Option<[f64; 2]>,
+    /// Highest sample since launch or the last clear, even if it scrolled
+    /// out of the window.
+    peak: Option<i32>,
+    /// Samples were pushed since the cache was last cleared.
+    changed: bool,
+    /// Whole seconds the stale watermark was last drawn with, if any.
+    stale_shown: Option<i64>,
+}
+
+impl SimpleChart {
+    fn new(data: impl Iterator<Item = (Time, i32)>, unit: String, max_value: i32) -> Self {
+        let data_points: VecDeque<_> = data.collect();
+        Self {
+            cache: Cache::new(),
+            data_points,
+            limit: Duration::from_secs(60),
+            unit,
+            max_value,
+            min_value: 0,
+            auto_scale: false,
+            floor_at_zero: false,
+            source: None,
+            color: colors::rgb(ChartPalette::default().primary),
+            warn_above: None,
+            gradient: None,
+            excursions: ExcursionTracker::default(),
+            finished_excursions: Vec::new(),
+            ewma_alpha: None,
+            log_scale: false,
+            gauge: false,
+            precision: 0,
+            title: None,
+            caption: None,
+            scale: 1,
+            byte_units: None,
+            converter: None,
+            smooth: false,
+            style: TraceStyle::Area,
+            low_quality: false,
+            mark_samples: false,
+            palette: ChartPalette::default(),
+            interval: Duration::from_millis(500),
+            track_staleness: false,
+            last_sample: None,
+            anchor: None,
+            markers: Vec::new(),
+            breaks: Vec::new(),
+            crosshair: false,
+            cursor_time: None,
+            overlay: None,
+            limits: Vec::new(),
+            bands: None,
+            range: None,
+            peak: None,
+            changed: false,
+            stale_shown: None,
+        }
+    }
+
+    fn push_data(&mut self, time: Time, value: i32) {
+        let value = if self.floor_at_zero {
+            value.max(0)
+        } else {
+            value
+        };
+        let value = match (self.ewma_alpha, self.latest()) {
+            (Some(alpha), Some(prev)) => {
+                (alpha * value as f32 + (1.0 - alpha) * prev as f32).round() as i32
+            }
+            _ => value,
+        };
+        self.data_points.push_front((time, value));
+        self.peak = Some(self.peak.map_or(value, |peak| peak.max(value)));
+        self.finished_excursions
+            .extend(self.excursions.observe(time, value));
+        self.prune();
+        self.changed = true;
+    }
+
+    /// Interrupts the trace at `time`, between the sample before and the
+    /// next one.
+    fn push_break(&mut self, time: Time) {
+        self.breaks.push(time);
+        self.changed = true;
+    }
+
+    /// Clears the cache if samples were pushed since the last redraw, so a
+    /// batch of them is drawn in one frame rather than one per push.
+    fn redraw_changed(&mut self) {
+        if std::mem::take(&mut self.changed) {
+            self.cache.clear();
+        }
+    }
+
+    /// Ends an update at `now`, the newest sample of any chart. Only
+    /// charts with new samples are redrawn, and stale ones so their
+    /// watermark keeps counting, unless all are to scroll in step.
+    fn end_update(&mut self, now: Time, redraw_all: bool) {
+        self.anchor = Some(now);
+        if self.stale_age_changed(now) || redraw_all {
+            self.changed = true;
+        }
+        self.redraw_changed();
+    }
+
+    /// Whether the stale watermark would read differently at `now` than
+    /// when it was last drawn, noting the new reading. It counts whole
+    /// seconds, so a stale chart is redrawn once a second at most.
+    fn stale_age_changed(&mut self, now: Time) -> bool {
+        let shown = self.stale_for(now).map(|age| age.num_seconds());
+        std::mem::replace(&mut self.stale_shown, shown) != shown
+    }
+
+    pub fn raw_data(&self) -> &VecDeque<(Time, i32)> {
+        &self.data_points
+    }
+
+    /// The newest sample minus the one before, unscaled.
+    fn last_delta(&self) -> Option<i32> {
+        match (self.data_points.front(), self.data_points.get(1)) {
+            (Some((_, newest)), Some((_, previous))) => Some(newest - previous),
+            _ => None,
+        }
+    }
+
+    /// Test-only escape hatch for setting up edge cases directly.
+    #[cfg(test)]
+    pub fn raw_data_mut(&mut self) -> &mut VecDeque<(Time, i32)> {
+        &mut self.data_points
+    }
+
+    /// Draws with `palette`, tracing in its primary color.
+    fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        self.color = colors::rgb(palette.primary);
+        self.cache.clear();
+    }
+
+    /// A sample as the reading it stands for, in the shown unit.
+    fn reading(&self, value: f64) -> f64 {
+        let value = value / self.scale as f64;
+        self.converter
+            .map_or(value, |converter| converter.convert(value))
+    }
+
+    /// A sample as a reading, with `decimals` decimals.
+    fn display(&self, value: f64) -> String {
+        if let Some(units) = self.byte_units {
+            return units.format(value * 1024.0);
+        }
+        format!("{:.*}", self.decimals(), self.reading(value))
+    }
+
+    /// A sample and the unit to show it in, with the prefix that suits
+    /// `magnitude`, another sample: `5.0` and ` GHz` for 5000 MHz. Charts
+    /// with a configured unit keep it.
+    fn display_scaled(&self, value: f64, magnitude: f64) -> (String, String) {
+        let prefix = Some(self.unit.trim())
+            .filter(|_| self.byte_units.is_none() && self.converter.is_none())
+            .and_then(|unit| Prefix::pick(unit, self.reading(magnitude)));
+        match prefix {
+            Some(prefix) => (prefix.format(self.reading(value)), prefix.suffix()),
+            None => (self.display(value), self.unit.clone()),
+        }
+    }
+
+    /// A label of the log axis, for sample `y`. The decades span too much
+    /// for one prefix, so each label picks its own, and units without
+    /// prefixes are shortened to k, M and G.
+    fn log_label(&self, y: f64) -> String {
+        let (value, unit) = self.display_scaled(y, y);
+        if unit != self.unit || self.byte_units.is_some() {
+            return format!("{}{}", value, unit);
+        }
+        format!("{}{}", compact(&self.reading(y)), self.unit)
+    }
+
+    /// The configured number of decimals, or enough to show what the scale
+    /// and unit keep.
+    fn decimals(&self) -> usize {
+        self.precision.max(self.scale.ilog10() as usize).max(
+            self.converter
+                .map_or(0, |converter| converter.to.decimals()),
+        )
+    }
+
+    /// Percentiles and moments of the readings in the window, in the shown
+    /// unit.
+    fn statistics_report(&self) -> StatReport {
+        StatReport::new(
+            self.data_points
+                .iter()
+                .map(|(_, value)| self.reading(*value as f64)),
+        )
+    }
+
+    fn over_threshold(&self) -> bool {
+        self.excursions.active().is_some()
+    }
+
+    /// The alert logged for `excursion` over `threshold`, both shown like
+    /// the title's value.
+    fn alert_text(&self, label: &str, threshold: i32, excursion: &Excursion) -> String {
+        format!(
+            "{} exceeded {}{} for {}s (peak {}{})",
+            label,
+            self.display(threshold.into()),
+            self.unit,
+            excursion.duration().num_seconds(),
+            self.display(excursion.peak.into()),
+            self.unit
+        )
+    }
+
+    /// A reading in the metric's unit as a sample.
+    fn sample(&self, reading: f64) -> i32 {
+        (reading * self.scale as f64).round() as i32
+    }
+
+    /// Takes the threshold and bands from the chart's config, which has
+    /// them in the metric's unit.
+    fn set_thresholds(&mut self, chart_config: &ChartConfig) {
+        let rule = chart_config.threshold.map(|enter| ThresholdRule {
+            enter: self.sample(enter),
+            hysteresis: self.sample(chart_config.hysteresis),
+            sustain: chrono::Duration::milliseconds(chart_config.sustain_ms.into()),
+        });
+        self.excursions.set_rule(rule);
+        self.bands = chart_config
+            .bands
+            .map(|bands| bands.map(|value| self.sample(value)));
+    }
+
+    /// Width of the y-axis labels, in pixels: room for the number of the
+    /// widest label at either end of the axis, as the axis formats it.
+    fn y_label_width(&self) -> i32 {
+        let top = self.y_max() as f64;
+        let label = |y: f64| {
+            let label = if self.log_scale {
+                self.log_label(y.max(LOG_FLOOR))
+            } else {
+                let (value, unit) = self.display_scaled(y, top);
+                value + &unit
+            };
+            let number = label.trim_end_matches(|c: char| !c.is_ascii_digit());
+            number.chars().count() as i32
+        };
+        16 * label(top).max(label(self.min_value.into()))
+    }
+
+    /// The time at pixel `x` of the chart drawn `width` wide, if that is
+    /// over the plotting area.
+    fn time_at(&self, x: f32, width: f32) -> Option<Time> {
+        let left = (CHART_MARGIN + self.y_label_width()) as f32;
+        let plot_width = width - left - CHART_MARGIN as f32;
+        let fraction = (x - left) / plot_width;
+        if plot_width <= 0.0 || !(0.0..=1.0).contains(&fraction) {
+            return None;
+        }
+        let (oldest_time, newest_time) = self.time_range();
+        let span = (newest_time - oldest_time).num_milliseconds() as f64;
+        Some(oldest_time + chrono::Duration::milliseconds((span * fraction as f64).round() as i64))
+    }
+
+    /// The sample closest to `time`.
+    fn sample_near(&self, time: Time) -> Option<(Time, i32)> {
+        self.data_points
+            .iter()
+            .min_by_key(|(sample, _)| (*sample - time).num_milliseconds().abs())
+            .copied()
+    }
+
+    /// Top of the y-axis: `max_value`, or with auto-scale the peak in the
+    /// window rounded up on its leading digit so the labels stay readable.
+    fn y_max(&self) -> i32 {
+        if !self.auto_scale {
+            return self.max_value;
+        }
+        let peak = self
+            .data_points
+            .iter()
+            .map(|(_, value)| *value)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        let magnitude = 10i32.pow(peak.ilog10());
+        (peak / magnitude + 1) * magnitude
+    }
+
+    /// The buffer oldest first as `time,value,marker` lines, for
+    /// spreadsheets. Markers get lines of their own, without a value.
+    fn csv(&self) -> String {
+        let mut lines: Vec<_> = self
+            .data_points
+            .iter()
+            .map(|(time, value)| (*time, self.reading(*value as f64).to_string(), ""))
+            .chain(
+                self.markers
+                    .iter()
+                    .map(|(time, label)| (*time, String::new(), label.as_str())),
+            )
+            .collect();
+        lines.sort_by_key(|(time, _, _)| *time);
+
+        let mut csv = format!("time,value ({}),marker\n", self.unit.trim());
+        for (time, value, marker) in lines {
+            csv += &format!(
+                "{},{},{}\n",
+                clock::wall(time).to_rfc3339(),
+                value,
+                csv_field(marker)
+            );
+        }
+        csv
+    }
+
+    fn trace_color(&self) -> RGBColor {
+        match self.warn_above {
+            Some((limit, color)) if self.latest().is_some_and(|v| v > limit) => color,
+            _ => self.color,
+        }
+    }
+
+    /// Age of the newest sample once it is over three intervals old, i.e.
+    /// the sampler has stalled and the chart shows old data.
+    fn stale_for(&self, now: Time) -> Option<chrono::Duration> {
+        if !self.track_staleness {
+            return None;
+        }
+        let age = now - self.data_points.front()?.0;
+        (age.to_std().ok()? > 3 * self.interval).then_some(age)
+    }
+
+    /// Dimmed while the data is stale, red while the chart is over its
+    /// threshold (or the caller reports an alert of its own), otherwise
+    /// the theme's text color.
+    fn title_color(&self, alert: bool) -> Option<Color> {
+        if self.stale_for(clock::now()).is_some() {
+            return Some(Color {
+                a: 0.5,
+                ..colors::color(self.palette.text)
+            });
+        }
+        (alert || self.over_threshold()).then(|| colors::color(self.palette.danger))
+    }
+
+    /// The x-axis range. It spans exactly `limit`, the same window `prune`
+    /// keeps, so the axis never clips data or shows empty space.
+    fn time_range(&self) -> (Time, Time) {
+        // In smooth mode the right edge is the current time, so the newest
+        // sample drifts in from the edge until the next.
+        let newest_time = if self.smooth {
+            clock::now()
+        } else {
+            self.anchor
+                .unwrap_or(self.data_points.front().unwrap_or(&(Time::default(), 0)).0)
+        };
+        let limit = chrono::Duration::from_std(self.limit).unwrap_or(chrono::Duration::zero());
+        (newest_time - limit, newest_time)
+    }
+
+    fn due(&self) -> bool {
+        match self.last_sample {
+            Some(last) => last.elapsed() >= self.interval,
+            None => true,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.data_points.clear();
+        self.peak = None;
+        self.markers.clear();
+        self.breaks.clear();
+        self.cache.clear();
+    }
+
+    fn latest(&self) -> Option<i32> {
+        self.data_points.front().map(|(_, value)| *value)
+    }
+
+    /// Appends points from an earlier session behind the live data. Restored
+    /// points only ever extend the buffer into the past; anything at or after
+    /// the oldest live sample (e.g. because the clock moved backwards) is
+    /// dropped so the buffer stays ordered. A gap to the live data longer
+    /// than the window, like the night since yesterday's session, is
+    /// closed up to one sample interval and the trace broken there, so the
+    /// window needn't span it. The window widens to what is left, which
+    /// would otherwise be pruned right away, until the config's window is
+    /// applied again.
+    fn merge_history(&mut self, points: Vec<(Time, i32)>) {
+        let cutoff = self.data_points.back().map(|(time, _)| *time);
+        let mut older: Vec<_> = points
+            .into_iter()
+            .filter(|(time, _)| match cutoff {
+                Some(cutoff) => *time < cutoff,
+                None => true,
+            })
+            .collect();
+        older.sort_by_key(|(time, _)| Reverse(*time));
+
+        if let (Some(cutoff), Some(&(newest, _))) = (cutoff, older.first()) {
+            let gap = (cutoff - newest).to_std().unwrap_or_default();
+            if gap > self.limit {
+                let interval = chrono::Duration::from_std(self.interval).unwrap_or_default();
+                let shift = cutoff - interval - newest;
+                for (time, _) in &mut older {
+                    *time = *time + shift;
+                }
+                self.breaks.push(cutoff - interval);
+            }
+        }
+        self.data_points.extend(older);
+        if let (Some((newest, _)), Some((oldest, _))) =
+            (self.data_points.front(), self.data_points.back())
+        {
+            let span = (*newest - *oldest).to_std().unwrap_or_default();
+            self.limit = self.limit.max(span);
+        }
+        self.cache.clear();
+    }
+
+    fn prune(&mut self) {
+        let Some(newest) = self.data_points.front().map(|(time, _)| *time) else {
+            return;
+        };
+        let age = |time: Time| (newest - time).to_std().unwrap_or_default();
+        while self
+            .data_points
+            .back()
+            .is_some_and(|(time, _)| age(*time) > self.limit)
+        {
+            self.data_points.pop_back();
+        }
+        // Markers scroll out with the data.
+        self.markers.retain(|(time, _)| age(*time) <= self.limit);
+        self.breaks.retain(|time| age(*time) <= self.limit);
+    }
+
+    fn view(
+        &self,
+        id: ChartId,
+        title: String,
+        chart_height: f32,
+        color: Option<Color>,
+    ) -> Element<'_, Message> {
+        let mut title = Text::new(title);
+        if let Some(color) = color {
+            title = title.style(color);
+        }
+
+        let title = Row::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(
+                mouse_area(title)
+                    .on_press(Message::DragStart(id))
+                    .interaction(mouse::Interaction::Grab),
+            )
+            .push(
+                Button::new(Text::new(if self.gauge { "Chart" } else { "Gauge" }).size(12))
+                    .padding([2, 6])
+                    .on_press(Message::ToggleGauge(id)),
+            );
+        match &self.caption {
+            Some(caption) => self.titled(
+                Column::new()
+                    .spacing(2)
+                    .align_items(Alignment::Center)
+                    .push(title)
+                    .push(Text::new(caption).size(13)),
+                chart_height,
+            ),
+            None => self.titled(title, chart_height),
+        }
+    }
+
+    fn view_gauge(&self, size: f32) -> Element<'_, Message> {
+        let value = self.latest().unwrap_or_default();
+        GaugeWidget::new(
+            value,
+            self.max_value,
+            format!("{}{}", self.display(value.into()), self.unit),
+        )
+        .view(size)
+    }
+
+    /// The chart under an arbitrary title widget.
+    fn titled<'a>(
+        &'a self,
+        title: impl Into<Element<'a, Message>>,
+        chart_height: f32,
+    ) -> Element<'a, Message> {
+        Column::new()
+            .width(Length::Fill)
+            .height(Length::Shrink)
+            .spacing(5)
+            .align_items(Alignment::Center)
+            .push(title)
+            .push(if self.gauge {
+                self.view_gauge(chart_height)
+            } else {
+                Layers::new(
+                    ChartWidget::new(self)
+                        .height(Length::Fixed(chart_height))
+                        .into(),
+                    ChartWidget::new(Crosshair(self))
+                        .height(Length::Fixed(chart_height))
+                        .into(),
+                )
+                .into()
+            })
+            .into()
+    }
+}
+
+/// Space around the plotting area of a `SimpleChart`, in pixels.
+const CHART_MARGIN: i32 = 20;
+
+/// What a pass over a `SimpleChart`'s axes draws.
+#[derive(Clone, Copy)]
+enum Layer {
+    /// Everything but the crosshair; cached.
+    Data,
+    /// Only the crosshair, redrawn whenever the cursor moves. Whether the
+    /// cursor is over this very chart.
+    Crosshair(bool),
+}
+
+impl Chart<Message> for SimpleChart {
+    type State = ();
+
+    #[inline]
+    fn draw<R: Renderer, F: Fn(&mut Frame)>(
+        &self,
+        renderer: &R,
+        bounds: Size,
+        draw_fn: F,
+    ) -> Geometry {
+        renderer.draw_cache(&self.cache, bounds, draw_fn)
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &(), chart: ChartBuilder<DB>) {
+        self.build_layer(chart, Layer::Data);
+    }
+}
+
+/// The crosshair of a `SimpleChart`, drawn uncached over it so moving the
+/// mouse doesn't redraw the charts.
+struct Crosshair<'a>(&'a SimpleChart);
+
+impl Chart<Message> for Crosshair<'_> {
+    /// Whether the cursor is over the chart, which then also gets the
+    /// horizontal crosshair and the tooltip.
+    type State = bool;
+
+    fn build_chart<DB: DrawingBackend>(&self, hovered: &bool, chart: ChartBuilder<DB>) {
+        if self.0.cursor_time.is_some() {
+            self.0.build_layer(chart, Layer::Crosshair(*hovered));
+        }
+    }
+
+    fn update(
+        &self,
+        hovered: &mut bool,
+        event: canvas::Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        if !self.0.crosshair {
+            return (event::Status::Ignored, None);
+        }
+        let canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) = event else {
+            return (event::Status::Ignored, None);
+        };
+        // Leaving the chart is left to its tile, as the next chart may
+        // already have reported the cursor.
+        let Some(position) = cursor.position_in(bounds) else {
+            *hovered = false;
+            return (event::Status::Ignored, None);
+        };
+        let time = self.0.time_at(position.x, bounds.width);
+        *hovered = time.is_some();
+        (event::Status::Ignored, Some(Message::CursorAt(time)))
+    }
+}
+
+impl SimpleChart {
+    /// Builds the axes, the same for every layer so they line up, and
+    /// draws `layer` into them.
+    fn build_layer<DB: DrawingBackend>(&self, mut chart: ChartBuilder<DB>, layer: Layer) {
+        use plotters::prelude::*;
+
+        let (oldest_time, newest_time) = self.time_range();
+        let chart = chart
+            .x_label_area_size(0)
+            .y_label_area_size(self.y_label_width())
+            .margin(CHART_MARGIN);
+
+        if self.log_scale {
+            let top = (self.y_max() as f64).max(LOG_FLOOR * 10.0);
+            let chart = chart
+                .build_cartesian_2d(
+                    TimeAxis::from(oldest_time..newest_time),
+                    (LOG_FLOOR..top).log_scale(),
+                )
+                .expect("failed to build chart");
+            match layer {
+                Layer::Data => self.draw_data(chart, LOG_FLOOR, &|y| self.log_label(*y)),
+                Layer::Crosshair(hovered) => self.draw_crosshair(&chart, LOG_FLOOR, hovered),
+            }
+        } else {
+            let chart = chart
+                .build_cartesian_2d(
+                    TimeAxis::from(oldest_time..newest_time),
+                    self.min_value as f64..self.y_max() as f64,
+                )
+                .expect("failed to build chart");
+            let floor = self.min_value as f64;
+            match layer {
+                Layer::Data => {
+                    // One prefix for the whole axis, so the labels compare.
+                    let top = self.y_max().into();
+                    self.draw_data(chart, floor, &|y| {
+                        let (value, unit) = self.display_scaled(*y, top);
+                        format!("{}{}", value, unit)
+                    })
+                }
+                Layer::Crosshair(hovered) => self.draw_crosshair(&chart, floor, hovered),
+            }
+        }
+    }
+}
+
+impl SimpleChart {
+    /// Draws the mesh and trace into a chart with either y-axis scale.
+    /// Values below `floor` are drawn at `floor`, which is also the baseline
+    /// of the fill.
+    fn draw_data<DB, Y>(
+        &self,
+        mut chart: ChartContext<'_, DB, Cartesian2d<TimeAxis, Y>>,
+        floor: f64,
+        y_label_formatter: &dyn Fn(&f64) -> String,
+    ) where
+        DB: DrawingBackend,
+        Y: Ranged<ValueType = f64> + ValueFormatter<f64>,
+    {
+        use plotters::prelude::*;
+
+        let y = |value: i32| (value as f64).max(floor);
+
+        chart
+            .configure_mesh()
+            .bold_line_style(self.palette.grid())
+            .light_line_style(if self.low_quality {
+                TRANSPARENT
+            } else {
+                self.palette.fine_grid()
+            })
+            .axis_style(ShapeStyle::from(self.palette.axis()).stroke_width(1))
+            .y_labels(if self.low_quality { 4 } else { 10 })
+            .y_label_style(
+                ("sans-serif", 15)
+                    .into_font()
+                    .color(&self.palette.label())
+                    .transform(FontTransform::Rotate90),
+            )
+            .y_label_formatter(y_label_formatter)
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        self.draw_bands(&mut chart, floor);
+        self.draw_range(&mut chart, floor);
+
+        if let Some(gradient) = &self.gradient {
+            // Heat coloring: every segment between two samples gets its own
+            // fill and stroke, colored by the segment's mean value.
+            let segments = || {
+                self.plotted()
+                    .zip(self.plotted().skip(1))
+                    .filter(|(a, b)| !self.broken(b.0, a.0))
+                    .map(|(a, b)| {
+                        let mean = (a.1 + b.1) as f32 / 2.0;
+                        (*a, *b, gradient.color_at(mean / self.max_value as f32))
+                    })
+            };
+
+            if self.filled() {
+                chart
+                    .draw_series(segments().map(|(a, b, color)| {
+                        Polygon::new(
+                            vec![(a.0, y(a.1)), (b.0, y(b.1)), (b.0, floor), (a.0, floor)],
+                            color.mix(0.175).filled(),
+                        )
+                    }))
+                    .expect("failed to draw chart data");
+            }
+            chart
+                .draw_series(segments().map(|(a, b, color)| {
+                    PathElement::new(
+                        vec![(a.0, y(a.1)), (b.0, y(b.1))],
+                        ShapeStyle::from(color).stroke_width(2),
+                    )
+                }))
+                .expect("failed to draw chart data");
+        } else if !self.filled() {
+            for run in self.runs(self.plotted()) {
+                chart
+                    .draw_series(LineSeries::new(
+                        run.into_iter().map(|x| (x.0, y(x.1))),
+                        ShapeStyle::from(self.trace_color()).stroke_width(2),
+                    ))
+                    .expect("failed to draw chart data");
+            }
+        } else {
+            for run in self.runs(self.data_points.iter()) {
+                chart
+                    .draw_series(
+                        AreaSeries::new(
+                            run.into_iter().map(|x| (x.0, y(x.1))),
+                            floor,
+                            self.trace_color().mix(0.175),
+                        )
+                        .border_style(ShapeStyle::from(self.trace_color()).stroke_width(2)),
+                    )
+                    .expect("failed to draw chart data");
+            }
+        }
+
+        if self.mark_samples {
+            chart
+                .draw_series(
+                    self.plotted()
+                        .map(|x| Circle::new((x.0, y(x.1)), 3, self.trace_color().filled())),
+                )
+                .expect("failed to draw samples");
+        }
+
+        if let Some((points, color)) = &self.overlay {
+            chart
+                .draw_series(LineSeries::new(
+                    points.iter().map(|x| (x.0, y(x.1))),
+                    ShapeStyle::from(color.mix(0.8)).stroke_width(2),
+                ))
+                .expect("failed to draw overlay");
+        }
+
+        self.draw_limits(&chart);
+        self.draw_peak(&chart);
+        self.draw_markers(&chart);
+
+        if let Some(age) = self.stale_for(clock::now()) {
+            use plotters::style::text_anchor::{HPos, Pos, VPos};
+
+            let area = chart.plotting_area().strip_coord_spec();
+            let (width, height) = area.dim_in_pixel();
+            let style = ("sans-serif", 28)
+                .into_font()
+                .color(&self.palette.watermark())
+                .pos(Pos::new(HPos::Center, VPos::Center));
+            area.draw(&Text::new(
+                format!("stale ({}s ago)", age.num_seconds()),
+                (width as i32 / 2, height as i32 / 2),
+                style,
+            ))
+            .expect("failed to draw stale watermark");
+        }
+    }
+
+    /// Whether the area under the trace is shaded.
+    fn filled(&self) -> bool {
+        self.style == TraceStyle::Area && !self.low_quality
+    }
+
+    /// Whether the trace is interrupted between samples at `older` and
+    /// `newer`.
+    fn broken(&self, older: Time, newer: Time) -> bool {
+        self.breaks.iter().any(|time| (older..newer).contains(time))
+    }
+
+    /// `points`, newest first, split into the stretches drawn as one
+    /// trace.
+    fn runs<'a>(&self, points: impl Iterator<Item = &'a (Time, i32)>) -> Vec<Vec<(Time, i32)>> {
+        let mut runs: Vec<Vec<(Time, i32)>> = Vec::new();
+        for &point in points {
+            match runs.last_mut() {
+                Some(run) if !self.broken(point.0, run[run.len() - 1].0) => run.push(point),
+                _ => runs.push(vec![point]),
+            }
+        }
+        runs
+    }
+
+    /// The samples to draw, newest first: all of them, or in low quality
+    /// mode every n-th so that at most `LOW_QUALITY_POINTS` remain.
+    fn plotted(&self) -> impl Iterator<Item = &(Time, i32)> {
+        let step = if self.low_quality {
+            self.data_points.len().div_ceil(LOW_QUALITY_POINTS).max(1)
+        } else {
+            1
+        };
+        self.data_points.iter().step_by(step)
+    }
+
+    /// Severity bands behind the trace, clipped to the y-axis range.
+    fn draw_bands<DB, Y>(
+        &self,
+        chart: &mut ChartContext<'_, DB, Cartesian2d<TimeAxis, Y>>,
+        floor: f64,
+    ) where
+        DB: DrawingBackend,
+        Y: Ranged<ValueType = f64> + ValueFormatter<f64>,
+    {
+        use plotters::prelude::*;
+
+        let Some([low, high]) = self.bands else {
+            return;
+        };
+        let (oldest_time, newest_time) = self.time_range();
+        let top = self.y_max() as f64;
+        let bands = [
+            (floor, low as f64, colors::rgb(self.palette.success)),
+            (low as f64, high as f64, colors::rgb(self.palette.warning)),
+            (high as f64, top, colors::rgb(self.palette.danger)),
+        ];
+        chart
+            .draw_series(
+                bands
+                    .into_iter()
+                    .map(|(bottom, top_of_band, color)| {
+                        (bottom.max(floor), top_of_band.min(top), color)
+                    })
+                    .filter(|(bottom, top_of_band, _)| bottom < top_of_band)
+                    .map(|(bottom, top_of_band, color)| {
+                        Rectangle::new(
+                            [(oldest_time, bottom), (newest_time, top_of_band)],
+                            color.mix(0.08).filled(),
+                        )
+                    }),
+            )
+            .expect("failed to draw bands");
+    }
+
+    /// `range` behind the trace, clipped to the y-axis range.
+    fn draw_range<DB, Y>(
+        &self,
+        chart: &mut ChartContext<'_, DB, Cartesian2d<TimeAxis, Y>>,
+        floor: f64,
+    ) where
+        DB: DrawingBackend,
+        Y: Ranged<ValueType = f64> + ValueFormatter<f64>,
+    {
+        use plotters::prelude::*;
+
+        let Some([bottom, top]) = self.range else {
+            return;
+        };
+        let (bottom, top) = (bottom.max(floor), top.min(self.y_max() as f64));
+        if bottom >= top {
+            return;
+        }
+        let (oldest_time, newest_time) = self.time_range();
+        chart
+            .draw_series(std::iter::once(Rectangle::new(
+                [(oldest_time, bottom), (newest_time, top)],
+                self.trace_color().mix(0.1).filled(),
+            )))
+            .expect("failed to draw range");
+    }
+
+    /// Dashed vertical lines with a rotated label for every marker inside
+    /// the visible window. Drawn in pixels so the dashes stay even on a
+    /// log axis.
+    fn draw_markers<DB, Y>(&self, chart: &ChartContext<'_, DB, Cartesian2d<TimeAxis, Y>>)
+    where
+        DB: DrawingBackend,
+        Y: Ranged<ValueType = f64>,
+    {
+        use plotters::prelude::*;
+
+        let area = chart.plotting_area();
+        let (left, _) = area.get_base_pixel();
+        let (_, height) = area.dim_in_pixel();
+        let pixels = area.strip_coord_spec();
+        let x_range = chart.x_range();
+        let style = ShapeStyle::from(self.palette.annotation()).stroke_width(1);
+        let font = ("sans-serif", 13)
+            .into_font()
+            .color(&self.palette.annotation_text())
+            .transform(FontTransform::Rotate90);
+
+        for (time, label) in self.markers.iter().filter(|(t, _)| x_range.contains(t)) {
+            let (x, _) = area.map_coordinate(&(*time, chart.y_range().start));
+            let x = x - left;
+            for y in (0..height as i32).step_by(10) {
+                pixels
+                    .draw(&PathElement::new(vec![(x, y), (x, y + 5)], style))
+                    .expect("failed to draw marker");
+            }
+            pixels
+                .draw(&Text::new(label.clone(), (x + 16, 4), font.clone()))
+                .expect("failed to draw marker label");
+        }
+    }
+
+    /// A vertical line at `cursor_time`, and on the hovered chart a
+    /// horizontal one through the closest sample with its time and value.
+    fn draw_crosshair<DB, Y>(
+        &self,
+        chart: &ChartContext<'_, DB, Cartesian2d<TimeAxis, Y>>,
+        floor: f64,
+        hovered: bool,
+    ) where
+        DB: DrawingBackend,
+        Y: Ranged<ValueType = f64>,
+    {
+        use plotters::prelude::*;
+
+        let Some(time) = self.cursor_time.filter(|t| chart.x_range().contains(t)) else {
+            return;
+        };
+        let area = chart.plotting_area();
+        let (left, top) = area.get_base_pixel();
+        let (width, height) = area.dim_in_pixel();
+        let pixels = area.strip_coord_spec();
+        let style = ShapeStyle::from(self.palette.axis()).stroke_width(1);
+
+        let (x, _) = area.map_coordinate(&(time, chart.y_range().start));
+        let x = x - left;
+        pixels
+            .draw(&PathElement::new(vec![(x, 0), (x, height as i32)], style))
+            .expect("failed to draw crosshair");
+
+        let Some((sample_time, value)) = self.sample_near(time).filter(|_| hovered) else {
+            return;
+        };
+        let (_, y) = area.map_coordinate(&(time, (value as f64).max(floor)));
+        let y = (y - top).clamp(0, height as i32);
+        pixels
+            .draw(&PathElement::new(vec![(0, y), (width as i32, y)], style))
+            .expect("failed to draw crosshair");
+
+        let (value, unit) = self.display_scaled(value.into(), value.into());
+        let text = format!(
+            "time: {}, value: {}{}",
+            clock::wall(sample_time)
+                .with_timezone(&Local)
+                .format("%H:%M:%S%.3f"),
+            value,
+            unit
+        );
+        let font = ("sans-serif", 13).into_font().color(&self.palette.label());
+        let (text_width, text_height) = pixels.estimate_text_size(&text, &font).unwrap_or_default();
+        let (text_width, text_height) = (text_width as i32 + 8, text_height as i32 + 6);
+        // Beside the cursor, on whichever side has room.
+        let x = if x + 8 + text_width > width as i32 {
+            x - 8 - text_width
+        } else {
+            x + 8
+        };
+        let y = (y - 8 - text_height).max(0);
+        pixels
+            .draw(&Rectangle::new(
+                [(x, y), (x + text_width, y + text_height)],
+                self.palette.legend_background().filled(),
+            ))
+            .expect("failed to draw tooltip");
+        pixels
+            .draw(&Text::new(text, (x + 4, y + 3), font))
+            .expect("failed to draw tooltip");
+    }
+}
+
+impl SimpleChart {
+    /// Dashed horizontal line per limit, labeled at the left.
+    fn draw_limits<DB, Y>(&self, chart: &ChartContext<'_, DB, Cartesian2d<TimeAxis, Y>>)
+    where
+        DB: DrawingBackend,
+        Y: Ranged<ValueType = f64>,
+    {
+        use plotters::prelude::*;
+
+        let area = chart.plotting_area();
+        let (_, top) = area.get_base_pixel();
+        let (width, _) = area.dim_in_pixel();
+        let pixels = area.strip_coord_spec();
+        let start = chart.x_range().start;
+        let style = ShapeStyle::from(self.palette.annotation()).stroke_width(1);
+        let font = ("sans-serif", 13)
+            .into_font()
+            .color(&self.palette.annotation_text());
+
+        for (value, label) in &self.limits {
+            let (_, y) = area.map_coordinate(&(start, *value));
+            let y = y - top;
+            for x in (0..width as i32).step_by(10) {
+                pixels
+                    .draw(&PathElement::new(vec![(x, y), (x + 5, y)], style))
+                    .expect("failed to draw limit");
+            }
+            pixels
+                .draw(&Text::new(label.clone(), (4, y - 16), font.clone()))
+                .expect("failed to draw limit label");
+        }
+    }
+
+    /// Faint line at the peak, labeled at the right. A peak above the
+    /// y-axis range sits on the top edge.
+    fn draw_peak<DB, Y>(&self, chart: &ChartContext<'_, DB, Cartesian2d<TimeAxis, Y>>)
+    where
+        DB: DrawingBackend,
+        Y: Ranged<ValueType = f64>,
+    {
+        use plotters::{
+            prelude::*,
+            style::text_anchor::{HPos, Pos, VPos},
+        };
+
+        let Some(peak) = self.peak else {
+            return;
+        };
+        let area = chart.plotting_area();
+        let (_, top) = area.get_base_pixel();
+        let (width, _) = area.dim_in_pixel();
+        let pixels = area.strip_coord_spec();
+        let (_, y) = area.map_coordinate(&(chart.x_range().start, peak.min(self.y_max()) as f64));
+        let y = (y - top).max(0);
+        let width = width as i32;
+
+        pixels
+            .draw(&PathElement::new(
+                vec![(0, y), (width, y)],
+                ShapeStyle::from(self.palette.faded(0.25)).stroke_width(1),
+            ))
+            .expect("failed to draw peak");
+        let (value, unit) = self.display_scaled(peak.into(), peak.into());
+        let font = ("sans-serif", 13)
+            .into_font()
+            .color(&self.palette.annotation())
+            .pos(Pos::new(HPos::Right, VPos::Top));
+        pixels
+            .draw(&Text::new(
+                format!("peak {}{}", value, unit),
+                (width - 4, y + 3),
+                font,
+            ))
+            .expect("failed to draw peak label");
+    }
+}
+
+/// Lowest value a logarithmic axis shows; smaller values sit on it.
+const LOG_FLOOR: f64 = 1.0;
+
+/// `1500` as `1.5k`, for log-axis labels spanning many magnitudes.
+fn compact(value: &f64) -> String {
+    let (value, suffix) = match value.abs() {
+        v if v >= 1e9 => (value / 1e9, "G"),
+        v if v >= 1e6 => (value / 1e6, "M"),
+        v if v >= 1e3 => (value / 1e3, "k"),
+        _ => (*value, ""),
+    };
+    format!("{}{}", (value * 10.0).round() / 10.0, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: i64) -> Time {
+        Time::default() + chrono::Duration::seconds(1_700_000_000 + secs)
+    }
+
+    fn chart(points: Vec<(Time, i32)>) -> SimpleChart {
+        SimpleChart::new(points.into_iter(), "%".into(), 100)
+    }
+
+    #[test]
+    fn parses_window_sizes() {
+        assert_eq!(parse_size("1600x900"), Ok([1600, 900]));
+        assert_eq!(parse_size("800X600"), Ok([800, 600]));
+        assert!(parse_size("1600").is_err());
+        assert!(parse_size("0x900").is_err());
+        assert!(parse_size("wide x 900").is_err());
+    }
+
+    #[test]
+    fn ticks_at_the_interval_are_due_despite_jitter() {
+        let interval = Duration::from_millis(500);
+        assert!(interval_elapsed(Duration::from_millis(500), interval));
+        assert!(interval_elapsed(Duration::from_millis(497), interval));
+        assert!(!interval_elapsed(Duration::from_millis(250), interval));
+    }
+
+    #[test]
+    fn low_quality_caps_plotted_points() {
+        let mut chart = chart((0..1000).rev().map(|secs| (at(secs), 1)).collect());
+        assert_eq!(chart.plotted().count(), 1000);
+        chart.low_quality = true;
+        assert!(chart.plotted().count() <= LOW_QUALITY_POINTS);
+        assert_eq!(chart.plotted().next(), Some(&(at(999), 1)));
+    }
+
+    #[test]
+    fn pushes_are_redrawn_together() {
+        let mut chart = chart(vec![]);
+        chart.push_data(at(0), 1);
+        chart.push_data(at(1), 2);
+        assert!(chart.changed);
+        chart.redraw_changed();
+        assert!(!chart.changed);
+    }
+
+    /// How often `chart`'s cache runs the drawing, drawn twice to see
+    /// that it keeps what it drew.
+    fn draws(chart: &SimpleChart) -> usize {
+        let renderer = iced::Renderer::TinySkia(iced_tiny_skia::Renderer::new(
+            iced_tiny_skia::Backend::new(),
+            Font::default(),
+            iced::Pixels(16.0),
+        ));
+        let draws = std::cell::Cell::new(0);
+        for _ in 0..2 {
+            chart.cache.draw(&renderer, Size::new(100.0, 100.0), |_| {
+                draws.set(draws.get() + 1)
+            });
+        }
+        draws.get()
+    }
+
+    #[test]
+    fn updates_redraw_charts_with_new_samples_once() {
+        let mut chart = chart(vec![]);
+        assert_eq!(draws(&chart), 1);
+
+        chart.push_data(at(0), 1);
+        chart.push_data(at(1), 2);
+        assert_eq!(draws(&chart), 0);
+        chart.end_update(at(1), false);
+        assert_eq!(draws(&chart), 1);
+
+        // Nothing new: the cached drawing stays, unless all charts are to
+        // be redrawn.
+        chart.end_update(at(2), false);
+        assert_eq!(draws(&chart), 0);
+        chart.end_update(at(2), true);
+        assert_eq!(draws(&chart), 1);
+    }
+
+    #[test]
+    fn stale_charts_are_redrawn_as_their_age_ticks() {
+        let mut chart = chart(vec![(at(0), 1)]);
+        chart.track_staleness = true;
+        chart.interval = Duration::from_secs(1);
+        assert_eq!(draws(&chart), 1);
+
+        chart.end_update(at(2), false);
+        assert_eq!(draws(&chart), 0);
+        for secs in [10, 11] {
+            chart.end_update(at(secs), false);
+            assert_eq!(draws(&chart), 1);
+        }
+        // The watermark counts whole seconds; nothing to redraw within one.
+        chart.end_update(at(11) + chrono::Duration::milliseconds(500), false);
+        assert_eq!(draws(&chart), 0);
+    }
+
+    #[test]
+    fn breaks_split_the_trace() {
+        let mut chart = chart(vec![]);
+        chart.push_data(at(0), 1);
+        chart.push_data(at(1), 2);
+        chart.push_break(at(2));
+        chart.push_data(at(3), 3);
+        assert_eq!(
+            chart.runs(chart.raw_data().iter()),
+            [vec![(at(3), 3)], vec![(at(1), 2), (at(0), 1)]]
+        );
+
+        // The break scrolls out with the samples before it.
+        chart.limit = Duration::from_secs(60);
+        chart.push_data(at(100), 4);
+        assert!(chart.breaks.is_empty());
+        assert_eq!(chart.runs(chart.raw_data().iter()).len(), 1);
+    }
+
+    #[test]
+    fn thresholds_are_configured_in_the_metrics_unit() {
+        let mut latency = chart(vec![]);
+        latency.scale = metrics::descriptor(ChartId::Latency).scale;
+        latency.set_thresholds(&ChartConfig {
+            threshold: Some(100.0),
+            bands: Some([50.0, 80.0]),
+            ..ChartConfig::default()
+        });
+        assert_eq!(latency.bands, Some([5_000, 8_000]));
+
+        latency.push_data(at(0), latency.sample(1.0));
+        assert!(!latency.over_threshold());
+        latency.push_data(at(1), latency.sample(99.0));
+        assert!(!latency.over_threshold());
+        latency.push_data(at(2), latency.sample(100.0));
+        assert!(latency.over_threshold());
+    }
+
+    #[test]
+    fn alerts_show_readings_not_samples() {
+        let mut latency = chart(vec![]);
+        latency.unit = " ms".into();
+        latency.scale = 100;
+        let excursion = Excursion {
+            start: at(0),
+            end: at(5),
+            peak: 25_000,
+        };
+        assert_eq!(
+            latency.alert_text("Latency", 10_000, &excursion),
+            "Latency exceeded 100.00 ms for 5s (peak 250.00 ms)"
+        );
+    }
+
+    #[test]
+    fn log_axis_labels_show_readings() {
+        let mut latency = chart(vec![]);
+        latency.unit = " ms".into();
+        latency.scale = 100;
+        assert_eq!(latency.log_label(10_000.0), "100 ms");
+        assert_eq!(latency.log_label(1_000_000.0), "10k ms");
+
+        let mut freq = chart(vec![]);
+        freq.unit = " MHz".into();
+        assert_eq!(freq.log_label(3_400.0), "3.4 GHz");
+
+        let mut rate = chart(vec![]);
+        rate.unit = "/s".into();
+        assert_eq!(rate.log_label(20_000.0), "20k/s");
+    }
+
+    #[test]
+    fn y_labels_are_as_wide_as_their_numbers() {
+        let mut latency = chart(vec![]);
+        latency.unit = " ms".into();
+        latency.scale = 100;
+        latency.max_value = 10_000;
+        // "100.00 ms", not the sample's 10000.
+        assert_eq!(latency.y_label_width(), 16 * 6);
+
+        let mut memory = chart(vec![]);
+        memory.unit = String::new();
+        memory.byte_units = Some(ByteUnits::Binary);
+        memory.max_value = 8 * 1024 * 1024;
+        // "8.0 GiB", not the 7 digits of KiB.
+        assert_eq!(memory.y_label_width(), 16 * 3);
+    }
+
+    #[test]
+    fn time_range_spans_limit() {
+        let mut chart = chart(vec![]);
+        chart.limit = Duration::from_secs(900);
+        chart.push_data(at(0), 1);
+        chart.push_data(at(1000), 2);
+        assert_eq!(chart.time_range(), (at(100), at(1000)));
+    }
+
+    #[test]
+    fn oldest_kept_point_is_inside_time_range() {
+        let mut chart = chart(vec![]);
+        chart.limit = Duration::from_secs(120);
+        for secs in (0..=300).step_by(30) {
+            chart.push_data(at(secs), 1);
+        }
+        let (oldest_time, _) = chart.time_range();
+        assert_eq!(chart.raw_data().back().unwrap().0, oldest_time);
+    }
+
+    #[test]
+    fn cursor_maps_to_time_across_the_plotting_area() {
+        let mut chart = chart(vec![(at(0), 1)]);
+        chart.limit = Duration::from_secs(100);
+        chart.push_data(at(100), 2);
+        // 20 px margins and 48 px of labels for "100".
+        let left = (CHART_MARGIN + chart.y_label_width()) as f32;
+        let width = left + 200.0 + CHART_MARGIN as f32;
+        assert_eq!(chart.time_at(left, width), Some(at(0)));
+        assert_eq!(chart.time_at(left + 50.0, width), Some(at(25)));
+        assert_eq!(chart.time_at(left + 200.0, width), Some(at(100)));
+        assert_eq!(chart.time_at(left - 1.0, width), None);
+        assert_eq!(chart.time_at(width, width), None);
+
+        assert_eq!(chart.sample_near(at(40)), Some((at(0), 1)));
+        assert_eq!(chart.sample_near(at(60)), Some((at(100), 2)));
+    }
+
+    #[test]
+    fn empty_chart_has_no_data() {
+        let chart = chart(vec![]);
+        assert!(chart.raw_data().is_empty());
+        assert_eq!(chart.latest(), None);
+    }
+
+    #[test]
+    fn peak_outlives_the_window_until_cleared() {
+        let mut chart = chart(vec![]);
+        chart.limit = Duration::from_secs(60);
+        chart.push_data(at(0), 90);
+        for secs in 1..=120 {
+            chart.push_data(at(secs), 40);
+        }
+        assert!(chart.raw_data().iter().all(|&(_, v)| v == 40));
+        assert_eq!(chart.peak, Some(90));
+        chart.clear();
+        assert_eq!(chart.peak, None);
+    }
+
+    #[test]
+    fn push_into_empty_chart() {
+        let mut chart = chart(vec![]);
+        chart.push_data(at(0), 42);
+        assert_eq!(chart.raw_data(), &VecDeque::from([(at(0), 42)]));
+    }
+
+    #[test]
+    fn single_element_is_never_pruned() {
+        let mut chart = chart(vec![(at(0), 1)]);
+        chart.prune();
+        assert_eq!(chart.raw_data().len(), 1);
+    }
+
+    #[test]
+    fn newest_point_is_first() {
+        let mut chart = chart(vec![]);
+        chart.push_data(at(0), 1);
+        chart.push_data(at(1), 2);
+        assert_eq!(chart.raw_data().front(), Some(&(at(1), 2)));
+        assert_eq!(chart.latest(), Some(2));
+    }
+
+    #[test]
+    fn prunes_points_older_than_limit() {
+        let mut chart = chart(vec![(at(0), 1)]);
+        chart.push_data(at(30), 2);
+        chart.push_data(at(90), 3);
+        assert_eq!(
+            chart.raw_data(),
+            &VecDeque::from([(at(90), 3), (at(30), 2)])
+        );
+    }
+
+    #[test]
+    fn markers_are_pruned_with_the_data() {
+        let mut chart = chart(vec![(at(0), 1)]);
+        chart.markers = vec![(at(0), "build".into()), (at(50), "test".into())];
+        chart.push_data(at(30), 2);
+        assert_eq!(chart.markers.len(), 2);
+        chart.push_data(at(90), 3);
+        assert_eq!(chart.markers, [(at(50), "test".to_string())]);
+    }
+
+    #[test]
+    fn csv_has_markers_on_lines_of_their_own() {
+        let mut chart = chart(vec![(at(2), 40), (at(0), 35)]);
+        chart.markers = vec![(at(1), "build, then test".into())];
+        let csv = chart.csv();
+        let lines: Vec<_> = csv
+            .lines()
+            .map(|line| line.split_once(',').unwrap().1)
+            .collect();
+        assert_eq!(
+            lines,
+            ["value (%),marker", "35,", ",\"build, then test\"", "40,"]
+        );
+    }
+
+    #[test]
+    fn last_delta_is_between_the_two_newest_samples() {
+        let mut chart = chart(vec![(at(0), 40)]);
+        assert_eq!(chart.last_delta(), None);
+        chart.push_data(at(1), 35);
+        chart.push_data(at(2), 47);
+        assert_eq!(chart.last_delta(), Some(12));
+    }
+
+    #[test]
+    fn negative_samples_are_floored_unless_allowed() {
+        let mut chart = chart(vec![]);
+        chart.push_data(at(0), -3);
+        chart.floor_at_zero = true;
+        chart.push_data(at(1), -2);
+        chart.push_data(at(2), 5);
+        assert_eq!(
+            chart.raw_data(),
+            &VecDeque::from([(at(2), 5), (at(1), 0), (at(0), -3)])
+        );
+    }
+
+    #[test]
+    fn keeps_point_exactly_at_limit() {
+        let mut chart = chart(vec![(at(0), 1)]);
+        chart.push_data(at(60), 2);
+        assert_eq!(chart.raw_data().len(), 2);
+    }
+
+    #[test]
+    fn prunes_point_just_past_limit() {
+        let mut chart = chart(vec![(at(0), 1)]);
+        chart.push_data(at(60) + chrono::Duration::milliseconds(1), 2);
+        assert_eq!(chart.raw_data().len(), 1);
+    }
+
+    #[test]
+    fn equal_timestamps_are_all_kept() {
+        let mut chart = chart(vec![]);
+        for value in 0..5 {
+            chart.push_data(at(10), value);
+        }
+        assert_eq!(chart.raw_data().len(), 5);
+        assert_eq!(chart.latest(), Some(4));
+    }
+
+    #[test]
+    fn negative_values_are_preserved() {
+        let mut chart = chart(vec![]);
+        chart.push_data(at(0), -5);
+        chart.push_data(at(1), i32::MIN);
+        assert_eq!(
+            chart.raw_data(),
+            &VecDeque::from([(at(1), i32::MIN), (at(0), -5)])
+        );
+    }
+
+    #[test]
+    fn pruning_applies_to_mutated_data() {
+        let mut chart = chart(vec![]);
+        chart
+            .raw_data_mut()
+            .extend([(at(100), 3), (at(50), 2), (at(0), 1)]);
+        chart.prune();
+        assert_eq!(
+            chart.raw_data(),
+            &VecDeque::from([(at(100), 3), (at(50), 2)])
+        );
+    }
+
+    #[test]
+    fn merge_history_appends_older_points_only() {
+        let mut chart = chart(vec![(at(50), 5)]);
+        chart.merge_history(vec![(at(40), 4), (at(55), 9), (at(45), 4)]);
+        assert_eq!(
+            chart.raw_data(),
+            &VecDeque::from([(at(50), 5), (at(45), 4), (at(40), 4)])
+        );
+    }
+
+    #[test]
+    fn merge_history_widens_the_window_to_the_restored_span() {
+        let mut chart = chart(vec![]);
+        chart.merge_history(vec![(at(0), 1), (at(100), 2)]);
+        assert_eq!(
+            chart.raw_data(),
+            &VecDeque::from([(at(100), 2), (at(0), 1)])
+        );
+        assert_eq!(chart.limit, Duration::from_secs(100));
+    }
+
+    #[test]
+    fn restored_history_survives_a_gap_longer_than_the_window() {
+        let path = std::env::temp_dir().join(format!("monty-history-{}.bin", std::process::id()));
+        let saved = VecDeque::from([(at(200), 3), (at(100), 2), (at(0), 1)]);
+        history::save(&path, [("usage", &saved)].into_iter()).unwrap();
+        let snapshot = history::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        // A minute's window, and the live session started a day later.
+        let mut chart = chart(vec![(at(86_400), 4)]);
+        chart.interval = Duration::from_secs(1);
+        let (_, points) = snapshot.series.into_iter().next().unwrap();
+        chart.merge_history(points);
+
+        // The night is closed up to one interval and breaks the trace, so
+        // the window spans the samples, not the gap.
+        assert_eq!(
+            chart.raw_data(),
+            &VecDeque::from([
+                (at(86_400), 4),
+                (at(86_399), 3),
+                (at(86_299), 2),
+                (at(86_199), 1)
+            ])
+        );
+        assert_eq!(chart.limit, Duration::from_secs(201));
+        assert_eq!(chart.runs(chart.raw_data().iter()).len(), 2);
+        // It scrolls on from there.
+        chart.push_data(at(86_401), 5);
+        assert_eq!(chart.raw_data().len(), 4);
+    }
+
+    #[test]
+    fn chart_goes_stale_after_three_missed_intervals() {
+        let mut chart = chart(vec![(at(0), 1)]);
+        chart.interval = Duration::from_secs(1);
+        assert_eq!(chart.stale_for(at(10)), None);
+        chart.track_staleness = true;
+        assert_eq!(chart.stale_for(at(3)), None);
+        assert_eq!(chart.stale_for(at(4)), Some(chrono::Duration::seconds(4)));
+    }
+
+    #[test]
+    fn auto_scale_rounds_peak_up_on_leading_digit() {
+        let mut chart = chart(vec![(at(2), 1234), (at(1), 87)]);
+        assert_eq!(chart.y_max(), 100);
+        chart.auto_scale = true;
+        assert_eq!(chart.y_max(), 2000);
+        chart.data_points = VecDeque::from([(at(1), 0)]);
+        assert_eq!(chart.y_max(), 2);
+    }
+}
@@ -0,0 +1,184 @@
+use std::{collections::HashMap, fs, io, time::Duration};
+
+use iced::{
+    widget::{Column, Row, Scrollable, Text},
+    Alignment, Element, Length,
+};
+
+use crate::{clock, colors::ChartPalette, config::ByteUnits, Message, SimpleChart};
+
+/// How many of the largest mappings get a chart.
+const SHOWN_REGIONS: usize = 8;
+
+/// Resident set size of every mapping of another process over time, read
+/// from `/proc/<pid>/smaps`. Growing regions point at leaks without having
+/// to rebuild the target under a profiler.
+pub struct MemoryProfile {
+    pub pid: u32,
+    regions: HashMap<String, SimpleChart>,
+    window: Duration,
+    byte_units: ByteUnits,
+    palette: ChartPalette,
+}
+
+impl MemoryProfile {
+    pub fn new(pid: u32, window: Duration, byte_units: ByteUnits) -> Self {
+        Self {
+            pid,
+            regions: HashMap::new(),
+            window,
+            byte_units,
+            palette: ChartPalette::default(),
+        }
+    }
+
+    /// Takes one sample. Fails once the process is gone or unreadable.
+    pub fn update(&mut self) -> io::Result<()> {
+        let smaps = fs::read_to_string(format!("/proc/{}/smaps", self.pid))?;
+        let now = clock::now();
+        let sample = parse_smaps(&smaps);
+
+        self.regions.retain(|name, _| sample.contains_key(name));
+        for (name, rss) in sample {
+            let chart = self.regions.entry(name).or_insert_with(|| {
+                let mut chart = SimpleChart::new(std::iter::empty(), String::new(), 1);
+                chart.limit = self.window;
+                chart.byte_units = Some(self.byte_units);
+                chart.set_palette(self.palette);
+                chart
+            });
+            chart.max_value = chart.max_value.max(rss);
+            chart.push_data(now, rss);
+            chart.redraw_changed();
+        }
+        Ok(())
+    }
+
+    pub fn set_window(&mut self, window: Duration) {
+        self.window = window;
+        for chart in self.regions.values_mut() {
+            chart.limit = window;
+        }
+    }
+
+    pub fn set_byte_units(&mut self, byte_units: ByteUnits) {
+        self.byte_units = byte_units;
+        for chart in self.regions.values_mut() {
+            chart.byte_units = Some(byte_units);
+            chart.cache.clear();
+        }
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        for chart in self.regions.values_mut() {
+            chart.set_palette(palette);
+        }
+    }
+
+    pub fn view(&self, chart_height: f32) -> Element<'_, Message> {
+        let mut regions: Vec<_> = self.regions.iter().collect();
+        regions.sort_by_key(|(_, chart)| std::cmp::Reverse(chart.latest().unwrap_or_default()));
+        let total: i32 = regions.iter().filter_map(|(_, c)| c.latest()).sum();
+
+        let mut col = Column::new()
+            .width(Length::Fill)
+            .align_items(Alignment::Center)
+            .push(Text::new(format!(
+                "PID {}: {} mappings, {} resident",
+                self.pid,
+                regions.len(),
+                self.byte_units.format(total as f64 * 1024.0)
+            )));
+
+        let shown: Vec<_> = regions.into_iter().take(SHOWN_REGIONS).collect();
+        for pair in shown.chunks(2) {
+            let row = pair.iter().fold(
+                Row::new()
+                    .spacing(15)
+                    .padding(20)
+                    .width(Length::Fill)
+                    .align_items(Alignment::Center),
+                |row, (name, chart)| {
+                    row.push(chart.titled(
+                        Text::new(format!(
+                            "{}: {}",
+                            name,
+                            chart.display(chart.latest().unwrap_or_default().into())
+                        )),
+                        chart_height,
+                    ))
+                },
+            );
+            col = col.push(row);
+        }
+
+        Scrollable::new(col).height(Length::Shrink).into()
+    }
+}
+
+/// RSS in kB per mapping. Mappings are named by their path (or pseudo-path
+/// like `[heap]`) plus base address, since the same file is usually mapped
+/// several times and anonymous mappings have no name at all.
+fn parse_smaps(smaps: &str) -> HashMap<String, i32> {
+    let mut regions = HashMap::new();
+    let mut current = None;
+
+    for line in smaps.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(first) = fields.next() else {
+            continue;
+        };
+
+        if let Some((start, _)) = first.split_once('-') {
+            current = Some(format!("{} @ {}", mapping_path(line), start));
+        } else if first == "Rss:" {
+            let rss = fields.next().and_then(|kb| kb.parse().ok()).unwrap_or(0);
+            if let Some(name) = current.take() {
+                regions.insert(name, rss);
+            }
+        }
+    }
+
+    regions
+}
+
+/// The path of a mapping's header line, `address perms offset dev inode
+/// [path]`: everything after the inode, as paths may contain spaces.
+fn mapping_path(header: &str) -> &str {
+    let mut rest = header;
+    for _ in 0..5 {
+        rest = rest
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .map_or("", |(_, rest)| rest);
+    }
+    match rest.trim() {
+        "" => "[anon]",
+        path => path,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mappings_are_named_by_their_whole_path() {
+        let smaps = "\
+55d4c2a00000-55d4c2a21000 r-xp 00000000 08:01 131090                     /opt/My App/bin/app
+Size:                132 kB
+Rss:                 120 kB
+7f3a1c000000-7f3a1c021000 rw-p 00000000 00:00 0 
+Size:                132 kB
+Rss:                  8 kB
+";
+        assert_eq!(
+            parse_smaps(smaps),
+            HashMap::from([
+                ("/opt/My App/bin/app @ 55d4c2a00000".to_string(), 120),
+                ("[anon] @ 7f3a1c000000".to_string(), 8),
+            ])
+        );
+    }
+}
@@ -0,0 +1,242 @@
+use std::{collections::VecDeque, fs, io};
+
+use chrono::Duration;
+use iced::{
+    widget::canvas::{Cache, Frame, Geometry},
+    Element, Length, Size,
+};
+use plotters::{coord::Shift, prelude::*};
+use plotters_iced::{Chart, ChartWidget, DrawingBackend, Renderer};
+
+use crate::{
+    clock::{Time, TimeAxis},
+    colors::ChartPalette,
+    config::ByteUnits,
+    Message,
+};
+
+/// `/proc/meminfo` fields stacked on the chart, bottom first, with their
+/// legend labels.
+const CATEGORIES: [(&str, &str); 6] = [
+    ("AnonPages", "Anonymous"),
+    ("Cached", "Page cache"),
+    ("SReclaimable", "Reclaimable slab"),
+    ("SUnreclaim", "Unreclaimable slab"),
+    ("KernelStack", "Kernel stacks"),
+    ("PageTables", "Page tables"),
+];
+
+/// One read of `/proc/meminfo`, in KiB.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MemInfo {
+    total: u64,
+    /// In the order of `CATEGORIES`.
+    categories: [u64; CATEGORIES.len()],
+}
+
+/// System memory over time, broken down into what holds it: one stacked
+/// area per category, against `MemTotal`.
+pub struct MemoryTimeline {
+    cache: Cache,
+    samples: VecDeque<(Time, [u64; CATEGORIES.len()])>,
+    /// `MemTotal` in KiB; 0 until the first read.
+    total: u64,
+    limit: Duration,
+    pub byte_units: ByteUnits,
+    palette: ChartPalette,
+}
+
+impl MemoryTimeline {
+    pub fn new() -> Self {
+        Self {
+            cache: Cache::new(),
+            samples: VecDeque::new(),
+            total: 0,
+            limit: Duration::seconds(60),
+            byte_units: ByteUnits::Binary,
+            palette: ChartPalette::default(),
+        }
+    }
+
+    pub fn update(&mut self, time: Time) -> io::Result<()> {
+        let meminfo = parse_meminfo(&fs::read_to_string("/proc/meminfo")?).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "no MemTotal in /proc/meminfo")
+        })?;
+        self.total = meminfo.total;
+        self.samples.push_front((time, meminfo.categories));
+        while self
+            .samples
+            .back()
+            .is_some_and(|(oldest, _)| time - *oldest > self.limit)
+        {
+            self.samples.pop_back();
+        }
+        self.cache.clear();
+        Ok(())
+    }
+
+    pub fn set_limit(&mut self, limit: std::time::Duration) {
+        self.limit = Duration::from_std(limit).unwrap_or(self.limit);
+        self.cache.clear();
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        self.cache.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+        self.cache.clear();
+    }
+
+    pub fn view(&self, height: f32) -> Element<'_, Message> {
+        ChartWidget::new(self)
+            .width(Length::Fill)
+            .height(Length::Fixed(height))
+            .into()
+    }
+
+    fn format(&self, kib: u64) -> String {
+        self.byte_units.format(kib as f64 * 1024.0)
+    }
+}
+
+impl Chart<Message> for MemoryTimeline {
+    type State = ();
+
+    #[inline]
+    fn draw<R: Renderer, F: Fn(&mut Frame)>(
+        &self,
+        renderer: &R,
+        bounds: Size,
+        draw_fn: F,
+    ) -> Geometry {
+        renderer.draw_cache(&self.cache, bounds, draw_fn)
+    }
+
+    fn draw_chart<DB: DrawingBackend>(&self, state: &Self::State, root: DrawingArea<DB, Shift>) {
+        self.build_chart(state, ChartBuilder::on(&root));
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut chart: ChartBuilder<DB>) {
+        let Some(&(newest_time, newest)) = self.samples.front() else {
+            return;
+        };
+        let oldest_time = newest_time - self.limit;
+
+        let mut chart = chart
+            .x_label_area_size(0)
+            .y_label_area_size(80)
+            .margin(20)
+            .caption(
+                format!("Memory of {}", self.format(self.total)),
+                ("sans-serif", 18).into_font().color(&self.palette.label()),
+            )
+            .build_cartesian_2d(
+                TimeAxis::from(oldest_time..newest_time),
+                0..self.total.max(1),
+            )
+            .expect("failed to build chart");
+
+        chart
+            .configure_mesh()
+            .bold_line_style(self.palette.grid())
+            .light_line_style(self.palette.fine_grid())
+            .axis_style(ShapeStyle::from(self.palette.axis()).stroke_width(1))
+            .y_labels(8)
+            .y_label_style(("sans-serif", 13).into_font().color(&self.palette.label()))
+            .y_label_formatter(&|kib| self.format(*kib))
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        // Top of the stack first, so every lower area is painted over the
+        // ones above it and only its own band shows.
+        for (index, (_, label)) in CATEGORIES.iter().enumerate().rev() {
+            // Fixed hues, as the theme has fewer accents than categories.
+            let color = Palette99::pick(index);
+            chart
+                .draw_series(AreaSeries::new(
+                    self.samples
+                        .iter()
+                        .map(|(time, categories)| (*time, stacked(categories, index))),
+                    0,
+                    color.filled(),
+                ))
+                .expect("failed to draw chart data")
+                .label(format!("{}: {}", label, self.format(newest[index])))
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 15, y + 5)], color.filled())
+                });
+        }
+
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .background_style(self.palette.legend_background())
+            .border_style(self.palette.legend_border())
+            .label_font(
+                ("sans-serif", 14)
+                    .into_font()
+                    .color(&self.palette.annotation_text()),
+            )
+            .draw()
+            .expect("failed to draw legend");
+    }
+}
+
+/// Top of the area of category `index`: it and everything under it.
+fn stacked(categories: &[u64], index: usize) -> u64 {
+    categories[..=index].iter().sum()
+}
+
+/// `MemTotal` and the charted categories, in KiB. A category the kernel
+/// doesn't report counts as 0.
+fn parse_meminfo(text: &str) -> Option<MemInfo> {
+    let field = |key: &str| {
+        text.lines().find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            (name == key).then(|| value.split_whitespace().next()?.parse().ok())?
+        })
+    };
+    Some(MemInfo {
+        total: field("MemTotal")?,
+        categories: CATEGORIES.map(|(key, _)| field(key).unwrap_or(0)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_charted_fields() {
+        let meminfo = "\
+MemTotal:       32594196 kB
+MemFree:        20125540 kB
+Cached:          6013220 kB
+SwapCached:            0 kB
+AnonPages:       4021640 kB
+KernelStack:       26432 kB
+PageTables:        61240 kB
+SReclaimable:     412700 kB
+SUnreclaim:       157908 kB
+";
+        assert_eq!(
+            parse_meminfo(meminfo),
+            Some(MemInfo {
+                total: 32594196,
+                categories: [4021640, 6013220, 412700, 157908, 26432, 61240],
+            })
+        );
+        assert_eq!(parse_meminfo("MemFree: 1 kB\n"), None);
+    }
+
+    #[test]
+    fn areas_stack_from_the_bottom() {
+        let categories = [4, 3, 2, 1, 0, 5];
+        assert_eq!(stacked(&categories, 0), 4);
+        assert_eq!(stacked(&categories, 2), 9);
+        assert_eq!(stacked(&categories, 5), 15);
+    }
+}
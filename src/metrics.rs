@@ -0,0 +1,518 @@
+//! Every metric monty charts. `SystemChart` keeps one chart per entry of
+//! [`REGISTRY`], and layout, config, titles, snapshots, thresholds, alerts
+//! and the command palette all iterate it, so adding a metric means a `ChartId`
+//! variant and a descriptor here, plus a `SampleSource` if none of the
+//! existing ones reads it; `SystemChart::update` fills every chart from its
+//! source.
+//! Charts derived from these in the config, then those of `--plugin`
+//! libraries, are registered behind them at startup; see
+//! [`register_derived`] and [`register_plugins`].
+
+use std::sync::OnceLock;
+
+use iced::{theme::palette::Extended, Color};
+
+use crate::{config::DerivedConfig, expr::Expr, plugin::Plugin, sources::SampleSource, ChartId};
+
+pub struct MetricDescriptor {
+    pub id: ChartId,
+    /// Stable name used in the config file and history snapshots.
+    pub key: &'static str,
+    pub label: &'static str,
+    pub unit: &'static str,
+    /// What the chart samples unless its config binds it to another
+    /// source; `None` for derived charts and plugins, which fill themselves.
+    pub source: Option<SampleSource>,
+    /// Top of the y-axis until something raises it.
+    pub default_max: i32,
+    /// Bottom of the y-axis, 0 unless the metric can go negative.
+    pub default_min: i32,
+    /// Samples are the reading times this, so fractional metrics like IPC
+    /// fit the integer history; 1 for everything else.
+    pub scale: i32,
+    /// Value above which the trace turns the palette's warning color.
+    pub warn_above: Option<i32>,
+    /// Fit the y-axis to the data unless the config says otherwise, for
+    /// rates without a natural top.
+    pub auto_scale: bool,
+    /// Tile title template unless the config sets one; see
+    /// [`format_title`].
+    pub title: &'static str,
+    /// Line color picked from the theme, unless the config sets one.
+    pub default_color: fn(&Extended) -> Color,
+}
+
+/// Tile title of most metrics.
+pub const DEFAULT_TITLE: &str = "{label}: {value}{unit}";
+
+/// In default layout order.
+pub const REGISTRY: &[MetricDescriptor] = &[
+    MetricDescriptor {
+        id: ChartId::Usage,
+        key: "usage",
+        label: "CPU usage",
+        unit: "%",
+        source: Some(SampleSource::GlobalCpu),
+        default_max: 100,
+        default_min: 0,
+        scale: 1,
+        warn_above: None,
+        auto_scale: false,
+        title: DEFAULT_TITLE,
+        default_color: |palette| palette.primary.base.color,
+    },
+    MetricDescriptor {
+        id: ChartId::Freq,
+        key: "freq",
+        label: "Frequency",
+        unit: " MHz",
+        source: Some(SampleSource::AverageFreq),
+        default_max: 5000,
+        default_min: 0,
+        scale: 1,
+        warn_above: None,
+        auto_scale: false,
+        title: DEFAULT_TITLE,
+        default_color: |palette| palette.success.base.color,
+    },
+    MetricDescriptor {
+        id: ChartId::Temp,
+        key: "temp",
+        label: "Temperature",
+        unit: " °C",
+        source: Some(SampleSource::PackageTemp),
+        default_max: 100,
+        default_min: 0,
+        scale: 1,
+        warn_above: None,
+        auto_scale: false,
+        title: DEFAULT_TITLE,
+        default_color: |palette| palette.danger.base.color,
+    },
+    MetricDescriptor {
+        id: ChartId::Watts,
+        key: "watts",
+        label: "Power draw",
+        unit: " W",
+        source: Some(SampleSource::PkgWatts),
+        default_max: 80,
+        default_min: 0,
+        scale: 1,
+        warn_above: None,
+        auto_scale: false,
+        title: DEFAULT_TITLE,
+        default_color: |palette| palette.secondary.base.color,
+    },
+    MetricDescriptor {
+        id: ChartId::Headroom,
+        key: "headroom",
+        label: "TDP headroom",
+        unit: "%",
+        source: Some(SampleSource::TdpHeadroom),
+        default_max: 100,
+        default_min: 0,
+        scale: 1,
+        warn_above: None,
+        auto_scale: false,
+        title: "{label}: {value}{unit} of {tdp} W",
+        default_color: |palette| palette.success.strong.color,
+    },
+    MetricDescriptor {
+        id: ChartId::TempRate,
+        key: "dtemp",
+        label: "Temperature rate",
+        unit: " °C/s",
+        source: Some(SampleSource::TempRate),
+        default_max: 10,
+        default_min: -10,
+        scale: 1,
+        warn_above: Some(3),
+        auto_scale: false,
+        title: DEFAULT_TITLE,
+        default_color: |palette| palette.danger.weak.color,
+    },
+    MetricDescriptor {
+        id: ChartId::Ipc,
+        key: "ipc",
+        label: "Instructions per cycle",
+        unit: "",
+        source: Some(SampleSource::Ipc),
+        // Close to the sustained limit of current cores.
+        default_max: 4,
+        default_min: 0,
+        scale: 100,
+        warn_above: None,
+        auto_scale: false,
+        title: DEFAULT_TITLE,
+        default_color: |palette| palette.primary.strong.color,
+    },
+    MetricDescriptor {
+        id: ChartId::GpuMem,
+        key: "gpu_mem",
+        label: "GPU memory",
+        // Samples are KiB, shown in the configured byte units.
+        unit: "",
+        source: Some(SampleSource::GpuMemory),
+        // 8 GiB until the card reports its total.
+        default_max: 8 << 20,
+        default_min: 0,
+        scale: 1,
+        warn_above: None,
+        auto_scale: false,
+        title: DEFAULT_TITLE,
+        default_color: |palette| palette.primary.weak.color,
+    },
+    MetricDescriptor {
+        id: ChartId::Latency,
+        key: "latency",
+        label: "Ping latency",
+        unit: " ms",
+        source: Some(SampleSource::PingLatency),
+        default_max: 200,
+        default_min: 0,
+        // LAN round trips are well under a millisecond.
+        scale: 100,
+        // Red above its threshold, or `ping_threshold_ms`, instead.
+        warn_above: None,
+        auto_scale: false,
+        title: DEFAULT_TITLE,
+        default_color: |palette| palette.secondary.strong.color,
+    },
+    MetricDescriptor {
+        id: ChartId::ContextSwitches,
+        key: "context_switches",
+        label: "Context switches",
+        unit: "/s",
+        source: Some(SampleSource::ContextSwitches),
+        default_max: 10_000,
+        default_min: 0,
+        scale: 1,
+        warn_above: None,
+        auto_scale: true,
+        title: DEFAULT_TITLE,
+        default_color: |palette| palette.primary.weak.color,
+    },
+    MetricDescriptor {
+        id: ChartId::Interrupts,
+        key: "interrupts",
+        label: "Interrupts",
+        unit: "/s",
+        source: Some(SampleSource::Interrupts),
+        default_max: 10_000,
+        default_min: 0,
+        scale: 1,
+        warn_above: None,
+        auto_scale: true,
+        title: DEFAULT_TITLE,
+        default_color: |palette| palette.secondary.weak.color,
+    },
+];
+
+/// A chart computed from the built-in metrics by an expression.
+pub struct DerivedMetric {
+    pub descriptor: MetricDescriptor,
+    pub expr: Expr,
+}
+
+static DERIVED: OnceLock<Vec<DerivedMetric>> = OnceLock::new();
+
+/// Registers the config's derived charts behind the built-in metrics and
+/// returns an error for every entry that was skipped. Only the first call
+/// registers anything, as the registry must not change under the charts.
+pub fn register_derived(configs: &[DerivedConfig]) -> Vec<String> {
+    let (metrics, mut errors) = build_derived(configs);
+    if DERIVED.set(metrics).is_err() {
+        errors.push("Derived charts were already registered".to_string());
+    }
+    errors
+}
+
+fn build_derived(configs: &[DerivedConfig]) -> (Vec<DerivedMetric>, Vec<String>) {
+    let built_in = |key: &str| REGISTRY.iter().any(|metric| metric.key == key);
+    let mut metrics: Vec<DerivedMetric> = Vec::new();
+    let mut errors = Vec::new();
+    for config in configs {
+        let name = config.name.as_str();
+        if built_in(name) || metrics.iter().any(|m| m.descriptor.key == name) {
+            errors.push(format!("Derived chart `{}` is defined twice", name));
+            continue;
+        }
+        let expr = match Expr::parse(&config.expr, built_in) {
+            Ok(expr) => expr,
+            Err(e) => {
+                errors.push(format!(
+                    "Derived chart `{}`: {} in `{}`",
+                    name, e, config.expr
+                ));
+                continue;
+            }
+        };
+        // Registered once for the whole run, so the strings can live as
+        // long as the built-in ones.
+        let name: &'static str = Box::leak(name.into());
+        let unit = match config.unit.as_str() {
+            "" | "%" => config.unit.clone(),
+            unit => format!(" {}", unit),
+        };
+        metrics.push(DerivedMetric {
+            descriptor: MetricDescriptor {
+                id: ChartId::Derived(metrics.len()),
+                key: name,
+                label: name,
+                unit: Box::leak(unit.into()),
+                source: None,
+                default_max: config.max,
+                default_min: config.min,
+                // Ratios like MHz/W need decimals to be useful.
+                scale: 100,
+                warn_above: None,
+                auto_scale: false,
+                title: DEFAULT_TITLE,
+                default_color: |palette| palette.secondary.strong.color,
+            },
+            expr,
+        });
+    }
+    (metrics, errors)
+}
+
+fn derived() -> &'static [DerivedMetric] {
+    DERIVED.get().map_or(&[], Vec::as_slice)
+}
+
+static PLUGINS: OnceLock<Vec<MetricDescriptor>> = OnceLock::new();
+
+/// Registers a chart per plugin behind the derived ones, which must be
+/// registered first. Returns the plugins with their charts, and an error
+/// for every plugin that was skipped.
+pub fn register_plugins(plugins: Vec<Plugin>) -> (Vec<(ChartId, Plugin)>, Vec<String>) {
+    let (metrics, registered, mut errors) = build_plugins(plugins);
+    if PLUGINS.set(metrics).is_err() {
+        errors.push("Plugins were already registered".to_string());
+        return (Vec::new(), errors);
+    }
+    (registered, errors)
+}
+
+fn build_plugins(
+    plugins: Vec<Plugin>,
+) -> (Vec<MetricDescriptor>, Vec<(ChartId, Plugin)>, Vec<String>) {
+    let mut metrics: Vec<MetricDescriptor> = Vec::new();
+    let mut registered = Vec::new();
+    let mut errors = Vec::new();
+    for plugin in plugins {
+        let name = plugin.name.as_str();
+        let taken = REGISTRY
+            .iter()
+            .chain(derived().iter().map(|metric| &metric.descriptor))
+            .chain(&metrics)
+            .any(|metric| metric.key == name);
+        if taken {
+            errors.push(format!("Plugin `{}` clashes with another metric", name));
+            continue;
+        }
+        let id = ChartId::Plugin(metrics.len());
+        let unit = match plugin.unit.as_str() {
+            "" | "%" => plugin.unit.clone(),
+            unit => format!(" {}", unit),
+        };
+        let name: &'static str = Box::leak(name.into());
+        metrics.push(MetricDescriptor {
+            id,
+            key: name,
+            label: name,
+            unit: Box::leak(unit.into()),
+            source: None,
+            default_max: plugin.max_value,
+            default_min: 0,
+            scale: 1,
+            warn_above: None,
+            auto_scale: false,
+            title: DEFAULT_TITLE,
+            default_color: |palette| palette.primary.weak.color,
+        });
+        registered.push((id, plugin));
+    }
+    (metrics, registered, errors)
+}
+
+fn plugins() -> &'static [MetricDescriptor] {
+    PLUGINS.get().map_or(&[], Vec::as_slice)
+}
+
+/// The expression of a derived chart; `None` for built-in metrics.
+pub fn expr(id: ChartId) -> Option<&'static Expr> {
+    match id {
+        ChartId::Derived(index) => Some(&derived()[index].expr),
+        _ => None,
+    }
+}
+
+pub fn descriptor(id: ChartId) -> &'static MetricDescriptor {
+    descriptors()
+        .find(|metric| metric.id == id)
+        .expect("every ChartId is registered")
+}
+
+/// Built-in metrics, then derived ones, then plugins.
+pub fn descriptors() -> impl Iterator<Item = &'static MetricDescriptor> {
+    REGISTRY
+        .iter()
+        .chain(derived().iter().map(|metric| &metric.descriptor))
+        .chain(plugins())
+}
+
+/// The metric with this config key.
+pub fn id(key: &str) -> Option<ChartId> {
+    ids().find(|id| id.key() == key)
+}
+
+/// Ids of all metrics, in default layout order.
+pub fn ids() -> impl Iterator<Item = ChartId> {
+    descriptors().map(|metric| metric.id)
+}
+
+/// Fills placeholders like `{label}`, `{value}` and `{unit}` into a title
+/// template from `args`; `value` comes formatted already, at the chart's
+/// precision. Unknown placeholders are kept as typed.
+pub fn format_title(template: &str, args: &[(&str, &str)]) -> String {
+    let mut title = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        title += &rest[..start];
+        rest = &rest[start..];
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        match args.iter().find(|(name, _)| *name == &rest[1..end]) {
+            Some((_, value)) => title += value,
+            None => title += &rest[..=end],
+        }
+        rest = &rest[end + 1..];
+    }
+    title + rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn keys_are_unique() {
+        for metric in REGISTRY {
+            assert_eq!(descriptor(metric.id).key, metric.key);
+            assert_eq!(REGISTRY.iter().filter(|m| m.key == metric.key).count(), 1);
+        }
+    }
+
+    #[test]
+    fn title_template_fills_placeholders() {
+        let args = |label, value, unit| [("label", label), ("value", value), ("unit", unit)];
+        assert_eq!(
+            format_title(DEFAULT_TITLE, &args("Frequency", "3400", " MHz")),
+            "Frequency: 3400 MHz"
+        );
+        assert_eq!(
+            format_title("{value}{unit} {label}", &args("IPC", "1.37", "")),
+            "1.37 IPC"
+        );
+        assert_eq!(
+            format_title(
+                descriptor(ChartId::Headroom).title,
+                &[
+                    ("label", "TDP headroom"),
+                    ("value", "40"),
+                    ("unit", "%"),
+                    ("tdp", "65")
+                ]
+            ),
+            "TDP headroom: 40% of 65 W"
+        );
+        // Unknown and unterminated placeholders are kept as typed.
+        assert_eq!(
+            format_title("{label} {nope} {value", &args("Temperature", "60", " °C")),
+            "Temperature {nope} {value"
+        );
+    }
+
+    #[test]
+    fn derived_charts_that_fail_to_parse_are_reported() {
+        let derived = |name: &str, expr: &str| DerivedConfig {
+            name: name.to_string(),
+            expr: expr.to_string(),
+            unit: "MHz/W".to_string(),
+            ..DerivedConfig::default()
+        };
+        let (metrics, errors) = build_derived(&[
+            derived("efficiency", "freq * usage / 100 / watts"),
+            derived("broken", "freq * volts"),
+            derived("efficiency", "freq"),
+            derived("temp", "temp"),
+        ]);
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].descriptor.id, ChartId::Derived(0));
+        assert_eq!(metrics[0].descriptor.unit, " MHz/W");
+        assert_eq!(
+            errors,
+            [
+                "Derived chart `broken`: unknown metric `volts` at column 8 in `freq * volts`",
+                "Derived chart `efficiency` is defined twice",
+                "Derived chart `temp` is defined twice",
+            ]
+        );
+    }
+
+    #[test]
+    fn default_layout_is_registry_order() {
+        assert_eq!(Config::default().layout(), ids().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn layout_appends_metrics_missing_from_config() {
+        let config = Config {
+            layout: Some(vec!["watts".into(), "usage".into(), "watts".into()]),
+            ..Config::default()
+        };
+        assert_eq!(
+            config.layout(),
+            vec![
+                ChartId::Watts,
+                ChartId::Usage,
+                ChartId::Freq,
+                ChartId::Temp,
+                ChartId::Headroom,
+                ChartId::TempRate,
+                ChartId::Ipc,
+                ChartId::GpuMem,
+                ChartId::Latency,
+                ChartId::ContextSwitches,
+                ChartId::Interrupts,
+            ]
+        );
+    }
+
+    #[test]
+    fn layout_leaves_out_hidden_metrics() {
+        let mut config = Config {
+            layout: Some(vec!["temp".into(), "gone".into()]),
+            ..Config::default()
+        };
+        config.chart_mut(ChartId::Temp).hidden = true;
+        config.chart_mut(ChartId::Headroom).hidden = true;
+        assert_eq!(
+            config.layout(),
+            vec![
+                ChartId::Usage,
+                ChartId::Freq,
+                ChartId::Watts,
+                ChartId::TempRate,
+                ChartId::Ipc,
+                ChartId::GpuMem,
+                ChartId::Latency,
+                ChartId::ContextSwitches,
+                ChartId::Interrupts,
+            ]
+        );
+    }
+}
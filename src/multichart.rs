@@ -0,0 +1,117 @@
+use iced::{Element, Length};
+use plotters::{coord::Shift, prelude::*};
+use plotters_iced::{Chart, ChartWidget, DrawingBackend};
+
+use crate::{clock::TimeAxis, colors::ChartPalette, Message, SimpleChart};
+
+/// Every chart on one shared time axis, each as a line scaled to 0–100% of
+/// its own range, so the metrics can be correlated at a glance.
+pub struct MultiChart<'a> {
+    series: Vec<(&'static str, &'a SimpleChart)>,
+    palette: ChartPalette,
+}
+
+impl<'a> MultiChart<'a> {
+    pub fn new(series: Vec<(&'static str, &'a SimpleChart)>, palette: ChartPalette) -> Self {
+        Self { series, palette }
+    }
+
+    pub fn view(self, height: f32) -> Element<'a, Message> {
+        ChartWidget::new(self)
+            .width(Length::Fill)
+            .height(Length::Fixed(height))
+            .into()
+    }
+}
+
+impl Chart<Message> for MultiChart<'_> {
+    type State = ();
+
+    fn draw_chart<DB: DrawingBackend>(&self, state: &Self::State, root: DrawingArea<DB, Shift>) {
+        self.build_chart(state, ChartBuilder::on(&root));
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut chart: ChartBuilder<DB>) {
+        // Wide enough for the longest window, ending at the newest sample.
+        let ranges = self.series.iter().map(|(_, series)| series.time_range());
+        let (Some(oldest_time), Some(newest_time)) = (
+            ranges.clone().map(|(oldest, _)| oldest).min(),
+            ranges.map(|(_, newest)| newest).max(),
+        ) else {
+            return;
+        };
+
+        let mut chart = chart
+            .x_label_area_size(0)
+            .y_label_area_size(48)
+            .margin(20)
+            .build_cartesian_2d(TimeAxis::from(oldest_time..newest_time), 0.0..100.0)
+            .expect("failed to build chart");
+
+        chart
+            .configure_mesh()
+            .bold_line_style(self.palette.grid())
+            .light_line_style(self.palette.fine_grid())
+            .axis_style(ShapeStyle::from(self.palette.axis()).stroke_width(1))
+            .y_labels(10)
+            .y_label_style(("sans-serif", 15).into_font().color(&self.palette.label()))
+            .y_label_formatter(&|y| format!("{:.0}%", y))
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        for &(label, series) in &self.series {
+            let color = series.color;
+            chart
+                .draw_series(LineSeries::new(
+                    series
+                        .raw_data()
+                        .iter()
+                        .map(|&(time, value)| (time, normalized(series, value))),
+                    ShapeStyle::from(color).stroke_width(2),
+                ))
+                .expect("failed to draw chart data")
+                .label(label)
+                .legend(move |(x, y)| {
+                    PathElement::new(vec![(x, y), (x + 20, y)], color.stroke_width(2))
+                });
+        }
+
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .background_style(self.palette.legend_background())
+            .border_style(self.palette.legend_border())
+            .label_font(
+                ("sans-serif", 15)
+                    .into_font()
+                    .color(&self.palette.annotation_text()),
+            )
+            .draw()
+            .expect("failed to draw legend");
+    }
+}
+
+/// `value` as a percentage of the chart's y-axis range.
+fn normalized(series: &SimpleChart, value: i32) -> f64 {
+    let (bottom, top) = (series.min_value as f64, series.y_max() as f64);
+    if top <= bottom {
+        return 0.0;
+    }
+    ((value as f64 - bottom) / (top - bottom) * 100.0).clamp(0.0, 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_are_scaled_to_their_own_range() {
+        let mut rate = SimpleChart::new(std::iter::empty(), " °C/s".into(), 10);
+        rate.min_value = -10;
+        assert_eq!(normalized(&rate, 0), 50.0);
+        assert_eq!(normalized(&rate, 15), 100.0);
+
+        let freq = SimpleChart::new(std::iter::empty(), " MHz".into(), 5000);
+        assert_eq!(normalized(&freq, 1250), 25.0);
+    }
+}
@@ -0,0 +1,137 @@
+use iced::{
+    widget::{text_input, Button, Column, Container, Text},
+    Element, Length,
+};
+
+use crate::Message;
+
+/// How many matching actions are listed at once.
+const MAX_SHOWN: usize = 10;
+
+/// Ctrl+P search over every registered action, run with Enter.
+pub struct CommandPalette {
+    query: String,
+    /// Index into the matching actions, not into all of them.
+    selected: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum PaletteMessage {
+    Query(String),
+    /// Moves the selection up (negative) or down the matches.
+    Move(isize),
+    RunSelected,
+    Run(usize),
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            selected: 0,
+        }
+    }
+
+    /// Returns the action to run, if the message picked one.
+    pub fn update(
+        &mut self,
+        message: PaletteMessage,
+        actions: &[(String, Message)],
+    ) -> Option<Message> {
+        let count = self.matches(actions).take(MAX_SHOWN).count();
+        match message {
+            PaletteMessage::Query(query) => {
+                self.query = query;
+                self.selected = 0;
+                None
+            }
+            PaletteMessage::Move(delta) => {
+                self.selected = self
+                    .selected
+                    .saturating_add_signed(delta)
+                    .min(count.saturating_sub(1));
+                None
+            }
+            PaletteMessage::RunSelected => self.run(actions, self.selected),
+            PaletteMessage::Run(index) => self.run(actions, index),
+        }
+    }
+
+    fn run(&self, actions: &[(String, Message)], index: usize) -> Option<Message> {
+        self.matches(actions)
+            .nth(index)
+            .map(|(_, message)| message.clone())
+    }
+
+    fn matches<'a>(
+        &'a self,
+        actions: &'a [(String, Message)],
+    ) -> impl Iterator<Item = &'a (String, Message)> {
+        actions
+            .iter()
+            .filter(|(name, _)| fuzzy_match(&self.query, name))
+    }
+
+    pub fn view(&self, actions: &[(String, Message)]) -> Element<'_, Message> {
+        let input = text_input("Type an action", &self.query)
+            .id(input_id())
+            .on_input(|query| Message::Palette(PaletteMessage::Query(query)))
+            .on_submit(Message::Palette(PaletteMessage::RunSelected));
+
+        let list = self.matches(actions).take(MAX_SHOWN).enumerate().fold(
+            Column::new().spacing(2),
+            |list, (i, (name, _))| {
+                let label = if i == self.selected {
+                    format!("▸ {}", name)
+                } else {
+                    format!("  {}", name)
+                };
+                list.push(
+                    Button::new(Text::new(label))
+                        .width(Length::Fill)
+                        .style(if i == self.selected {
+                            iced::theme::Button::Primary
+                        } else {
+                            iced::theme::Button::Text
+                        })
+                        .on_press(Message::Palette(PaletteMessage::Run(i))),
+                )
+            },
+        );
+
+        Container::new(Column::new().spacing(10).push(input).push(list))
+            .width(Length::Fixed(480.0))
+            .padding(10)
+            .style(iced::theme::Container::Box)
+            .into()
+    }
+}
+
+pub fn input_id() -> text_input::Id {
+    text_input::Id::new("palette")
+}
+
+/// Whether every character of `query` appears in `name` in order,
+/// ignoring case; `clhi` matches "Clear history".
+fn fuzzy_match(query: &str, name: &str) -> bool {
+    let mut name = name.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .all(|q| name.any(|c| c == q))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_match_needs_characters_in_order() {
+        assert!(fuzzy_match("clhi", "Clear history"));
+        assert!(fuzzy_match("", "Clear history"));
+        assert!(fuzzy_match("CLEAR hist", "Clear history"));
+        assert!(!fuzzy_match("hicl", "Clear history"));
+        assert!(!fuzzy_match("x", "Clear history"));
+    }
+}
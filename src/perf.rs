@@ -0,0 +1,126 @@
+//! Hardware cycle and instruction counters through `perf_event_open`,
+//! counted system-wide on every CPU. Needs root, `CAP_PERFMON` or
+//! `kernel.perf_event_paranoid` at 0 or below.
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    mem,
+    os::fd::FromRawFd,
+};
+
+const PERF_TYPE_HARDWARE: u32 = 0;
+const PERF_COUNT_HW_CPU_CYCLES: u64 = 0;
+const PERF_COUNT_HW_INSTRUCTIONS: u64 = 1;
+/// `exclude_hv` in the attribute's flag bits.
+const EXCLUDE_HV: u64 = 1 << 6;
+
+/// `struct perf_event_attr` up to `PERF_ATTR_SIZE_VER5`, which libc doesn't
+/// define. Everything not set here stays zero.
+#[repr(C)]
+#[derive(Default)]
+struct PerfEventAttr {
+    kind: u32,
+    size: u32,
+    config: u64,
+    sample_period: u64,
+    sample_type: u64,
+    read_format: u64,
+    flags: u64,
+    wakeup_events: u32,
+    bp_type: u32,
+    config1: u64,
+    config2: u64,
+    branch_sample_type: u64,
+    sample_regs_user: u64,
+    sample_stack_user: u32,
+    clockid: i32,
+    sample_regs_intr: u64,
+    aux_watermark: u32,
+    sample_max_stack: u16,
+    reserved: u16,
+}
+
+/// Instructions per cycle across all CPUs.
+pub struct PerfEventReader {
+    /// Cycle and instruction counter of every CPU.
+    counters: Vec<(File, File)>,
+    /// Totals at the previous reading.
+    last: Option<(u64, u64)>,
+}
+
+impl PerfEventReader {
+    pub fn open(cpu_count: usize) -> io::Result<Self> {
+        let counters = (0..cpu_count as i32)
+            .map(|cpu| {
+                Ok((
+                    open_counter(PERF_COUNT_HW_CPU_CYCLES, cpu)?,
+                    open_counter(PERF_COUNT_HW_INSTRUCTIONS, cpu)?,
+                ))
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self {
+            counters,
+            last: None,
+        })
+    }
+
+    /// IPC since the previous call; `None` on the first call, if no cycles
+    /// were counted or the counters can't be read.
+    pub fn ipc(&mut self) -> Option<f64> {
+        let mut cycles = 0;
+        let mut instructions = 0;
+        for (cycle_counter, instruction_counter) in &mut self.counters {
+            cycles += read_counter(cycle_counter).ok()?;
+            instructions += read_counter(instruction_counter).ok()?;
+        }
+        let (last_cycles, last_instructions) = self.last.replace((cycles, instructions))?;
+        let cycles = cycles.saturating_sub(last_cycles);
+        (cycles > 0).then(|| instructions.saturating_sub(last_instructions) as f64 / cycles as f64)
+    }
+}
+
+fn open_counter(config: u64, cpu: i32) -> io::Result<File> {
+    let attr = PerfEventAttr {
+        kind: PERF_TYPE_HARDWARE,
+        size: mem::size_of::<PerfEventAttr>() as u32,
+        config,
+        flags: EXCLUDE_HV,
+        ..Default::default()
+    };
+    // SAFETY: `attr` is a valid, fully initialized perf_event_attr of the
+    // size it declares, and outlives the call. pid -1 with a CPU counts
+    // every task on that CPU; no group, no flags.
+    let fd = unsafe {
+        libc::syscall(
+            libc::SYS_perf_event_open,
+            &attr as *const PerfEventAttr,
+            -1,
+            cpu,
+            -1,
+            0,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the kernel just handed us this descriptor and nothing else
+    // owns it.
+    Ok(unsafe { File::from_raw_fd(fd as i32) })
+}
+
+fn read_counter(counter: &mut File) -> io::Result<u64> {
+    let mut count = [0; 8];
+    counter.read_exact(&mut count)?;
+    Ok(u64::from_ne_bytes(count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attr_has_the_ver5_layout() {
+        assert_eq!(mem::size_of::<PerfEventAttr>(), 112);
+    }
+}
@@ -0,0 +1,213 @@
+//! Hot spots of a `perf record` session, to mark on the CPU usage chart:
+//! stretches where perf took the most samples, each labeled with the
+//! function most of them hit. Samples are read through `perf script`.
+//! Their timestamps line up with the charts exactly for a recording made
+//! with `-k CLOCK_MONOTONIC`, and to within perf's clock drift otherwise.
+
+use std::{cmp::Reverse, collections::HashMap, io, path::PathBuf, process::Stdio, time::Duration};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, BufReader},
+    process::Command,
+};
+
+/// Width of the bins samples are counted in.
+const BIN: Duration = Duration::from_secs(1);
+/// Share of the busiest bin's samples from which a bin counts as hot.
+const HOT_SHARE: f64 = 0.8;
+/// At most this many hot spots are marked, the ones with most samples.
+const MAX_HOT_SPOTS: usize = 20;
+
+/// A stretch of consecutive hot bins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HotSpot {
+    /// `CLOCK_MONOTONIC` at the start of the stretch.
+    pub start: Duration,
+    /// The function most of the stretch's samples hit.
+    pub symbol: String,
+    /// Samples in the stretch.
+    pub samples: u32,
+}
+
+/// Runs `perf script` on the recording at `path` and finds its hot spots,
+/// oldest first.
+pub async fn load(path: PathBuf) -> io::Result<Vec<HotSpot>> {
+    let mut child = Command::new("perf")
+        .arg("script")
+        .arg("--input")
+        .arg(&path)
+        .args(["--fields", "comm,time,ip,sym", "--hide-call-graph"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+
+    let mut bins = Bins::default();
+    let samples = async {
+        let mut lines = BufReader::new(stdout).lines();
+        while let Some(line) = lines.next_line().await? {
+            if let Some((time, symbol)) = parse_sample(&line) {
+                bins.add(time, symbol);
+            }
+        }
+        io::Result::Ok(())
+    };
+    // perf warns about every missing symbol and lost chunk, and stops
+    // writing samples once a full stderr pipe blocks it, so drain both.
+    let mut errors = Vec::new();
+    let (samples, _) = tokio::join!(samples, stderr.read_to_end(&mut errors));
+    samples?;
+
+    if !child.wait().await?.success() {
+        let errors = String::from_utf8_lossy(&errors);
+        return Err(io::Error::other(format!(
+            "perf script failed: {}",
+            errors.lines().next().unwrap_or("no error message")
+        )));
+    }
+    Ok(bins.hot_spots())
+}
+
+/// The time and function of one line of `perf script --fields
+/// comm,time,ip,sym`, e.g. `cc1 5123.456789: ffffffff81 memcpy`; `None`
+/// for other lines and for samples of the idle task.
+fn parse_sample(line: &str) -> Option<(Duration, &str)> {
+    let (comm, rest) = line.split_once(": ")?;
+    let (comm, time) = comm.trim().rsplit_once(char::is_whitespace)?;
+    if comm.trim() == "swapper" {
+        return None;
+    }
+    let time = parse_seconds(time)?;
+    let mut fields = rest.split_whitespace();
+    let _ip = fields.next()?;
+    let symbol = fields.next().unwrap_or("[unknown]");
+    Some((time, symbol))
+}
+
+/// perf's `seconds.fraction`, without rounding through a float.
+fn parse_seconds(text: &str) -> Option<Duration> {
+    let (secs, fraction) = text.split_once('.').unwrap_or((text, ""));
+    if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let nanos = format!("{:0<9}", fraction).parse().ok()?;
+    Some(Duration::new(secs.parse().ok()?, nanos))
+}
+
+/// Samples by bin, and by function within each.
+#[derive(Default)]
+struct Bins {
+    bins: HashMap<u64, HashMap<String, u32>>,
+}
+
+impl Bins {
+    fn add(&mut self, time: Duration, symbol: &str) {
+        let bin = (time.as_nanos() / BIN.as_nanos()) as u64;
+        let symbols = self.bins.entry(bin).or_default();
+        match symbols.get_mut(symbol) {
+            Some(count) => *count += 1,
+            None => {
+                symbols.insert(symbol.to_string(), 1);
+            }
+        }
+    }
+
+    fn hot_spots(&self) -> Vec<HotSpot> {
+        let total = |symbols: &HashMap<String, u32>| symbols.values().sum::<u32>();
+        let busiest = self.bins.values().map(total).max().unwrap_or_default();
+        let mut hot: Vec<_> = self
+            .bins
+            .iter()
+            .filter(|(_, symbols)| total(symbols) as f64 >= HOT_SHARE * busiest as f64)
+            .collect();
+        hot.sort_unstable_by_key(|(bin, _)| **bin);
+
+        // Consecutive hot bins make one stretch.
+        let mut stretches: Vec<(u64, HashMap<&str, u32>)> = Vec::new();
+        let mut last_bin = None;
+        for (&bin, symbols) in hot {
+            if last_bin.map(|last| last + 1) != Some(bin) {
+                stretches.push((bin, HashMap::new()));
+            }
+            last_bin = Some(bin);
+            let (_, counts) = stretches.last_mut().expect("a stretch was started");
+            for (symbol, count) in symbols {
+                *counts.entry(symbol.as_str()).or_default() += count;
+            }
+        }
+
+        let mut spots: Vec<_> = stretches
+            .into_iter()
+            .filter_map(|(bin, counts)| {
+                let (symbol, _) = counts
+                    .iter()
+                    .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))?;
+                Some(HotSpot {
+                    start: BIN * bin as u32,
+                    symbol: symbol.to_string(),
+                    samples: counts.values().sum(),
+                })
+            })
+            .collect();
+        spots.sort_by_key(|spot| Reverse(spot.samples));
+        spots.truncate(MAX_HOT_SPOTS);
+        spots.sort_by_key(|spot| spot.start);
+        spots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_are_parsed_without_the_idle_task() {
+        assert_eq!(
+            parse_sample("    kworker/u16:3 12345.678901:  ffffffff8123abcd memcpy_orig"),
+            Some((Duration::from_micros(12_345_678_901), "memcpy_orig"))
+        );
+        assert_eq!(
+            parse_sample("  Web Content 100.500000:      7f12a3 [unknown]"),
+            Some((Duration::from_millis(100_500), "[unknown]"))
+        );
+        assert_eq!(
+            parse_sample("         swapper  100.500000:  ffffffff81 intel_idle"),
+            None
+        );
+        assert_eq!(parse_sample(""), None);
+    }
+
+    #[test]
+    fn consecutive_busy_seconds_make_one_hot_spot() {
+        let mut bins = Bins::default();
+        let mut add = |secs: u64, symbol, count| {
+            for _ in 0..count {
+                bins.add(Duration::from_secs(secs), symbol);
+            }
+        };
+        add(10, "idle_loop", 2);
+        add(11, "compress", 9);
+        add(12, "compress", 6);
+        add(12, "memcpy", 4);
+        add(13, "hash", 2);
+        add(20, "memcpy", 10);
+
+        assert_eq!(
+            bins.hot_spots(),
+            [
+                HotSpot {
+                    start: Duration::from_secs(11),
+                    symbol: "compress".to_string(),
+                    samples: 19,
+                },
+                HotSpot {
+                    start: Duration::from_secs(20),
+                    symbol: "memcpy".to_string(),
+                    samples: 10,
+                },
+            ]
+        );
+    }
+}
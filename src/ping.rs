@@ -0,0 +1,131 @@
+//! `--ping HOST`: the round-trip time of an ICMP echo to HOST every
+//! second, for the latency chart, and how many of the last minute's echoes
+//! went unanswered. Echoes go over an unprivileged ICMP socket where
+//! `net.ipv4.ping_group_range` allows one, else a raw socket, which needs
+//! CAP_NET_RAW.
+
+use std::{collections::VecDeque, future::Future, io, net::IpAddr, time::Duration};
+
+use surge_ping::{Client, Config, PingIdentifier, PingSequence, ICMP};
+use tokio::{
+    net::lookup_host,
+    sync::mpsc,
+    time::{self, MissedTickBehavior},
+};
+
+use crate::clock::{self, Time};
+
+pub const INTERVAL: Duration = Duration::from_secs(1);
+/// Echoes the packet loss is worked out over: a minute's.
+const LOSS_WINDOW: usize = 60;
+const PAYLOAD: [u8; 56] = [0; 56];
+
+pub struct PingMonitor {
+    /// Each echo's send time and round-trip time in ms, unset if no reply
+    /// came within the interval.
+    echoes: mpsc::UnboundedReceiver<(Time, Option<f64>)>,
+    /// Whether each of the last echoes was answered, oldest first.
+    answered: VecDeque<bool>,
+}
+
+impl PingMonitor {
+    /// Pings `host` from the returned future, which must run on the tokio
+    /// runtime and only returns on an error or once this is dropped.
+    pub fn start(host: String) -> (Self, impl Future<Output = io::Result<()>>) {
+        let (sender, echoes) = mpsc::unbounded_channel();
+        let ping = async move {
+            let address = lookup_host((host.as_str(), 0))
+                .await?
+                .next()
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, format!("{} has no address", host))
+                })?
+                .ip();
+            let config = match address {
+                IpAddr::V4(_) => Config::default(),
+                IpAddr::V6(_) => Config::builder().kind(ICMP::V6).build(),
+            };
+            let client = Client::new(&config)?;
+            let mut pinger = client
+                .pinger(address, PingIdentifier(std::process::id() as u16))
+                .await;
+            pinger.timeout(INTERVAL);
+
+            let mut interval = time::interval(INTERVAL);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            let mut sequence = 0u16;
+            loop {
+                interval.tick().await;
+                let sent = clock::now();
+                // A timeout and an unreachable network are both loss.
+                let rtt = pinger
+                    .ping(PingSequence(sequence), &PAYLOAD)
+                    .await
+                    .ok()
+                    .map(|(_, rtt)| rtt.as_secs_f64() * 1000.0);
+                if sender.send((sent, rtt)).is_err() {
+                    return Ok(());
+                }
+                sequence = sequence.wrapping_add(1);
+            }
+        };
+        let monitor = Self {
+            echoes,
+            answered: VecDeque::with_capacity(LOSS_WINDOW),
+        };
+        (monitor, ping)
+    }
+
+    /// The round-trip times in ms since the last take, by send time.
+    pub fn take(&mut self) -> Vec<(Time, f64)> {
+        let mut samples = Vec::new();
+        while let Ok((sent, rtt)) = self.echoes.try_recv() {
+            self.record(rtt.is_some());
+            samples.extend(rtt.map(|rtt| (sent, rtt)));
+        }
+        samples
+    }
+
+    fn record(&mut self, answered: bool) {
+        if self.answered.len() == LOSS_WINDOW {
+            self.answered.pop_front();
+        }
+        self.answered.push_back(answered);
+    }
+
+    /// Share of the last minute's echoes that went unanswered in %, unset
+    /// before the first.
+    pub fn loss_percent(&self) -> Option<f64> {
+        let lost = self.answered.iter().filter(|answered| !**answered).count();
+        (!self.answered.is_empty()).then(|| 100.0 * lost as f64 / self.answered.len() as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loss_covers_the_last_minute_of_echoes() {
+        let (sender, echoes) = mpsc::unbounded_channel();
+        let mut monitor = PingMonitor {
+            echoes,
+            answered: VecDeque::new(),
+        };
+        assert_eq!(monitor.loss_percent(), None);
+
+        let now = clock::now();
+        for rtt in [Some(12.0), None, Some(14.0), None] {
+            sender.send((now, rtt)).unwrap();
+        }
+        assert_eq!(monitor.take(), [(now, 12.0), (now, 14.0)]);
+        assert_eq!(monitor.loss_percent(), Some(50.0));
+
+        // A minute of replies later, the losses are out of the window.
+        for _ in 0..LOSS_WINDOW {
+            sender.send((now, Some(10.0))).unwrap();
+        }
+        assert_eq!(monitor.take().len(), LOSS_WINDOW);
+        assert_eq!(monitor.loss_percent(), Some(0.0));
+    }
+}
@@ -0,0 +1,123 @@
+//! Window hints that depend on the display server. Keeping the window
+//! above the others is winit's `_NET_WM_STATE_ABOVE` on X11; Wayland has
+//! no protocol for it, so an overlay there is made borderless and
+//! see-through instead, for a compositor rule to pin.
+
+use std::env;
+
+use iced::{application, theme, window, Color, Theme};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DisplayServer {
+    Wayland,
+    X11,
+    #[default]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlatformHints {
+    pub server: DisplayServer,
+    /// Borderless and translucent, to sit over a game or a video.
+    pub overlay: bool,
+    /// Above every other window.
+    pub always_on_top: bool,
+}
+
+impl PlatformHints {
+    /// The hints for the display server winit will pick: Wayland when
+    /// `WAYLAND_DISPLAY` is set, else X11 when `DISPLAY` is.
+    pub fn detect(overlay: bool, always_on_top: bool) -> Self {
+        let set = |var| env::var_os(var).is_some_and(|value| !value.is_empty());
+        let server = if set("WAYLAND_DISPLAY") {
+            DisplayServer::Wayland
+        } else if set("DISPLAY") {
+            DisplayServer::X11
+        } else {
+            DisplayServer::Unknown
+        };
+        Self {
+            server,
+            overlay,
+            always_on_top,
+        }
+    }
+
+    pub fn apply(&self, window: &mut window::Settings) {
+        // An overlay hidden behind the game is no use. On Wayland winit
+        // ignores the level.
+        if self.always_on_top || self.overlay {
+            window.level = window::Level::AlwaysOnTop;
+        }
+        if self.transparent() {
+            window.decorations = false;
+            window.transparent = true;
+        }
+    }
+
+    /// Whether the window is see-through, so the background must be
+    /// drawn translucent for it to show.
+    pub fn transparent(&self) -> bool {
+        self.overlay && self.server == DisplayServer::Wayland
+    }
+
+    /// The application style for these hints.
+    pub fn style(&self) -> theme::Application {
+        if self.transparent() {
+            theme::Application::custom(Translucent)
+        } else {
+            theme::Application::Default
+        }
+    }
+}
+
+/// The theme's background, letting what is under the window show through.
+struct Translucent;
+
+impl application::StyleSheet for Translucent {
+    type Style = Theme;
+
+    fn appearance(&self, theme: &Theme) -> application::Appearance {
+        let background = theme.extended_palette().background.base;
+        application::Appearance {
+            background_color: Color {
+                a: 0.75,
+                ..background.color
+            },
+            text_color: background.text,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_wayland_overlay_is_transparent() {
+        let hints = |server, overlay, always_on_top| PlatformHints {
+            server,
+            overlay,
+            always_on_top,
+        };
+        let applied = |hints: PlatformHints| {
+            let mut window = window::Settings::default();
+            hints.apply(&mut window);
+            window
+        };
+
+        let overlay = applied(hints(DisplayServer::Wayland, true, false));
+        assert!(overlay.transparent && !overlay.decorations);
+        assert_eq!(overlay.level, window::Level::AlwaysOnTop);
+
+        let on_top = applied(hints(DisplayServer::X11, false, true));
+        assert!(!on_top.transparent && on_top.decorations);
+        assert_eq!(on_top.level, window::Level::AlwaysOnTop);
+
+        let x11_overlay = applied(hints(DisplayServer::X11, true, false));
+        assert!(!x11_overlay.transparent);
+
+        let plain = applied(hints(DisplayServer::Wayland, false, false));
+        assert_eq!(plain.level, window::Level::Normal);
+    }
+}
@@ -0,0 +1,139 @@
+//! Custom metrics from the shared libraries named by `--plugin`; see
+//! `monty-lib` for the interface. A plugin that panics is disabled rather
+//! than taking monty down with it.
+
+use std::{fmt, path::Path};
+
+use color_eyre::eyre::{eyre, Result as EyreResult, WrapErr};
+use libloading::Library;
+use monty_lib::{CreatePlugin, MontyPlugin, CREATE_SYMBOL};
+
+pub struct Plugin {
+    /// Declared before `_library` so it is dropped while its code is still
+    /// loaded.
+    plugin: Box<dyn MontyPlugin>,
+    _library: Option<Library>,
+    pub name: String,
+    pub unit: String,
+    pub max_value: i32,
+    /// Set once the plugin failed; it isn't called again.
+    failed: bool,
+}
+
+impl fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plugin")
+            .field("name", &self.name)
+            .field("failed", &self.failed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Plugin {
+    pub fn load(path: &Path) -> EyreResult<Self> {
+        // SAFETY: loading runs the library's initializers, and the
+        // constructor is trusted to have the signature `monty-lib` gives
+        // it; the user vouched for both by naming the library.
+        let (library, plugin) = unsafe {
+            let library = Library::new(path)
+                .wrap_err_with(|| format!("failed to load plugin {}", path.display()))?;
+            let create = *library
+                .get::<CreatePlugin>(CREATE_SYMBOL)
+                .wrap_err_with(|| {
+                    format!(
+                        "{} is not a monty plugin: no `monty_plugin_create`",
+                        path.display()
+                    )
+                })?;
+            (library, create())
+        };
+        Self::new(plugin, Some(library))
+            .wrap_err_with(|| format!("invalid plugin {}", path.display()))
+    }
+
+    fn new(plugin: Box<dyn MontyPlugin>, library: Option<Library>) -> EyreResult<Self> {
+        if let Some(failure) = plugin.failure() {
+            return Err(eyre!("panicked on creation: {}", failure));
+        }
+        Ok(Self {
+            name: plugin.name().to_string(),
+            unit: plugin.unit().to_string(),
+            max_value: plugin.max_value(),
+            plugin,
+            _library: library,
+            failed: false,
+        })
+    }
+
+    /// The plugin's next reading, or `None` once it has failed. The failure
+    /// that disabled it is returned once, for the event log.
+    pub fn collect(&mut self) -> EyreResult<Option<i32>> {
+        if self.failed {
+            return Ok(None);
+        }
+        let value = self.plugin.collect();
+        match self.plugin.failure() {
+            Some(failure) => {
+                self.failed = true;
+                Err(eyre!(
+                    "Plugin `{}` failed and was disabled: {}",
+                    self.name,
+                    failure
+                ))
+            }
+            None => Ok(Some(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fails like a guarded plugin whose third reading panics.
+    #[derive(Default)]
+    struct Counter {
+        next: i32,
+        failure: Option<String>,
+    }
+
+    impl MontyPlugin for Counter {
+        fn name(&self) -> &str {
+            "counter"
+        }
+        fn unit(&self) -> &str {
+            "ops"
+        }
+        fn max_value(&self) -> i32 {
+            10
+        }
+        fn collect(&mut self) -> i32 {
+            self.next += 1;
+            if self.next == 3 {
+                self.failure = Some("counter overflow".to_string());
+            }
+            self.next
+        }
+        fn failure(&self) -> Option<&str> {
+            self.failure.as_deref()
+        }
+    }
+
+    #[test]
+    fn a_failed_plugin_is_disabled() {
+        let mut plugin = Plugin::new(Box::<Counter>::default(), None).unwrap();
+        assert_eq!((plugin.name.as_str(), plugin.max_value), ("counter", 10));
+        assert_eq!(plugin.collect().unwrap(), Some(1));
+        assert_eq!(plugin.collect().unwrap(), Some(2));
+        assert_eq!(
+            plugin.collect().unwrap_err().to_string(),
+            "Plugin `counter` failed and was disabled: counter overflow"
+        );
+        assert_eq!(plugin.collect().unwrap(), None);
+    }
+
+    #[test]
+    fn missing_libraries_are_refused() {
+        assert!(Plugin::load(Path::new("/nonexistent/libplugin.so")).is_err());
+    }
+}
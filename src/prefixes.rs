@@ -0,0 +1,137 @@
+//! Picks the unit prefix a reading reads best with, e.g. `5.0 GHz` rather
+//! than `5000 MHz` or `12.0 MiB/s` rather than `12582912 B/s`, for chart
+//! titles and axis labels.
+
+/// Units that are multiples of each other.
+struct Family {
+    /// Smallest first, each `step` times the previous.
+    units: &'static [&'static str],
+    step: f64,
+}
+
+/// Units not listed here, like `%` and `°C`, are shown as they are.
+const FAMILIES: &[Family] = &[
+    Family {
+        units: &["Hz", "kHz", "MHz", "GHz", "THz"],
+        step: 1000.0,
+    },
+    Family {
+        units: &["mW", "W", "kW", "MW"],
+        step: 1000.0,
+    },
+    Family {
+        units: &["B/s", "KiB/s", "MiB/s", "GiB/s", "TiB/s"],
+        step: 1024.0,
+    },
+    Family {
+        units: &["kB/s", "MB/s", "GB/s", "TB/s"],
+        step: 1000.0,
+    },
+];
+
+/// A larger unit to show readings in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prefix {
+    pub unit: &'static str,
+    /// Readings in the original unit per one of `unit`.
+    divisor: f64,
+    decimals: usize,
+}
+
+impl Prefix {
+    /// The largest unit in which `magnitude`, a reading in `unit`, is still
+    /// at least 1. `None` if that is `unit` itself, or it has no family.
+    pub fn pick(unit: &str, magnitude: f64) -> Option<Prefix> {
+        let family = FAMILIES
+            .iter()
+            .find(|family| family.units.contains(&unit))?;
+        let base = family.units.iter().position(|u| *u == unit)?;
+
+        let mut divisor = 1.0;
+        let mut index = base;
+        while index + 1 < family.units.len() && magnitude.abs() >= divisor * family.step {
+            divisor *= family.step;
+            index += 1;
+        }
+        (index != base).then(|| Prefix {
+            unit: family.units[index],
+            divisor,
+            // Three significant digits down to 10, two below.
+            decimals: if magnitude.abs() / divisor < 100.0 {
+                1
+            } else {
+                0
+            },
+        })
+    }
+
+    /// `value`, in the original unit, as a number in this one.
+    pub fn format(self, value: f64) -> String {
+        format!("{:.*}", self.decimals, value / self.divisor)
+    }
+
+    /// Appended to formatted values, with the space the chart units use.
+    pub fn suffix(self) -> String {
+        format!(" {}", self.unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scaled(value: f64, unit: &str) -> Option<String> {
+        Prefix::pick(unit, value)
+            .map(|prefix| format!("{}{}", prefix.format(value), prefix.suffix()))
+    }
+
+    #[test]
+    fn switches_at_the_step() {
+        assert_eq!(scaled(999.0, "MHz"), None);
+        assert_eq!(scaled(1000.0, "MHz").as_deref(), Some("1.0 GHz"));
+        assert_eq!(scaled(5000.0, "MHz").as_deref(), Some("5.0 GHz"));
+        assert_eq!(scaled(1500.0, "W").as_deref(), Some("1.5 kW"));
+        assert_eq!(scaled(-2500.0, "W").as_deref(), Some("-2.5 kW"));
+        assert_eq!(scaled(0.0, "W"), None);
+    }
+
+    #[test]
+    fn bytes_step_by_1024() {
+        assert_eq!(scaled(1000.0, "B/s"), None);
+        assert_eq!(scaled(1023.0, "B/s"), None);
+        assert_eq!(scaled(1024.0, "B/s").as_deref(), Some("1.0 KiB/s"));
+        assert_eq!(scaled(12_582_912.0, "B/s").as_deref(), Some("12.0 MiB/s"));
+        assert_eq!(scaled(1000.0, "MB/s").as_deref(), Some("1.0 GB/s"));
+    }
+
+    #[test]
+    fn units_without_prefixes_pass_through() {
+        for unit in ["%", "°C", "°F", "RPM", "", "MHz/W"] {
+            assert_eq!(scaled(123_456.0, unit), None, "{}", unit);
+        }
+    }
+
+    #[test]
+    fn scaled_values_stay_between_1_and_the_step() {
+        for family in FAMILIES {
+            let largest = family.units[family.units.len() - 1];
+            for exponent in 0..40 {
+                let value = 1.7f64.powi(exponent);
+                let Some(prefix) = Prefix::pick(family.units[0], value) else {
+                    assert!(value < family.step);
+                    continue;
+                };
+                let shown = value / prefix.divisor;
+                assert!(shown >= 1.0, "{} {}", value, prefix.unit);
+                assert!(shown < family.step || prefix.unit == largest);
+                // One decimal below 100.
+                assert_eq!(prefix.decimals, usize::from(shown < 100.0));
+            }
+        }
+    }
+
+    #[test]
+    fn stops_at_the_largest_unit() {
+        assert_eq!(scaled(5e9, "W").as_deref(), Some("5000 MW"));
+    }
+}
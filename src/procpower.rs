@@ -0,0 +1,311 @@
+use std::{collections::HashMap, fs};
+
+use iced::{
+    widget::{Column, Text},
+    Element, Length,
+};
+
+use crate::Message;
+
+/// How many processes get an estimate.
+const SHOWN_PROCESSES: usize = 5;
+
+/// CPU time of one process, from `/proc/<pid>/stat`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessTimes {
+    pub pid: u32,
+    pub name: String,
+    /// Start time in clock ticks since boot; tells a reused pid apart.
+    pub start: u64,
+    /// User plus system time in clock ticks.
+    pub ticks: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribution {
+    pub pid: u32,
+    pub name: String,
+    pub watts: f64,
+}
+
+/// Package power split over the busiest processes by their share of the
+/// CPU time all processes used since the previous sample. RAPL only knows
+/// the package total, so this is an estimate: it ignores that cores at
+/// different frequencies or running different code draw differently.
+#[derive(Default)]
+pub struct ProcessPower {
+    /// Start time and CPU ticks of every process at the previous sample.
+    last: HashMap<u32, (u64, u64)>,
+    top: Vec<Attribution>,
+}
+
+impl ProcessPower {
+    /// Takes one sample of all processes and splits `package_watts` over
+    /// them. The first sample only primes the counters.
+    pub fn update(&mut self, package_watts: f64) {
+        let current = read_processes();
+        self.top = attribute(&self.last, &current, package_watts);
+        self.top.truncate(SHOWN_PROCESSES);
+        self.last = ticks_by_pid(current);
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let col = Column::new()
+            .spacing(5)
+            .width(Length::Fill)
+            .push(Text::new("Estimated power by process").size(24))
+            .push(
+                Text::new("Package power split by CPU time; RAPL can't measure processes").size(14),
+            );
+
+        self.top
+            .iter()
+            .fold(col, |col, process| {
+                col.push(Text::new(format!(
+                    "{} ({}): ~{:.1} W",
+                    process.name, process.pid, process.watts
+                )))
+            })
+            .into()
+    }
+}
+
+/// Processes of the configured names, to log when one starts or exits.
+#[derive(Default)]
+pub struct ProcessWatch {
+    names: Vec<String>,
+    /// The watched processes at the last update, by pid and start time;
+    /// unset until the first update, which only notes what runs already.
+    running: Option<HashMap<(u32, u64), String>>,
+}
+
+/// A watched process that started or exited since the last update.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProcessChange {
+    Started { pid: u32, name: String },
+    Exited { pid: u32, name: String },
+}
+
+impl ProcessWatch {
+    /// Watches processes named like one of `names` from the next update.
+    pub fn set_names(&mut self, names: &[String]) {
+        if self.names != names {
+            self.names = names.to_vec();
+            self.running = None;
+        }
+    }
+
+    /// Reads the processes and reports the watched ones that started or
+    /// exited since the last update; reads nothing while none are watched.
+    pub fn update(&mut self) -> Vec<ProcessChange> {
+        if self.names.is_empty() {
+            return Vec::new();
+        }
+        let running: HashMap<_, _> = read_processes()
+            .into_iter()
+            .filter(|process| self.names.contains(&process.name))
+            .map(|process| ((process.pid, process.start), process.name))
+            .collect();
+        match self.running.replace(running) {
+            Some(last) => changes(&last, self.running.as_ref().expect("just set")),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// The processes in `current` but not `last`, then the reverse, each by
+/// pid. A reused pid counts as an exit and a start.
+fn changes(
+    last: &HashMap<(u32, u64), String>,
+    current: &HashMap<(u32, u64), String>,
+) -> Vec<ProcessChange> {
+    let mut started: Vec<_> = current
+        .iter()
+        .filter(|(key, _)| !last.contains_key(key))
+        .map(|(&(pid, _), name)| ProcessChange::Started {
+            pid,
+            name: name.clone(),
+        })
+        .collect();
+    let mut exited: Vec<_> = last
+        .iter()
+        .filter(|(key, _)| !current.contains_key(key))
+        .map(|(&(pid, _), name)| ProcessChange::Exited {
+            pid,
+            name: name.clone(),
+        })
+        .collect();
+    let pid = |change: &ProcessChange| match change {
+        ProcessChange::Started { pid, .. } | ProcessChange::Exited { pid, .. } => *pid,
+    };
+    started.sort_by_key(pid);
+    exited.sort_by_key(pid);
+    exited.extend(started);
+    exited
+}
+
+/// Splits `package_watts` over the processes in `current` in proportion to
+/// the CPU time they used since `last`, busiest first. Processes that are
+/// new, or whose pid was reused by a process started since, have no delta
+/// yet and get nothing; processes that exited are simply gone.
+pub fn attribute(
+    last: &HashMap<u32, (u64, u64)>,
+    current: &[ProcessTimes],
+    package_watts: f64,
+) -> Vec<Attribution> {
+    let deltas = busiest(last, current);
+    let total: u64 = deltas.iter().map(|(_, delta)| delta).sum();
+
+    deltas
+        .into_iter()
+        .map(|(process, delta)| Attribution {
+            pid: process.pid,
+            name: process.name.clone(),
+            watts: package_watts * delta as f64 / total as f64,
+        })
+        .collect()
+}
+
+/// The processes in `current` that used CPU time since `last`, with the
+/// ticks they used, busiest first; new and reused pids are left out as in
+/// [`attribute`].
+pub fn busiest<'a>(
+    last: &HashMap<u32, (u64, u64)>,
+    current: &'a [ProcessTimes],
+) -> Vec<(&'a ProcessTimes, u64)> {
+    let mut deltas: Vec<_> = current
+        .iter()
+        .filter_map(|process| {
+            let &(start, ticks) = last.get(&process.pid)?;
+            let delta = process.ticks.checked_sub(ticks)?;
+            (start == process.start && delta > 0).then_some((process, delta))
+        })
+        .collect();
+    deltas.sort_by_key(|&(process, delta)| (std::cmp::Reverse(delta), process.pid));
+    deltas
+}
+
+/// Start time and CPU ticks of every process, by pid, to diff the next
+/// sample against.
+pub fn ticks_by_pid(processes: Vec<ProcessTimes>) -> HashMap<u32, (u64, u64)> {
+    processes
+        .into_iter()
+        .map(|process| (process.pid, (process.start, process.ticks)))
+        .collect()
+}
+
+pub fn read_processes() -> Vec<ProcessTimes> {
+    fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|pid| {
+            let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+            parse_stat(pid, &stat)
+        })
+        .collect()
+}
+
+/// The command name sits in parentheses and may itself contain spaces or
+/// parentheses, so fields are counted from the last `)`.
+fn parse_stat(pid: u32, stat: &str) -> Option<ProcessTimes> {
+    let (head, rest) = stat.rsplit_once(')')?;
+    let name = head.split_once('(')?.1.to_string();
+    // `rest` starts at field 3 (state); utime, stime and starttime are
+    // fields 14, 15 and 22.
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    let field = |n: usize| fields.get(n - 3)?.parse::<u64>().ok();
+    Some(ProcessTimes {
+        pid,
+        name,
+        start: field(22)?,
+        ticks: field(14)? + field(15)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, start: u64, ticks: u64) -> ProcessTimes {
+        ProcessTimes {
+            pid,
+            name: format!("p{}", pid),
+            start,
+            ticks,
+        }
+    }
+
+    #[test]
+    fn power_follows_cpu_time_share() {
+        let last = HashMap::from([(1, (10, 100)), (2, (20, 50)), (3, (30, 7))]);
+        let current = [process(1, 10, 130), process(2, 20, 60), process(3, 30, 7)];
+        let watts: Vec<_> = attribute(&last, &current, 40.0)
+            .into_iter()
+            .map(|a| (a.pid, a.watts))
+            .collect();
+        assert_eq!(watts, [(1, 30.0), (2, 10.0)]);
+    }
+
+    #[test]
+    fn new_reused_and_exited_pids_get_nothing() {
+        // 2 exited, 3 is a new process that reused an old pid, 4 is new.
+        let last = HashMap::from([(1, (10, 100)), (2, (20, 50)), (3, (30, 0))]);
+        let current = [process(1, 10, 110), process(3, 900, 5), process(4, 950, 20)];
+        let attributed = attribute(&last, &current, 25.0);
+        assert_eq!(attributed.len(), 1);
+        assert_eq!(attributed[0].pid, 1);
+        assert_eq!(attributed[0].watts, 25.0);
+    }
+
+    #[test]
+    fn watched_processes_are_diffed_by_pid_and_start() {
+        let processes = |list: &[(u32, u64)]| -> HashMap<_, _> {
+            list.iter()
+                .map(|&(pid, start)| ((pid, start), "make".to_string()))
+                .collect()
+        };
+        let last = processes(&[(1, 10), (2, 20), (3, 30)]);
+        // 2 exited, 3's pid was reused, 4 is new.
+        let current = processes(&[(1, 10), (3, 900), (4, 950)]);
+        let name = "make".to_string();
+        let change = |started: bool, pid| {
+            if started {
+                ProcessChange::Started {
+                    pid,
+                    name: name.clone(),
+                }
+            } else {
+                ProcessChange::Exited {
+                    pid,
+                    name: name.clone(),
+                }
+            }
+        };
+        assert_eq!(
+            changes(&last, &current),
+            [
+                change(false, 2),
+                change(false, 3),
+                change(true, 3),
+                change(true, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_names_with_spaces_and_parens() {
+        let stat = "42 (Web (Content) x) S 1 42 42 0 -1 4194560 100 0 0 0 \
+                    250 50 0 0 20 0 12 0 123456 1000 200";
+        assert_eq!(
+            parse_stat(42, stat),
+            Some(ProcessTimes {
+                pid: 42,
+                name: "Web (Content) x".to_string(),
+                start: 123456,
+                ticks: 300,
+            })
+        );
+    }
+}
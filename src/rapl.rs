@@ -0,0 +1,198 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+/// A longer gap between two readings means the machine was suspended (or
+/// the power thread starved); the energy counted over it isn't a draw.
+const MAX_GAP: Duration = Duration::from_secs(1);
+
+/// One reading before filtering, for `--debug-overlay`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RawReading {
+    /// Counter increments since the previous reading.
+    pub consumed: u32,
+    pub elapsed: Duration,
+    pub watts: f64,
+}
+
+/// Package power from successive readings of the RAPL energy counter. Every
+/// reading becomes a wattage over the time since the previous one; the
+/// median of the last three drops single-sample glitches and an EWMA evens
+/// out the rest. Readings that can't give a wattage are dropped instead of
+/// smoothed in.
+pub struct PowerFilter {
+    /// Joules per counter increment.
+    energy_unit: f64,
+    last: Option<(SystemTime, u32)>,
+    /// Up to three unsmoothed wattages, oldest first.
+    recent: VecDeque<f64>,
+    smoothed: Option<f64>,
+    /// Unset when the last reading gave no wattage.
+    last_raw: Option<RawReading>,
+}
+
+impl PowerFilter {
+    pub fn new(energy_unit: f64) -> Self {
+        Self {
+            energy_unit,
+            last: None,
+            recent: VecDeque::with_capacity(3),
+            smoothed: None,
+            last_raw: None,
+        }
+    }
+
+    /// Takes one counter reading and returns the wattage to publish, if
+    /// any. `alpha` is the weight of the newest median in the average.
+    ///
+    /// Nothing is published for the first reading, one spanning a suspend
+    /// or a clock jump, one where the 32-bit counter wrapped, or until three
+    /// wattages are in for the median.
+    pub fn observe(&mut self, time: SystemTime, counter: u32, alpha: f64) -> Option<f64> {
+        self.last_raw = None;
+        let (last_time, last_counter) = self.last.replace((time, counter))?;
+        let elapsed = time
+            .duration_since(last_time)
+            .ok()
+            .filter(|elapsed| !elapsed.is_zero() && *elapsed <= MAX_GAP)?;
+        let consumed = counter.checked_sub(last_counter)?;
+        let watts = consumed as f64 * self.energy_unit / elapsed.as_secs_f64();
+        self.last_raw = Some(RawReading {
+            consumed,
+            elapsed,
+            watts,
+        });
+
+        if self.recent.len() == 3 {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(watts);
+        if self.recent.len() < 3 {
+            return None;
+        }
+        let mut window: Vec<f64> = self.recent.iter().copied().collect();
+        window.sort_by(f64::total_cmp);
+        let median = window[1];
+
+        let smoothed = match self.smoothed {
+            Some(previous) => alpha * median + (1.0 - alpha) * previous,
+            None => median,
+        };
+        self.smoothed = Some(smoothed);
+        Some(smoothed)
+    }
+
+    /// The last reading as it came in, before the median and the average.
+    pub fn last_raw(&self) -> Option<RawReading> {
+        self.last_raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `(milliseconds, counter)` readings at one joule per increment
+    /// and returns what gets published, rounded to whole watts.
+    fn publish(readings: &[(u64, u32)], alpha: f64) -> Vec<Option<i64>> {
+        let mut filter = PowerFilter::new(1.0);
+        readings
+            .iter()
+            .map(|&(ms, counter)| {
+                let time = SystemTime::UNIX_EPOCH + Duration::from_millis(ms);
+                filter
+                    .observe(time, counter, alpha)
+                    .map(|w| w.round() as i64)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn steady_draw_is_published_after_the_median_fills() {
+        // 10 J per 100 ms is 100 W.
+        let readings: Vec<_> = (0..6).map(|i| (i * 100, i as u32 * 10)).collect();
+        assert_eq!(
+            publish(&readings, 0.5),
+            [None, None, None, Some(100), Some(100), Some(100)]
+        );
+    }
+
+    #[test]
+    fn single_sample_spike_is_filtered_out() {
+        let readings = [
+            (0, 0),
+            (100, 10),
+            (200, 20),
+            (300, 30),
+            (400, 130),
+            (500, 140),
+            (600, 150),
+        ];
+        assert_eq!(
+            publish(&readings, 1.0),
+            [None, None, None, Some(100), Some(100), Some(100), Some(100)]
+        );
+    }
+
+    #[test]
+    fn wrap_and_suspend_are_dropped() {
+        let readings = [
+            (0, u32::MAX - 35),
+            (100, u32::MAX - 25),
+            (200, u32::MAX - 15),
+            (300, u32::MAX - 5),
+            // Wrapped.
+            (400, 5),
+            (500, 15),
+            // Suspended for a minute, still counting a little.
+            (60_500, 515),
+            (60_600, 525),
+        ];
+        assert_eq!(
+            publish(&readings, 1.0),
+            [
+                None,
+                None,
+                None,
+                Some(100),
+                None,
+                Some(100),
+                None,
+                Some(100)
+            ]
+        );
+    }
+
+    #[test]
+    fn ewma_follows_a_step_gradually() {
+        let mut readings: Vec<_> = (0..4).map(|i| (i * 100, i as u32 * 10)).collect();
+        readings.extend((1..=3).map(|i| (300 + i * 100, 30 + i as u32 * 20)));
+        // The median reaches 200 W on the second fast reading, then the
+        // average closes half the gap per reading.
+        assert_eq!(
+            publish(&readings, 0.5),
+            [None, None, None, Some(100), Some(100), Some(150), Some(175)]
+        );
+    }
+
+    #[test]
+    fn raw_reading_is_kept_before_filtering() {
+        let mut filter = PowerFilter::new(0.5);
+        let at = |ms| SystemTime::UNIX_EPOCH + Duration::from_millis(ms);
+        assert_eq!(filter.observe(at(0), 100, 1.0), None);
+        assert_eq!(filter.last_raw(), None);
+        assert_eq!(filter.observe(at(250), 120, 1.0), None);
+        assert_eq!(
+            filter.last_raw(),
+            Some(RawReading {
+                consumed: 20,
+                elapsed: Duration::from_millis(250),
+                watts: 40.0,
+            })
+        );
+        // Wrapped.
+        filter.observe(at(500), 10, 1.0);
+        assert_eq!(filter.last_raw(), None);
+    }
+}
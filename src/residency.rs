@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+
+use iced::{
+    widget::canvas::{Cache, Frame, Geometry},
+    Element, Length, Size,
+};
+use plotters::{coord::Shift, prelude::*};
+use plotters_iced::{Chart, ChartWidget, DrawingBackend, Renderer};
+
+use crate::{colors::ChartPalette, heatmap::rainbow, Message};
+
+/// Share of the window each core spent in every frequency bin, as one
+/// stacked bar per core. Shows what an averaged frequency line hides, like
+/// cores idling at their minimum clock most of the time.
+#[derive(Default)]
+pub struct FrequencyResidency {
+    cache: Cache,
+    /// Lower edges of the bins in MHz, ascending.
+    bins: Vec<u64>,
+    /// Per core, the fraction of samples in every bin.
+    shares: Vec<Vec<f32>>,
+    palette: ChartPalette,
+}
+
+impl FrequencyResidency {
+    pub fn set_bins(&mut self, mut bins: Vec<u64>) {
+        bins.sort_unstable();
+        bins.dedup();
+        self.bins = bins;
+        // Until the next recompute, keep the bars drawable with the new
+        // bins rather than indexing past the old shares.
+        for shares in &mut self.shares {
+            shares.resize(self.bins.len(), 0.0);
+        }
+        self.cache.clear();
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        self.cache.clear();
+    }
+
+    /// Recomputes the bars from the per-core samples, newest first.
+    pub fn recompute(&mut self, cores: &[VecDeque<u64>]) {
+        self.shares = residency(cores, &self.bins);
+        self.cache.clear();
+    }
+
+    pub fn view(&self, height: f32) -> Element<'_, Message> {
+        ChartWidget::new(self)
+            .width(Length::Fill)
+            .height(Length::Fixed(height))
+            .into()
+    }
+
+    fn bin_label(&self, bin: usize) -> String {
+        match self.bins.get(bin + 1) {
+            Some(upper) => format!("{}–{} MHz", self.bins[bin], upper),
+            None => format!("{}+ MHz", self.bins[bin]),
+        }
+    }
+}
+
+/// Fraction of every core's samples falling into each bin. Bin `i` spans
+/// `bins[i]` up to the next edge; the last bin is open-ended and samples
+/// below the first edge count towards the first bin. Samples of 0 MHz,
+/// where the frequency couldn't be read, are left out.
+pub fn residency(cores: &[VecDeque<u64>], bins: &[u64]) -> Vec<Vec<f32>> {
+    cores
+        .iter()
+        .map(|samples| {
+            if bins.is_empty() {
+                return Vec::new();
+            }
+            let mut counts = vec![0usize; bins.len()];
+            for &mhz in samples.iter().filter(|&&mhz| mhz > 0) {
+                let bin = bins.partition_point(|&edge| edge <= mhz).saturating_sub(1);
+                counts[bin] += 1;
+            }
+            let total = counts.iter().sum::<usize>().max(1) as f32;
+            counts
+                .into_iter()
+                .map(|count| count as f32 / total)
+                .collect()
+        })
+        .collect()
+}
+
+impl Chart<Message> for FrequencyResidency {
+    type State = ();
+
+    #[inline]
+    fn draw<R: Renderer, F: Fn(&mut Frame)>(
+        &self,
+        renderer: &R,
+        bounds: Size,
+        draw_fn: F,
+    ) -> Geometry {
+        renderer.draw_cache(&self.cache, bounds, draw_fn)
+    }
+
+    fn draw_chart<DB: DrawingBackend>(&self, state: &Self::State, root: DrawingArea<DB, Shift>) {
+        self.build_chart(state, ChartBuilder::on(&root));
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut chart: ChartBuilder<DB>) {
+        let core_count = self.shares.len() as i32;
+        let gradient = rainbow();
+
+        let mut chart = chart
+            .x_label_area_size(30)
+            .y_label_area_size(60)
+            .margin(20)
+            .build_cartesian_2d(0f32..100f32, 0..core_count.max(1))
+            .expect("failed to build chart");
+
+        chart
+            .configure_mesh()
+            .disable_y_mesh()
+            .bold_line_style(self.palette.grid())
+            .light_line_style(self.palette.fine_grid())
+            .axis_style(ShapeStyle::from(self.palette.axis()).stroke_width(1))
+            .x_label_style(("sans-serif", 13).into_font().color(&self.palette.label()))
+            .x_label_formatter(&|percent| format!("{:.0}%", percent))
+            .y_labels(self.shares.len())
+            .y_label_style(("sans-serif", 13).into_font().color(&self.palette.label()))
+            .y_label_formatter(&|core| format!("CPU {}", core))
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        for bin in 0..self.bins.len() {
+            let fraction = bin as f32 / (self.bins.len() - 1).max(1) as f32;
+            let color = gradient.color_at(fraction);
+            chart
+                .draw_series(self.shares.iter().enumerate().map(|(core, shares)| {
+                    // Stacked left to right, lowest bin first.
+                    let start: f32 = shares[..bin].iter().sum::<f32>() * 100.0;
+                    let end = start + shares[bin] * 100.0;
+                    let core = core as i32;
+                    Rectangle::new([(start, core), (end, core + 1)], color.mix(0.85).filled())
+                }))
+                .expect("failed to draw residency")
+                .label(self.bin_label(bin))
+                .legend(move |(x, y)| {
+                    Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.filled())
+                });
+        }
+
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::LowerRight)
+            .background_style(self.palette.legend_background())
+            .border_style(self.palette.legend_border())
+            .label_font(
+                ("sans-serif", 13)
+                    .into_font()
+                    .color(&self.palette.annotation_text()),
+            )
+            .draw()
+            .expect("failed to draw legend");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_land_in_their_bins() {
+        let bins = [800, 1600, 3200];
+        let cores = [
+            VecDeque::from([400, 800, 1599, 1600, 3200, 4800, 900, 2000]),
+            VecDeque::new(),
+            VecDeque::from([0, 0, 1000, 2000]),
+        ];
+        assert_eq!(
+            residency(&cores, &bins),
+            [
+                vec![0.5, 0.25, 0.25],
+                vec![0.0, 0.0, 0.0],
+                vec![0.5, 0.5, 0.0]
+            ]
+        );
+    }
+
+    #[test]
+    fn changing_the_bins_keeps_the_bars_drawable() {
+        let mut residency = FrequencyResidency::default();
+        residency.set_bins(vec![800, 1600]);
+        residency.recompute(&[VecDeque::from([1000, 2000])]);
+        residency.set_bins(vec![800, 1600, 3200, 4800]);
+        assert_eq!(residency.shares, [vec![0.5, 0.5, 0.0, 0.0]]);
+        residency.set_bins(vec![800]);
+        assert_eq!(residency.shares, [vec![0.5]]);
+    }
+}
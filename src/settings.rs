@@ -0,0 +1,318 @@
+use std::{collections::HashMap, fmt};
+
+use iced::{
+    widget::{pick_list, slider, text_input, toggler, Button, Column, Container, Row, Text},
+    Alignment, Element, Length,
+};
+
+use crate::{
+    config::{ByteUnits, Config},
+    i18n, metrics,
+    sources::PowerLimits,
+    ChartId, Message,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window(pub u32);
+
+impl Window {
+    pub const CHOICES: &'static [Window] = &[
+        Window(30),
+        Window(60),
+        Window(120),
+        Window(300),
+        Window(600),
+        Window(900),
+    ];
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 < 60 {
+            write!(f, "{} s", self.0)
+        } else {
+            write!(f, "{} min", self.0 / 60)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SettingsMessage {
+    SampleInterval(u32),
+    Window(Window),
+    Theme(String),
+    ChartHeight(f32),
+    Columns(u32),
+    SmoothScroll(bool),
+    RedrawAllCharts(bool),
+    BinaryUnits(bool),
+    LowQuality(bool),
+    ManualSampling(bool),
+    Threshold(ChartId, String),
+    EwmaAlpha(ChartId, String),
+    PowerLimit(usize, String),
+    Reset,
+}
+
+/// Editing state of the settings panel. Text fields keep the raw input so a
+/// half-typed number isn't clobbered; valid values go straight to the config.
+pub struct SettingsPanel {
+    thresholds: HashMap<ChartId, String>,
+    alphas: HashMap<ChartId, String>,
+    /// PL1 and PL2 inputs, only with `--allow-control`.
+    power_limits: Option<[String; 2]>,
+}
+
+impl SettingsPanel {
+    pub fn new(config: &Config) -> Self {
+        let field = |value: Option<String>| value.unwrap_or_default();
+        Self {
+            thresholds: metrics::ids()
+                .map(|id| (id, field(config.chart(id).threshold.map(|t| t.to_string()))))
+                .collect(),
+            alphas: metrics::ids()
+                .map(|id| {
+                    (
+                        id,
+                        field(config.chart(id).ewma_alpha.map(|a| a.to_string())),
+                    )
+                })
+                .collect(),
+            power_limits: None,
+        }
+    }
+
+    /// Shows editable power limits, starting from the current ones.
+    pub fn edit_power_limits(&mut self, limits: PowerLimits) {
+        let field = |watts: Option<f64>| watts.map(|w| format!("{:.0}", w)).unwrap_or_default();
+        self.power_limits = Some([field(limits.pl1), field(limits.pl2)]);
+    }
+
+    pub fn update(&mut self, config: &mut Config, message: SettingsMessage) {
+        match message {
+            SettingsMessage::SampleInterval(ms) => config.sample_interval_ms = ms,
+            SettingsMessage::Window(window) => config.window_secs = window.0,
+            SettingsMessage::Theme(name) => config.theme = name,
+            SettingsMessage::ChartHeight(height) => config.chart_height = height,
+            SettingsMessage::Columns(columns) => config.columns = columns,
+            SettingsMessage::SmoothScroll(smooth) => config.smooth_scroll = smooth,
+            SettingsMessage::RedrawAllCharts(all) => config.redraw_all_charts = all,
+            SettingsMessage::LowQuality(low) => config.low_quality = low,
+            SettingsMessage::ManualSampling(manual) => config.manual_sampling = manual,
+            SettingsMessage::BinaryUnits(binary) => {
+                config.byte_units = if binary {
+                    ByteUnits::Binary
+                } else {
+                    ByteUnits::Si
+                };
+            }
+            SettingsMessage::Threshold(id, input) => {
+                match input.trim() {
+                    "" => config.chart_mut(id).threshold = None,
+                    value => {
+                        if let Ok(threshold) = value.parse::<f64>() {
+                            config.chart_mut(id).threshold = Some(threshold);
+                        }
+                    }
+                }
+                self.thresholds.insert(id, input);
+            }
+            SettingsMessage::EwmaAlpha(id, input) => {
+                match input.trim() {
+                    "" => config.chart_mut(id).ewma_alpha = None,
+                    value => {
+                        if let Ok(alpha) = value.parse::<f32>() {
+                            if alpha > 0.0 && alpha <= 1.0 {
+                                config.chart_mut(id).ewma_alpha = Some(alpha);
+                            }
+                        }
+                    }
+                }
+                self.alphas.insert(id, input);
+            }
+            SettingsMessage::PowerLimit(constraint, input) => {
+                if let Some(inputs) = &mut self.power_limits {
+                    inputs[constraint] = input;
+                }
+            }
+            SettingsMessage::Reset => {
+                *config = Config::default();
+                *self = SettingsPanel {
+                    power_limits: self.power_limits.take(),
+                    ..SettingsPanel::new(config)
+                };
+            }
+        }
+    }
+
+    pub fn view<'a>(&'a self, config: &'a Config) -> Element<'a, Message> {
+        let labeled = |label: &str, control: Element<'a, Message>| {
+            Row::new()
+                .spacing(15)
+                .align_items(Alignment::Center)
+                .push(Text::new(label.to_string()).width(Length::Fixed(160.0)))
+                .push(control)
+        };
+        let settings = |message| Message::Settings(message);
+
+        let mut panel = Column::new()
+            .spacing(12)
+            .max_width(640)
+            .push(Text::new(i18n::t("settings.title")).size(20))
+            .push(labeled(
+                &i18n::tf(
+                    "settings.sample_interval",
+                    &[("ms", &config.sample_interval_ms.to_string())],
+                ),
+                slider(100..=2000, config.sample_interval_ms, move |ms| {
+                    settings(SettingsMessage::SampleInterval(ms))
+                })
+                .step(100u32)
+                .into(),
+            ))
+            .push(labeled(
+                i18n::t("settings.window"),
+                pick_list(
+                    Window::CHOICES,
+                    Some(Window(config.window_secs)),
+                    move |window| settings(SettingsMessage::Window(window)),
+                )
+                .into(),
+            ))
+            .push(labeled(
+                i18n::t("settings.theme"),
+                pick_list(
+                    config.theme_names(),
+                    Some(config.theme.clone()),
+                    move |name| settings(SettingsMessage::Theme(name)),
+                )
+                .into(),
+            ))
+            .push(labeled(
+                &i18n::tf(
+                    "settings.chart_height",
+                    &[("px", &config.chart_height.to_string())],
+                ),
+                slider(150.0..=600.0, config.chart_height, move |height| {
+                    settings(SettingsMessage::ChartHeight(height))
+                })
+                .step(10.0)
+                .into(),
+            ))
+            .push(labeled(
+                &i18n::tf(
+                    "settings.columns",
+                    &[("columns", &config.columns.to_string())],
+                ),
+                slider(1..=4, config.columns, move |columns| {
+                    settings(SettingsMessage::Columns(columns))
+                })
+                .into(),
+            ))
+            .push(labeled(
+                i18n::t("settings.smooth_scroll"),
+                toggler(None, config.smooth_scroll, move |smooth| {
+                    settings(SettingsMessage::SmoothScroll(smooth))
+                })
+                .into(),
+            ))
+            .push(labeled(
+                i18n::t("settings.redraw_all_charts"),
+                toggler(None, config.redraw_all_charts, move |all| {
+                    settings(SettingsMessage::RedrawAllCharts(all))
+                })
+                .into(),
+            ))
+            .push(labeled(
+                i18n::t("settings.manual_sampling"),
+                toggler(None, config.manual_sampling, move |manual| {
+                    settings(SettingsMessage::ManualSampling(manual))
+                })
+                .into(),
+            ))
+            .push(labeled(
+                i18n::t("settings.low_quality"),
+                toggler(None, config.low_quality, move |low| {
+                    settings(SettingsMessage::LowQuality(low))
+                })
+                .into(),
+            ))
+            .push(labeled(
+                i18n::t("settings.binary_units"),
+                toggler(
+                    None,
+                    config.byte_units == ByteUnits::Binary,
+                    move |binary| settings(SettingsMessage::BinaryUnits(binary)),
+                )
+                .into(),
+            ))
+            .push(
+                Row::new()
+                    .spacing(15)
+                    .push(Text::new("").width(Length::Fixed(160.0)))
+                    .push(Text::new(i18n::t("settings.threshold")).width(Length::Fill))
+                    .push(Text::new(i18n::t("settings.ewma_alpha")).width(Length::Fill)),
+            );
+
+        for id in metrics::ids() {
+            panel = panel.push(labeled(
+                id.label(),
+                Row::new()
+                    .spacing(15)
+                    .push(
+                        text_input(i18n::t("settings.off"), &self.thresholds[&id])
+                            .on_input(move |input| settings(SettingsMessage::Threshold(id, input))),
+                    )
+                    .push(
+                        text_input(i18n::t("settings.off"), &self.alphas[&id])
+                            .on_input(move |input| settings(SettingsMessage::EwmaAlpha(id, input))),
+                    )
+                    .into(),
+            ));
+        }
+
+        if let Some(inputs) = &self.power_limits {
+            for (constraint, input) in inputs.iter().enumerate() {
+                let watts = input.trim().parse().ok();
+                panel = panel.push(labeled(
+                    &i18n::tf(
+                        "settings.power_limit",
+                        &[("number", &(constraint + 1).to_string())],
+                    ),
+                    Row::new()
+                        .spacing(15)
+                        .push(
+                            text_input(i18n::t("settings.unset"), input).on_input(move |input| {
+                                settings(SettingsMessage::PowerLimit(constraint, input))
+                            }),
+                        )
+                        .push(
+                            Button::new(Text::new(i18n::t("settings.apply"))).on_press_maybe(
+                                watts.map(|watts| Message::SetPowerLimit(constraint, watts)),
+                            ),
+                        )
+                        .into(),
+                ));
+            }
+        }
+
+        panel = panel.push(
+            Row::new()
+                .spacing(15)
+                .push(
+                    Button::new(Text::new(i18n::t("settings.reset")))
+                        .on_press(settings(SettingsMessage::Reset)),
+                )
+                .push(
+                    Button::new(Text::new(i18n::t("settings.close")))
+                        .on_press(Message::CloseSettings),
+                ),
+        );
+
+        Container::new(panel)
+            .width(Length::Fill)
+            .padding(20)
+            .center_x()
+            .into()
+    }
+}
@@ -0,0 +1,670 @@
+//! Where each metric is read from. x86 desktops have coretemp and the RAPL
+//! MSRs; ARM boards have thermal zones and, at most, an hwmon power monitor.
+//! GPUs are read through NVML or, for AMD cards, the amdgpu sysfs files;
+//! AMD temperatures come from the driver, see [`crate::amdgpu`].
+//! Sources are probed once at startup and a metric without one is hidden.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{bail, eyre, Result as EyreResult};
+#[cfg(feature = "sensors")]
+use lm_sensors::value::{Kind, Unit};
+#[cfg(feature = "sensors")]
+pub use lm_sensors::LMSensors;
+use nvml_wrapper::Nvml;
+use serde::{Deserialize, Serialize};
+use sysinfo::{Component, Components, System};
+
+/// What a chart samples. Every built-in metric has one in its descriptor,
+/// and a chart's `source` in the config binds it to another, e.g.
+/// `source = { core_freq = 0 }` on the frequency chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SampleSource {
+    /// Usage of all CPUs, or of the cgroup quota when scaled to it, in %.
+    GlobalCpu,
+    /// Usage of one logical CPU in %.
+    Core(usize),
+    /// Mean frequency of all CPUs in MHz.
+    AverageFreq,
+    /// Frequency of one logical CPU in MHz.
+    CoreFreq(usize),
+    /// Effective frequency of one package from APERF/MPERF in MHz, or the
+    /// mean frequency without access to those MSRs. Only built with the
+    /// `aperf` feature.
+    #[cfg(feature = "aperf")]
+    PackageFreq(usize),
+    /// The package sensor the temperature source found, in °C.
+    PackageTemp,
+    /// coretemp's `Core <n>` sensor in °C, numbered by physical core.
+    CoreTemp(usize),
+    /// Package power from RAPL or an hwmon monitor, in W.
+    PkgWatts,
+    /// Package power below the TDP, in %.
+    TdpHeadroom,
+    /// Change of the package temperature, in °C/s.
+    TempRate,
+    /// Instructions per cycle over all CPUs.
+    Ipc,
+    /// Memory in use on the GPU, in KiB.
+    GpuMemory,
+    /// Temperature of the hottest AMD GPU in °C.
+    GpuTemp,
+    /// Round-trip time of `--ping`'s echoes, in ms.
+    PingLatency,
+    /// Context switches per second, from `/proc/stat`.
+    ContextSwitches,
+    /// Interrupts per second, from `/proc/stat`.
+    Interrupts,
+}
+
+impl SampleSource {
+    /// Read from sysinfo's CPU list, which has to be refreshed first.
+    pub fn reads_cpus(self) -> bool {
+        match self {
+            SampleSource::GlobalCpu
+            | SampleSource::Core(_)
+            | SampleSource::AverageFreq
+            | SampleSource::CoreFreq(_) => true,
+            #[cfg(feature = "aperf")]
+            SampleSource::PackageFreq(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Stands in for libsensors' handle in builds without the `sensors`
+/// feature. It has no values, so every path that needs one is dead code
+/// and the sysinfo and thermal zone fallbacks are all that's left.
+#[cfg(not(feature = "sensors"))]
+pub enum LMSensors {}
+
+/// Initializes libsensors, which only works once per process: whoever
+/// needs the handle later has to be passed this one.
+#[cfg(feature = "sensors")]
+pub fn init_sensors() -> Result<LMSensors, String> {
+    lm_sensors::Initializer::default()
+        .initialize()
+        .map_err(|e| format!("initialization failed: {}", e))
+}
+
+#[cfg(not(feature = "sensors"))]
+pub fn init_sensors() -> Result<LMSensors, String> {
+    Err("monty was built without the sensors feature".to_string())
+}
+
+pub enum TempSource {
+    /// Package temperature of Intel's coretemp driver through libsensors.
+    Coretemp,
+    /// The same hwmon package sensor as sysinfo finds it, for systems
+    /// where libsensors isn't installed.
+    Component(Components),
+    /// `/sys/class/thermal/thermal_zone*` whose type names the CPU, for
+    /// systems without (a configured) libsensors.
+    ThermalZone(PathBuf),
+}
+
+impl TempSource {
+    pub fn probe(sensors: Option<&LMSensors>) -> Option<Self> {
+        if sensors.and_then(coretemp).is_some() {
+            return Some(TempSource::Coretemp);
+        }
+        let mut components = Components::new_with_refreshed_list();
+        if package_component(&mut components).is_some() {
+            return Some(TempSource::Component(components));
+        }
+        let mut zones: Vec<_> = fs::read_dir("/sys/class/thermal")
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with("thermal_zone"))
+            })
+            .filter_map(|path| {
+                let kind = fs::read_to_string(path.join("type")).ok()?;
+                Some((cpu_zone_rank(kind.trim())?, path))
+            })
+            .collect();
+        zones.sort();
+        zones
+            .into_iter()
+            .next()
+            .map(|(_, path)| TempSource::ThermalZone(path))
+    }
+
+    pub fn read(&mut self, sensors: Option<&LMSensors>) -> i32 {
+        let celsius = match self {
+            TempSource::Coretemp => sensors.and_then(coretemp),
+            TempSource::Component(components) => package_component(components).map(|package| {
+                package.refresh();
+                package.temperature().into()
+            }),
+            // Thermal zones always report millidegrees.
+            TempSource::ThermalZone(zone) => fs::read_to_string(zone.join("temp"))
+                .ok()
+                .and_then(|temp| temp.trim().parse::<f64>().ok())
+                .map(|millis| millis / 1000.0),
+        };
+        celsius.map_or(0, |c| c.round() as i32)
+    }
+}
+
+/// The CPU package sensor among sysinfo's components, e.g. coretemp's
+/// `Package id 0`.
+fn package_component(components: &mut Components) -> Option<&mut Component> {
+    components
+        .list_mut()
+        .iter_mut()
+        .find(|component| component.label().contains("Package"))
+        // Unreadable sensors report NaN.
+        .filter(|component| component.temperature().is_finite())
+}
+
+/// How well a zone type stands for the CPU package, best first: x86's
+/// package sensor, then the CPU and SoC zones of common ARM boards. `None`
+/// for zones that aren't the CPU at all, like `acpitz` or `iwlwifi`.
+fn cpu_zone_rank(kind: &str) -> Option<u8> {
+    match kind {
+        "x86_pkg_temp" => Some(0),
+        _ if kind.contains("cpu") => Some(1),
+        _ if kind.contains("soc") => Some(2),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "sensors")]
+fn coretemp(sensors: &LMSensors) -> Option<f64> {
+    sensors
+        .chip_iter(None)
+        .find(|ch| ch.name().is_ok_and(|n| n.contains("coretemp-isa-0000")))
+        .and_then(|ch| {
+            ch.feature_iter().find(|f| {
+                f.name()
+                    .is_some_and(|n| n.is_ok_and(|n| n.contains("temp1")))
+            })
+        })
+        .and_then(|ft| ft.sub_feature_by_kind(Kind::TemperatureInput).ok())
+        .and_then(|sf| sf.value().ok())
+        .filter(|v| v.unit() == Unit::Celcius)
+        .map(|v| normalize_celsius(v.raw_value()))
+}
+
+#[cfg(not(feature = "sensors"))]
+fn coretemp(sensors: &LMSensors) -> Option<f64> {
+    match *sensors {}
+}
+
+/// coretemp's sensor of physical core `core`.
+#[cfg(feature = "sensors")]
+pub fn core_temp(sensors: &LMSensors, core: usize) -> Option<f64> {
+    let label = format!("Core {}", core);
+    sensors
+        .chip_iter(None)
+        .find(|ch| ch.name().is_ok_and(|n| n.contains("coretemp")))?
+        .feature_iter()
+        .find(|f| f.label().is_ok_and(|l| l == label))?
+        .sub_feature_by_kind(Kind::TemperatureInput)
+        .ok()?
+        .value()
+        .ok()
+        .filter(|v| v.unit() == Unit::Celcius)
+        .map(|v| normalize_celsius(v.raw_value()))
+}
+
+#[cfg(not(feature = "sensors"))]
+pub fn core_temp(sensors: &LMSensors, _core: usize) -> Option<f64> {
+    match *sensors {}
+}
+
+/// libsensors scales temperatures to degrees, but some drivers hand us
+/// the raw sysfs millidegree reading instead. No CPU survives 1000 °C,
+/// so anything above that has to be millidegrees.
+#[cfg(feature = "sensors")]
+fn normalize_celsius(raw: f64) -> f64 {
+    if raw.abs() >= 1000.0 {
+        raw / 1000.0
+    } else {
+        raw
+    }
+}
+
+pub enum PowerSource {
+    /// RAPL package energy counter, sampled by the power thread.
+    Msr,
+    /// A board power monitor such as ina2xx/ina3221, by chip and feature.
+    #[cfg_attr(not(feature = "sensors"), allow(dead_code))]
+    Hwmon { chip: String, feature: String },
+}
+
+impl PowerSource {
+    /// The first power input any libsensors chip exposes.
+    #[cfg(feature = "sensors")]
+    pub fn probe_hwmon(sensors: &LMSensors) -> Option<Self> {
+        sensors.chip_iter(None).find_map(|chip| {
+            let feature = chip.feature_iter().find(|f| {
+                f.sub_feature_by_kind(Kind::PowerInput)
+                    .is_ok_and(|sf| sf.value().is_ok())
+            })?;
+            Some(PowerSource::Hwmon {
+                chip: chip.name().ok()?,
+                feature: feature.name()?.ok()?.to_string(),
+            })
+        })
+    }
+
+    #[cfg(not(feature = "sensors"))]
+    pub fn probe_hwmon(sensors: &LMSensors) -> Option<Self> {
+        match *sensors {}
+    }
+
+    /// Watts from an hwmon chip; the MSR source is read by its own thread.
+    #[cfg(feature = "sensors")]
+    pub fn read_hwmon(&self, sensors: Option<&LMSensors>) -> Option<f64> {
+        let PowerSource::Hwmon { chip, feature } = self else {
+            return None;
+        };
+        sensors?
+            .chip_iter(None)
+            .find(|ch| ch.name().is_ok_and(|n| n == *chip))?
+            .feature_iter()
+            .find(|f| f.name().is_some_and(|n| n.is_ok_and(|n| n == feature)))?
+            .sub_feature_by_kind(Kind::PowerInput)
+            .ok()?
+            .value()
+            .ok()
+            .map(|v| v.raw_value())
+    }
+
+    #[cfg(not(feature = "sensors"))]
+    pub fn read_hwmon(&self, sensors: Option<&LMSensors>) -> Option<f64> {
+        match *sensors? {}
+    }
+}
+
+/// The RAPL and power limit MSRs of the first CPU; needs the `msr` module
+/// and root or `CAP_SYS_RAWIO`.
+pub fn open_msr() -> io::Result<File> {
+    File::open(MSR_PATH)
+}
+
+pub const MSR_PATH: &str = "/dev/cpu/0/msr";
+
+/// Mean current frequency in MHz. sysinfo reads cpufreq where it can, but
+/// reports 0 on some ARM kernels, so fall back to cpufreq's sysfs directly.
+pub fn average_frequency(sys: &System) -> u64 {
+    let cpus = sys.cpus();
+    let sysinfo = cpus.iter().map(|c| c.frequency()).sum::<u64>() / cpus.len().max(1) as u64;
+    if sysinfo > 0 {
+        return sysinfo;
+    }
+
+    let mhz: Vec<u64> = (0..cpus.len()).filter_map(cpufreq_mhz).collect();
+    mhz.iter().sum::<u64>() / mhz.len().max(1) as u64
+}
+
+/// Current frequency of every logical CPU in MHz, with the same fallback
+/// as [`average_frequency`]; 0 where neither source knows.
+pub fn core_frequencies(sys: &System) -> impl Iterator<Item = u64> + '_ {
+    sys.cpus()
+        .iter()
+        .enumerate()
+        .map(|(cpu, c)| match c.frequency() {
+            0 => cpufreq_mhz(cpu).unwrap_or_default(),
+            mhz => mhz,
+        })
+}
+
+fn cpufreq_mhz(cpu: usize) -> Option<u64> {
+    let khz: u64 = fs::read_to_string(format!(
+        "/sys/devices/system/cpu/cpu{}/cpufreq/scaling_cur_freq",
+        cpu
+    ))
+    .ok()?
+    .trim()
+    .parse()
+    .ok()?;
+    Some(khz / 1000)
+}
+
+/// Highest frequency the first CPU can reach, turbo included, in MHz.
+pub fn max_frequency() -> Option<u64> {
+    let khz: u64 = fs::read_to_string("/sys/devices/system/cpu/cpu0/cpufreq/cpuinfo_max_freq")
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(khz / 1000)
+}
+
+/// The governor's bounds in MHz: the lowest `scaling_min_freq` and the
+/// highest `scaling_max_freq` of all cpufreq policies.
+pub fn scaling_limits() -> Option<(u64, u64)> {
+    let read = |policy: &Path, file| -> Option<u64> {
+        let khz: u64 = fs::read_to_string(policy.join(file))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()?;
+        Some(khz / 1000)
+    };
+    fs::read_dir("/sys/devices/system/cpu/cpufreq")
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter_map(|policy| {
+            Some((
+                read(&policy, "scaling_min_freq")?,
+                read(&policy, "scaling_max_freq")?,
+            ))
+        })
+        .reduce(|(min, max), (policy_min, policy_max)| (min.min(policy_min), max.max(policy_max)))
+}
+
+pub const INTEL_PSTATE_PATH: &str = "/sys/devices/system/cpu/intel_pstate";
+
+/// Who picks the P-states under the intel_pstate driver.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HwpMode {
+    /// The CPU itself, through hardware P-states.
+    Active,
+    /// The cpufreq governor; intel_pstate only applies its choice.
+    Passive,
+    /// Neither; the driver is loaded but not in use.
+    Off,
+}
+
+impl HwpMode {
+    fn parse(status: &str) -> Option<Self> {
+        match status.trim() {
+            "active" => Some(HwpMode::Active),
+            "passive" => Some(HwpMode::Passive),
+            "off" => Some(HwpMode::Off),
+            _ => None,
+        }
+    }
+
+    /// Who picks the frequency, for the frequency chart's title.
+    pub fn controller(self) -> Option<&'static str> {
+        match self {
+            HwpMode::Active => Some("HWP"),
+            HwpMode::Passive => Some("Governor"),
+            HwpMode::Off => None,
+        }
+    }
+}
+
+/// State of the intel_pstate driver, from its sysfs directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntelPstate {
+    pub mode: HwpMode,
+    pub no_turbo: bool,
+    /// Bounds on the performance level, in percent of the highest turbo
+    /// P-state.
+    pub min_perf_pct: Option<u32>,
+    pub max_perf_pct: Option<u32>,
+}
+
+impl IntelPstate {
+    /// `None` without the intel_pstate driver, e.g. on AMD or ARM.
+    pub fn read() -> Option<Self> {
+        let read = |file| fs::read_to_string(Path::new(INTEL_PSTATE_PATH).join(file)).ok();
+        let number = |file| read(file)?.trim().parse::<u32>().ok();
+        Some(Self {
+            mode: HwpMode::parse(&read("status")?)?,
+            no_turbo: number("no_turbo") == Some(1),
+            min_perf_pct: number("min_perf_pct"),
+            max_perf_pct: number("max_perf_pct"),
+        })
+    }
+
+    /// The performance bounds as labeled frequencies, given the highest
+    /// turbo frequency in MHz.
+    pub fn perf_limits(&self, max_mhz: u64) -> Vec<(f64, String)> {
+        [("min", self.min_perf_pct), ("max", self.max_perf_pct)]
+            .into_iter()
+            .filter_map(|(name, pct)| {
+                let pct = pct?;
+                Some((
+                    (max_mhz * u64::from(pct)) as f64 / 100.0,
+                    format!("{} perf {}%", name, pct),
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Limits above this are the "unlocked" all-ones patterns, not real ones.
+const MAX_PLAUSIBLE_WATTS: f64 = 1000.0;
+
+/// Package power limits in watts; `None` where unset or unlocked.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PowerLimits {
+    /// Long-term limit (PL1), roughly the TDP.
+    pub pl1: Option<f64>,
+    /// Short-term boost limit (PL2).
+    pub pl2: Option<f64>,
+}
+
+impl PowerLimits {
+    /// Reads powercap, which doesn't need root, or else the MSR directly.
+    pub fn read() -> Self {
+        Self::read_powercap().unwrap_or_else(Self::read_msr)
+    }
+
+    fn read_powercap() -> Option<Self> {
+        let read = |constraint| read_watts(&constraint_path(constraint, "power_limit_uw"));
+        let pl1 = read(0)?;
+        Some(Self::new(pl1, read(1).unwrap_or_default()))
+    }
+
+    /// Writes PL1 (constraint 0) or PL2 (constraint 1) through powercap.
+    /// Refuses anything below 1 W or above the platform's maximum.
+    pub fn write(constraint: usize, watts: f64) -> EyreResult<()> {
+        let max = read_watts(&constraint_path(constraint, "max_power_uw"))
+            .filter(|max| *max > 0.0)
+            .unwrap_or(MAX_PLAUSIBLE_WATTS);
+        if !(1.0..=max).contains(&watts) {
+            bail!("PL{} must be between 1 and {:.0} W", constraint + 1, max);
+        }
+
+        let path = constraint_path(constraint, "power_limit_uw");
+        fs::write(&path, format!("{}", (watts * 1e6).round() as u64)).map_err(|e| match e.kind() {
+            io::ErrorKind::PermissionDenied => eyre!(
+                "No permission to write {}; run monty as root or give your user \
+                     write access to it, e.g. with a udev rule",
+                path.display()
+            ),
+            io::ErrorKind::NotFound => eyre!("No powercap power limits on this system"),
+            _ => eyre!(e).wrap_err(format!("Failed to write {}", path.display())),
+        })
+    }
+
+    /// PL1 is bits 14:0 and PL2 bits 46:32 of MSR_PKG_POWER_LIMIT, in the
+    /// power unit of MSR_RAPL_POWER_UNIT.
+    fn read_msr() -> Self {
+        let Ok(mut msr_file) = open_msr() else {
+            return Self::default();
+        };
+        let mut read = |register| {
+            let mut msr_res = [0; 8];
+            msr_file.seek(SeekFrom::Start(register)).ok()?;
+            msr_file.read_exact(&mut msr_res).ok()?;
+            Some(u64::from_le_bytes(msr_res))
+        };
+        match (read(0x606), read(0x610)) {
+            (Some(units), Some(limit)) => {
+                let unit = 0.5f64.powi((units & 0xf) as i32);
+                Self::new(
+                    (limit & 0x7fff) as f64 * unit,
+                    (limit >> 32 & 0x7fff) as f64 * unit,
+                )
+            }
+            _ => Self::default(),
+        }
+    }
+
+    fn new(pl1: f64, pl2: f64) -> Self {
+        let plausible = |watts: f64| (watts > 0.0 && watts < MAX_PLAUSIBLE_WATTS).then_some(watts);
+        Self {
+            pl1: plausible(pl1),
+            pl2: plausible(pl2),
+        }
+    }
+}
+
+pub fn constraint_path(constraint: usize, file: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "/sys/class/powercap/intel-rapl:0/constraint_{}_{}",
+        constraint, file
+    ))
+}
+
+fn read_watts(path: &Path) -> Option<f64> {
+    let uw: u64 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    Some(uw as f64 / 1e6)
+}
+
+/// Video memory of the first GPU.
+pub enum GpuSource {
+    /// NVIDIA's management library, loaded at runtime; boxed as its
+    /// function table is large.
+    Nvml(Box<Nvml>),
+    /// The `device` directory of an amdgpu card under `/sys/class/drm`.
+    Amdgpu(PathBuf),
+}
+
+/// VRAM in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpuMemory {
+    pub used: u64,
+    pub total: u64,
+}
+
+impl GpuSource {
+    /// `None` without libnvidia-ml and an NVIDIA device, or an amdgpu card.
+    pub fn probe() -> Option<Self> {
+        if let Ok(nvml) = Nvml::init() {
+            if nvml.device_count().is_ok_and(|count| count > 0) {
+                return Some(GpuSource::Nvml(Box::new(nvml)));
+            }
+        }
+        let mut cards: Vec<_> = fs::read_dir("/sys/class/drm")
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| {
+                // Not the `card0-DP-1` connectors.
+                path.file_name()
+                    .and_then(|name| name.to_str()?.strip_prefix("card"))
+                    .is_some_and(|index| index.bytes().all(|b| b.is_ascii_digit()))
+            })
+            .map(|card| card.join("device"))
+            .filter(|device| read_amdgpu(device).is_some())
+            .collect();
+        cards.sort();
+        cards.into_iter().next().map(GpuSource::Amdgpu)
+    }
+
+    pub fn memory(&self) -> Option<GpuMemory> {
+        match self {
+            GpuSource::Nvml(nvml) => {
+                let info = nvml.device_by_index(0).ok()?.memory_info().ok()?;
+                Some(GpuMemory {
+                    used: info.used,
+                    total: info.total,
+                })
+            }
+            GpuSource::Amdgpu(device) => read_amdgpu(device),
+        }
+    }
+
+    /// What was found, for `--check`.
+    pub fn describe(&self) -> String {
+        match self {
+            GpuSource::Nvml(nvml) => nvml
+                .device_by_index(0)
+                .and_then(|device| device.name())
+                .map_or("NVML".to_string(), |name| format!("{} through NVML", name)),
+            GpuSource::Amdgpu(device) => format!("amdgpu {}", device.display()),
+        }
+    }
+}
+
+fn read_amdgpu(device: &Path) -> Option<GpuMemory> {
+    let read = |file| fs::read_to_string(device.join(file)).ok();
+    parse_vram(&read("mem_info_vram_used")?, &read("mem_info_vram_total")?)
+}
+
+/// The contents of amdgpu's `mem_info_vram_used` and `_total`; a card
+/// without dedicated VRAM reports a total of 0.
+fn parse_vram(used: &str, total: &str) -> Option<GpuMemory> {
+    let total = total.trim().parse().ok().filter(|total| *total > 0)?;
+    Some(GpuMemory {
+        used: used.trim().parse().ok()?,
+        total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_zone_beats_other_cpu_zones() {
+        let mut kinds = [
+            "acpitz",
+            "soc-thermal",
+            "cpu0-thermal",
+            "x86_pkg_temp",
+            "iwlwifi_1",
+        ];
+        kinds.sort_by_key(|kind| cpu_zone_rank(kind).unwrap_or(u8::MAX));
+        assert_eq!(kinds[..3], ["x86_pkg_temp", "cpu0-thermal", "soc-thermal"]);
+        assert_eq!(cpu_zone_rank("acpitz"), None);
+    }
+
+    #[test]
+    fn vram_is_read_in_bytes() {
+        assert_eq!(
+            parse_vram("1073741824\n", "8589934592\n"),
+            Some(GpuMemory {
+                used: 1 << 30,
+                total: 8 << 30
+            })
+        );
+        assert_eq!(parse_vram("0\n", "0\n"), None);
+        assert_eq!(parse_vram("", "8589934592"), None);
+    }
+
+    #[test]
+    fn intel_pstate_limits_are_shares_of_the_turbo_frequency() {
+        assert_eq!(HwpMode::parse("passive\n"), Some(HwpMode::Passive));
+        assert_eq!(HwpMode::parse("bogus"), None);
+
+        let pstate = IntelPstate {
+            mode: HwpMode::Active,
+            no_turbo: false,
+            min_perf_pct: Some(20),
+            max_perf_pct: Some(100),
+        };
+        assert_eq!(
+            pstate.perf_limits(4500),
+            [
+                (900.0, "min perf 20%".to_string()),
+                (4500.0, "max perf 100%".to_string())
+            ]
+        );
+        let unknown = IntelPstate {
+            min_perf_pct: None,
+            ..pstate
+        };
+        assert_eq!(unknown.perf_limits(4500).len(), 1);
+    }
+}
@@ -0,0 +1,139 @@
+use iced::{
+    widget::{Button, Column, Row, Text},
+    Alignment, Element, Length,
+};
+
+use crate::Message;
+
+/// Distribution of the readings in a chart's window, to characterize a
+/// workload without exporting the data.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StatReport {
+    /// Readings the report was computed from; everything else is zero
+    /// without any.
+    pub samples: usize,
+    pub p1: f64,
+    pub p5: f64,
+    pub p25: f64,
+    pub p50: f64,
+    pub p75: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+    /// Zero for a symmetric distribution, positive when a few readings
+    /// spike far above the rest.
+    pub skewness: f64,
+}
+
+impl StatReport {
+    pub fn new(values: impl Iterator<Item = f64>) -> Self {
+        let mut sorted: Vec<f64> = values.collect();
+        if sorted.is_empty() {
+            return Self::default();
+        }
+        sorted.sort_by(f64::total_cmp);
+
+        let n = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / n;
+        let moment = |k| sorted.iter().map(|v| (v - mean).powi(k)).sum::<f64>() / n;
+        let std_dev = moment(2).sqrt();
+        let percentile = |p: f64| {
+            // Interpolates between the two closest ranks.
+            let rank = p / 100.0 * (n - 1.0);
+            let (below, above) = (sorted[rank.floor() as usize], sorted[rank.ceil() as usize]);
+            below + (above - below) * rank.fract()
+        };
+
+        Self {
+            samples: sorted.len(),
+            p1: percentile(1.0),
+            p5: percentile(5.0),
+            p25: percentile(25.0),
+            p50: percentile(50.0),
+            p75: percentile(75.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+            mean,
+            std_dev,
+            skewness: if std_dev > 0.0 {
+                moment(3) / std_dev.powi(3)
+            } else {
+                0.0
+            },
+        }
+    }
+
+    /// The report as a table, with readings written by `format`.
+    pub fn view<'a>(&self, title: String, format: impl Fn(f64) -> String) -> Element<'a, Message> {
+        let cell = |text: String| Text::new(text).width(Length::Fixed(120.0));
+        let rows = [
+            ("1st percentile", format(self.p1)),
+            ("5th percentile", format(self.p5)),
+            ("25th percentile", format(self.p25)),
+            ("Median", format(self.p50)),
+            ("75th percentile", format(self.p75)),
+            ("95th percentile", format(self.p95)),
+            ("99th percentile", format(self.p99)),
+            ("Mean", format(self.mean)),
+            ("Std. deviation", format(self.std_dev)),
+            ("Skewness", format!("{:.2}", self.skewness)),
+        ];
+
+        let table = if self.samples == 0 {
+            Column::new().push(Text::new("No samples in the window yet"))
+        } else {
+            rows.into_iter()
+                .fold(Column::new().spacing(4), |table, (name, value)| {
+                    table.push(Row::new().push(cell(name.into())).push(cell(value)))
+                })
+        };
+
+        Column::new()
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .push(Text::new(title).size(20))
+            .push(Text::new(format!("{} samples", self.samples)).size(14))
+            .push(table)
+            .push(Button::new(Text::new("Close")).on_press(Message::CloseStats))
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_interpolate_between_ranks() {
+        let report = StatReport::new((0..=100).rev().map(f64::from));
+        assert_eq!(report.samples, 101);
+        assert_eq!(
+            [report.p1, report.p5, report.p25, report.p50, report.p75, report.p95, report.p99],
+            [1.0, 5.0, 25.0, 50.0, 75.0, 95.0, 99.0]
+        );
+        assert_eq!(report.mean, 50.0);
+        assert_eq!(report.skewness, 0.0);
+
+        let report = StatReport::new([10.0, 20.0].into_iter());
+        assert_eq!(report.p50, 15.0);
+        assert_eq!(report.p25, 12.5);
+    }
+
+    #[test]
+    fn spikes_skew_to_the_right() {
+        let report = StatReport::new([2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0].into_iter());
+        assert_eq!(report.mean, 5.0);
+        assert_eq!(report.std_dev, 2.0);
+        assert!(report.skewness > 0.0);
+        assert!(StatReport::new([1.0, 9.0, 9.0, 9.0].into_iter()).skewness < 0.0);
+    }
+
+    #[test]
+    fn flat_or_empty_windows_have_no_spread() {
+        let report = StatReport::new([3.0; 5].into_iter());
+        assert_eq!((report.p1, report.p99), (3.0, 3.0));
+        assert_eq!((report.std_dev, report.skewness), (0.0, 0.0));
+        assert_eq!(StatReport::new(std::iter::empty()), StatReport::default());
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    hint,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use iced::{
+    widget::{Button, Column, Row, Text},
+    Alignment, Element, Length,
+};
+
+use crate::Message;
+
+/// Busy threads at every step of a ramp: doubling from one, ending at the
+/// core count.
+pub fn thread_counts(cores: usize) -> Vec<usize> {
+    let cores = cores.max(1);
+    let mut counts: Vec<usize> = std::iter::successors(Some(1usize), |n| Some(n * 2))
+        .take_while(|&n| n < cores)
+        .collect();
+    counts.push(cores);
+    counts
+}
+
+/// Readings averaged over one step of the ramp.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StepStats {
+    pub threads: usize,
+    watts: Mean,
+    temp: Mean,
+    freq: Mean,
+}
+
+impl StepStats {
+    fn new(threads: usize) -> Self {
+        Self {
+            threads,
+            ..Self::default()
+        }
+    }
+
+    pub fn watts(&self) -> Option<f64> {
+        self.watts.get()
+    }
+
+    pub fn temp(&self) -> Option<f64> {
+        self.temp.get()
+    }
+
+    pub fn freq(&self) -> Option<f64> {
+        self.freq.get()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Mean {
+    sum: f64,
+    count: u32,
+}
+
+impl Mean {
+    fn add(&mut self, value: Option<i32>) {
+        if let Some(value) = value {
+            self.sum += value as f64;
+            self.count += 1;
+        }
+    }
+
+    fn get(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+pub enum Progress {
+    Running,
+    /// The ramp moved on to this many threads.
+    Stepped(usize),
+    Finished(Vec<StepStats>),
+}
+
+/// Loads the CPU with more and more spinning threads, recording power,
+/// temperature and frequency at every thread count: a workload scaling
+/// profile.
+pub struct StressTest {
+    schedule: Vec<usize>,
+    step_length: Duration,
+    step_started: Instant,
+    stats: Vec<StepStats>,
+    stop: Arc<AtomicBool>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl StressTest {
+    /// Starts the first step; the whole ramp takes about `duration`.
+    pub fn start(cores: usize, duration: Duration) -> Self {
+        let schedule = thread_counts(cores);
+        let mut test = Self {
+            step_length: duration / schedule.len() as u32,
+            step_started: Instant::now(),
+            stats: vec![StepStats::new(schedule[0])],
+            schedule,
+            stop: Arc::new(AtomicBool::new(false)),
+            workers: Vec::new(),
+        };
+        test.spawn_workers();
+        test
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Records one set of readings and moves the ramp on when a step is
+    /// over. Unavailable metrics are `None`.
+    pub fn sample(&mut self, watts: Option<i32>, temp: Option<i32>, freq: Option<i32>) -> Progress {
+        let step = self.stats.last_mut().expect("a step is always running");
+        step.watts.add(watts);
+        step.temp.add(temp);
+        step.freq.add(freq);
+
+        if self.step_started.elapsed() < self.step_length {
+            return Progress::Running;
+        }
+        let Some(&threads) = self.schedule.get(self.stats.len()) else {
+            self.stop_workers();
+            return Progress::Finished(std::mem::take(&mut self.stats));
+        };
+        self.stats.push(StepStats::new(threads));
+        self.step_started = Instant::now();
+        self.spawn_workers();
+        Progress::Stepped(threads)
+    }
+
+    fn spawn_workers(&mut self) {
+        let target = self.stats.last().map_or(0, |step| step.threads);
+        while self.workers.len() < target {
+            let stop = self.stop.clone();
+            self.workers.push(thread::spawn(move || {
+                let mut x = 1u64;
+                while !stop.load(Ordering::Relaxed) {
+                    for _ in 0..10_000 {
+                        x = hint::black_box(x.wrapping_mul(6364136223846793005).wrapping_add(1));
+                    }
+                }
+            }));
+        }
+    }
+
+    fn stop_workers(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for StressTest {
+    fn drop(&mut self) {
+        self.stop_workers();
+    }
+}
+
+/// Mean readings per thread count, as a table.
+pub fn summary_view(stats: &[StepStats]) -> Element<'_, Message> {
+    let cell = |text: String| Text::new(text).width(Length::Fixed(120.0));
+    let mean = |value: Option<f64>, unit: &str| {
+        value.map_or("–".to_string(), |v| format!("{:.0}{}", v, unit))
+    };
+    let header = Row::new()
+        .push(cell("Threads".into()))
+        .push(cell("Power".into()))
+        .push(cell("Temperature".into()))
+        .push(cell("Frequency".into()));
+
+    let table = stats
+        .iter()
+        .fold(Column::new().spacing(4).push(header), |table, step| {
+            table.push(
+                Row::new()
+                    .push(cell(step.threads.to_string()))
+                    .push(cell(mean(step.watts(), " W")))
+                    .push(cell(mean(step.temp(), " °C")))
+                    .push(cell(mean(step.freq(), " MHz"))),
+            )
+        });
+
+    Column::new()
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .push(Text::new("Stress ramp results").size(20))
+        .push(table)
+        .push(Button::new(Text::new("Close")).on_press(Message::DismissStressSummary))
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramp_doubles_up_to_the_core_count() {
+        assert_eq!(thread_counts(1), [1]);
+        assert_eq!(thread_counts(6), [1, 2, 4, 6]);
+        assert_eq!(thread_counts(8), [1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn steps_average_their_readings() {
+        let mut test = StressTest::start(2, Duration::ZERO);
+        assert_eq!(test.threads(), 1);
+        assert!(matches!(
+            test.sample(Some(10), Some(50), None),
+            Progress::Stepped(2)
+        ));
+        assert_eq!(test.threads(), 2);
+        test.step_length = Duration::from_secs(3600);
+        test.sample(Some(20), Some(60), None);
+        test.sample(Some(30), None, None);
+        test.step_length = Duration::ZERO;
+        let Progress::Finished(stats) = test.sample(None, Some(70), None) else {
+            panic!("the ramp should be over");
+        };
+        assert_eq!(test.threads(), 0);
+        assert_eq!(
+            stats
+                .iter()
+                .map(|step| (step.threads, step.watts(), step.temp(), step.freq()))
+                .collect::<Vec<_>>(),
+            [
+                (1, Some(10.0), Some(50.0), None),
+                (2, Some(25.0), Some(65.0), None)
+            ]
+        );
+    }
+}
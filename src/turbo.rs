@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use iced::{
+    widget::canvas::{Cache, Frame, Geometry},
+    Element, Length, Size,
+};
+use plotters::{coord::Shift, prelude::*};
+use plotters_iced::{Chart, ChartWidget, DrawingBackend, Renderer};
+
+use crate::{colors::ChartPalette, Message};
+
+/// Width of a bin in MHz; turbo bins step by 100 MHz.
+const BIN_MHZ: u64 = 100;
+
+/// How often the frequency chart showed each clock over the session, one
+/// bar per 100 MHz bin: the share of time spent at 4.8 GHz rather than
+/// 3.8 GHz, which the time series can't answer.
+pub struct TurboHistogram {
+    cache: Cache,
+    /// Samples by bin, in MHz.
+    counts: HashMap<u64, u64>,
+    palette: ChartPalette,
+}
+
+impl TurboHistogram {
+    pub fn new() -> Self {
+        Self {
+            cache: Cache::new(),
+            counts: HashMap::new(),
+            palette: ChartPalette::default(),
+        }
+    }
+
+    pub fn record(&mut self, mhz: u64) {
+        *self.counts.entry(bin(mhz)).or_default() += 1;
+        self.cache.clear();
+    }
+
+    pub fn set_palette(&mut self, palette: ChartPalette) {
+        self.palette = palette;
+        self.cache.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.clear();
+        self.cache.clear();
+    }
+
+    pub fn view(&self, height: f32) -> Element<'_, Message> {
+        ChartWidget::new(self)
+            .width(Length::Fill)
+            .height(Length::Fixed(height))
+            .into()
+    }
+
+    /// Every bin seen and its percentage of the samples, lowest first.
+    fn shares(&self) -> Vec<(u64, f64)> {
+        let total = self.counts.values().sum::<u64>().max(1) as f64;
+        let mut shares: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(&mhz, &count)| (mhz, 100.0 * count as f64 / total))
+            .collect();
+        shares.sort_by_key(|(mhz, _)| *mhz);
+        shares
+    }
+}
+
+/// `mhz` rounded to the nearest bin.
+fn bin(mhz: u64) -> u64 {
+    (mhz + BIN_MHZ / 2) / BIN_MHZ * BIN_MHZ
+}
+
+impl Chart<Message> for TurboHistogram {
+    type State = ();
+
+    #[inline]
+    fn draw<R: Renderer, F: Fn(&mut Frame)>(
+        &self,
+        renderer: &R,
+        bounds: Size,
+        draw_fn: F,
+    ) -> Geometry {
+        renderer.draw_cache(&self.cache, bounds, draw_fn)
+    }
+
+    fn draw_chart<DB: DrawingBackend>(&self, state: &Self::State, root: DrawingArea<DB, Shift>) {
+        self.build_chart(state, ChartBuilder::on(&root));
+    }
+
+    fn build_chart<DB: DrawingBackend>(&self, _state: &Self::State, mut chart: ChartBuilder<DB>) {
+        let shares = self.shares();
+        let highest = shares.iter().map(|(_, share)| *share).fold(0.0, f64::max);
+        let bars = shares.len().max(1) as i32;
+
+        let mut chart = chart
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .margin(20)
+            .build_cartesian_2d(
+                (0..bars).into_segmented(),
+                0.0..(highest * 1.1).clamp(1.0, 100.0),
+            )
+            .expect("failed to build chart");
+
+        let label_style = ("sans-serif", 13).into_font().color(&self.palette.label());
+        chart
+            .configure_mesh()
+            .disable_x_mesh()
+            .bold_line_style(self.palette.grid())
+            .light_line_style(self.palette.fine_grid())
+            .axis_style(ShapeStyle::from(self.palette.axis()).stroke_width(1))
+            .x_labels(shares.len())
+            .x_label_style(label_style.clone())
+            .x_label_formatter(&|bar| match bar {
+                SegmentValue::CenterOf(bar) => usize::try_from(*bar)
+                    .ok()
+                    .and_then(|bar| shares.get(bar))
+                    .map(|(mhz, _)| format!("{:.1} GHz", *mhz as f64 / 1000.0))
+                    .unwrap_or_default(),
+                _ => String::new(),
+            })
+            .y_labels(5)
+            .y_label_style(label_style)
+            .y_label_formatter(&|share| format!("{:.0}%", share))
+            .draw()
+            .expect("failed to draw chart mesh");
+
+        let color = crate::colors::rgb(self.palette.primary);
+        chart
+            .draw_series(shares.iter().enumerate().map(|(index, (_, share))| {
+                let index = index as i32;
+                let mut bar = Rectangle::new(
+                    [
+                        (SegmentValue::Exact(index), 0.0),
+                        (SegmentValue::Exact(index + 1), *share),
+                    ],
+                    color.filled(),
+                );
+                bar.set_margin(0, 0, 4, 4);
+                bar
+            }))
+            .expect("failed to draw chart data");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samples_count_towards_the_nearest_bin() {
+        assert_eq!(bin(3849), 3800);
+        assert_eq!(bin(3850), 3900);
+        assert_eq!(bin(4012), 4000);
+
+        let mut histogram = TurboHistogram::new();
+        for mhz in [4390, 4410, 4420, 3810] {
+            histogram.record(mhz);
+        }
+        assert_eq!(histogram.shares(), [(3800, 25.0), (4400, 75.0)]);
+    }
+}
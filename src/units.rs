@@ -0,0 +1,148 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Units a chart can be shown in; see [`UnitConverter`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Unit {
+    #[serde(rename = "MHz")]
+    Megahertz,
+    #[serde(rename = "GHz")]
+    Gigahertz,
+    #[serde(rename = "mW")]
+    Milliwatt,
+    #[serde(rename = "W")]
+    Watt,
+    #[serde(rename = "°C")]
+    Celsius,
+    #[serde(rename = "°F")]
+    Fahrenheit,
+    #[serde(rename = "%")]
+    Percent,
+    #[serde(rename = "RPM")]
+    Rpm,
+    #[serde(rename = "MB/s")]
+    MegabytesPerSecond,
+}
+
+impl Unit {
+    const ALL: &'static [Unit] = &[
+        Unit::Megahertz,
+        Unit::Gigahertz,
+        Unit::Milliwatt,
+        Unit::Watt,
+        Unit::Celsius,
+        Unit::Fahrenheit,
+        Unit::Percent,
+        Unit::Rpm,
+        Unit::MegabytesPerSecond,
+    ];
+
+    /// The unit a chart's suffix (like `" MHz"`) stands for.
+    pub fn from_suffix(suffix: &str) -> Option<Unit> {
+        Unit::ALL
+            .iter()
+            .copied()
+            .find(|unit| unit.to_string() == suffix.trim())
+    }
+
+    /// Appended to values, with the space the chart units use.
+    pub fn suffix(self) -> String {
+        match self {
+            Unit::Percent => self.to_string(),
+            _ => format!(" {}", self),
+        }
+    }
+
+    /// Units measuring the same thing, this one included.
+    pub fn alternatives(self) -> &'static [Unit] {
+        match self {
+            Unit::Megahertz | Unit::Gigahertz => &[Unit::Megahertz, Unit::Gigahertz],
+            Unit::Milliwatt | Unit::Watt => &[Unit::Milliwatt, Unit::Watt],
+            Unit::Celsius | Unit::Fahrenheit => &[Unit::Celsius, Unit::Fahrenheit],
+            Unit::Percent => &[Unit::Percent],
+            Unit::Rpm => &[Unit::Rpm],
+            Unit::MegabytesPerSecond => &[Unit::MegabytesPerSecond],
+        }
+    }
+
+    /// Decimals that keep a value in this unit meaningful, e.g. `3.40 GHz`.
+    pub fn decimals(self) -> usize {
+        match self {
+            Unit::Gigahertz => 2,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Unit::Megahertz => "MHz",
+            Unit::Gigahertz => "GHz",
+            Unit::Milliwatt => "mW",
+            Unit::Watt => "W",
+            Unit::Celsius => "°C",
+            Unit::Fahrenheit => "°F",
+            Unit::Percent => "%",
+            Unit::Rpm => "RPM",
+            Unit::MegabytesPerSecond => "MB/s",
+        })
+    }
+}
+
+/// Shows values sampled in one unit in another. Purely cosmetic: charts
+/// keep their samples in the unit they were read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitConverter {
+    pub from: Unit,
+    pub to: Unit,
+}
+
+impl UnitConverter {
+    /// `None` unless both units measure the same thing.
+    pub fn new(from: Unit, to: Unit) -> Option<Self> {
+        from.alternatives()
+            .contains(&to)
+            .then_some(UnitConverter { from, to })
+    }
+
+    pub fn convert(self, value: f64) -> f64 {
+        match (self.from, self.to) {
+            (Unit::Megahertz, Unit::Gigahertz) | (Unit::Milliwatt, Unit::Watt) => value / 1000.0,
+            (Unit::Gigahertz, Unit::Megahertz) | (Unit::Watt, Unit::Milliwatt) => value * 1000.0,
+            (Unit::Celsius, Unit::Fahrenheit) => value * 9.0 / 5.0 + 32.0,
+            (Unit::Fahrenheit, Unit::Celsius) => (value - 32.0) * 5.0 / 9.0,
+            _ => value,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_within_a_dimension_only() {
+        let convert = |from, to, value| UnitConverter::new(from, to).map(|c| c.convert(value));
+        assert_eq!(convert(Unit::Megahertz, Unit::Gigahertz, 3400.0), Some(3.4));
+        assert_eq!(convert(Unit::Watt, Unit::Milliwatt, 2.5), Some(2500.0));
+        assert_eq!(convert(Unit::Celsius, Unit::Fahrenheit, 100.0), Some(212.0));
+        assert_eq!(convert(Unit::Fahrenheit, Unit::Celsius, 32.0), Some(0.0));
+        assert_eq!(convert(Unit::Celsius, Unit::Celsius, 55.0), Some(55.0));
+        assert_eq!(convert(Unit::Celsius, Unit::Watt, 55.0), None);
+    }
+
+    #[test]
+    fn suffixes_round_trip() {
+        for &unit in Unit::ALL {
+            assert_eq!(Unit::from_suffix(&unit.suffix()), Some(unit));
+            assert_eq!(
+                toml::Value::try_from(unit).unwrap(),
+                toml::Value::String(unit.to_string())
+            );
+        }
+        assert_eq!(Unit::from_suffix(" MHz"), Some(Unit::Megahertz));
+        assert_eq!(Unit::from_suffix(" °C/s"), None);
+    }
+}
@@ -0,0 +1,114 @@
+use std::{fs, path::Path};
+
+/// What monty is running on, as far as the hardware sensors are concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Environment {
+    BareMetal,
+    VirtualMachine,
+    Container,
+}
+
+impl Environment {
+    /// MSRs and coretemp belong to the host; reading them from a guest
+    /// either fails or reports nothing meaningful.
+    pub fn is_virtualized(self) -> bool {
+        self != Environment::BareMetal
+    }
+}
+
+/// Probes the running system, in the spirit of `systemd-detect-virt`.
+pub fn detect() -> Environment {
+    detect_from(
+        &fs::read_to_string("/proc/cpuinfo").unwrap_or_default(),
+        Path::new("/.dockerenv").exists() || Path::new("/run/.containerenv").exists(),
+        &fs::read_to_string("/proc/1/cgroup").unwrap_or_default(),
+    )
+}
+
+/// Classifies the environment from already probed data: the contents of
+/// `/proc/cpuinfo`, whether a container marker file exists and the contents
+/// of `/proc/1/cgroup`.
+pub fn detect_from(cpuinfo: &str, container_marker: bool, init_cgroup: &str) -> Environment {
+    const CONTAINER_CGROUPS: &[&str] = &["docker", "kubepods", "lxc", "containerd", "libpod"];
+
+    let in_container = container_marker
+        || init_cgroup
+            .lines()
+            .any(|line| CONTAINER_CGROUPS.iter().any(|name| line.contains(name)));
+    if in_container {
+        return Environment::Container;
+    }
+
+    let hypervisor = cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("flags"))
+        .any(|line| line.split_whitespace().any(|flag| flag == "hypervisor"));
+    if hypervisor {
+        Environment::VirtualMachine
+    } else {
+        Environment::BareMetal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOST_CPUINFO: &str = "processor\t: 0\nflags\t\t: fpu vme de pse tsc msr\n";
+    const GUEST_CPUINFO: &str = "processor\t: 0\nflags\t\t: fpu vme msr hypervisor lahf_lm\n";
+    const HOST_CGROUP: &str = "0::/init.scope\n";
+
+    #[test]
+    fn plain_host_is_bare_metal() {
+        assert_eq!(
+            detect_from(HOST_CPUINFO, false, HOST_CGROUP),
+            Environment::BareMetal
+        );
+    }
+
+    #[test]
+    fn hypervisor_flag_means_vm() {
+        assert_eq!(
+            detect_from(GUEST_CPUINFO, false, HOST_CGROUP),
+            Environment::VirtualMachine
+        );
+    }
+
+    #[test]
+    fn hypervisor_in_another_field_is_ignored() {
+        let cpuinfo = "model name\t: hypervisor test cpu\nflags\t\t: fpu msr\n";
+        assert_eq!(
+            detect_from(cpuinfo, false, HOST_CGROUP),
+            Environment::BareMetal
+        );
+    }
+
+    #[test]
+    fn marker_file_means_container() {
+        assert_eq!(
+            detect_from(HOST_CPUINFO, true, HOST_CGROUP),
+            Environment::Container
+        );
+    }
+
+    #[test]
+    fn container_cgroup_means_container() {
+        let cgroup = "12:cpu,cpuacct:/docker/3f2a9c\n0::/system.slice/docker-3f2a9c.scope\n";
+        assert_eq!(
+            detect_from(HOST_CPUINFO, false, cgroup),
+            Environment::Container
+        );
+        assert_eq!(
+            detect_from(HOST_CPUINFO, false, "0::/kubepods/burstable/pod1\n"),
+            Environment::Container
+        );
+    }
+
+    #[test]
+    fn container_wins_over_vm() {
+        assert_eq!(
+            detect_from(GUEST_CPUINFO, true, HOST_CGROUP),
+            Environment::Container
+        );
+    }
+}
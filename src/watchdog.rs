@@ -0,0 +1,90 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Weak,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often the watcher thread looks at the flag; an update still running
+/// at two checks in a row took at least this long.
+const CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+struct State {
+    /// Set while an update runs.
+    busy: AtomicBool,
+    /// Updates finished so far, to tell a long update from a new one.
+    finished: AtomicU64,
+    /// Set by the watcher once it caught an update hanging.
+    timed_out: AtomicBool,
+}
+
+/// Notices when sampling blocks the UI thread, e.g. on a slow sensor read.
+/// The UI can't react while it is blocked, so the watcher thread logs to
+/// stderr right away and the UI catches up once the update returns.
+pub struct Watchdog {
+    state: Arc<State>,
+}
+
+impl Watchdog {
+    pub fn start() -> Self {
+        let state = Arc::new(State::default());
+        let watched = Arc::downgrade(&state);
+        thread::spawn(move || watch(watched));
+        Self { state }
+    }
+
+    /// Runs `update` with the busy flag set.
+    pub fn watch<T>(&self, update: impl FnOnce() -> T) -> T {
+        self.state.busy.store(true, Ordering::SeqCst);
+        let result = update();
+        self.state.busy.store(false, Ordering::SeqCst);
+        self.state.finished.fetch_add(1, Ordering::SeqCst);
+        result
+    }
+
+    /// Whether an update hung since the last call.
+    pub fn take_timeout(&self) -> bool {
+        self.state.timed_out.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// Checks the flag until the watchdog is dropped.
+fn watch(state: Weak<State>) {
+    let mut busy_since = None;
+    loop {
+        thread::sleep(CHECK_INTERVAL);
+        let Some(state) = state.upgrade() else {
+            return;
+        };
+        let busy = state.busy.load(Ordering::SeqCst);
+        let finished = state.finished.load(Ordering::SeqCst);
+        if hung(busy_since, busy, finished) && !state.timed_out.swap(true, Ordering::SeqCst) {
+            eprintln!("WARNING: update took >{}s", CHECK_INTERVAL.as_secs());
+        }
+        busy_since = busy.then_some(finished);
+    }
+}
+
+/// Whether the update that was running at the previous check, when
+/// `busy_since` updates had finished, is still running now.
+fn hung(busy_since: Option<u64>, busy: bool, finished: u64) -> bool {
+    busy && busy_since == Some(finished)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_an_update_spanning_two_checks_hangs() {
+        // Idle at the previous check, so this update may have just started.
+        assert!(!hung(None, true, 5));
+        // Busy at both checks, but updates finished in between.
+        assert!(!hung(Some(5), true, 6));
+        assert!(!hung(Some(5), false, 5));
+        assert!(hung(Some(5), true, 5));
+    }
+}